@@ -0,0 +1,99 @@
+use crate::vector::{Vec3, Point3, Float, Transform};
+use crate::ray::Ray;
+
+use std::ops::Range;
+
+/// An axis-aligned box that some shape fits entirely inside of.
+/// 
+/// Checking a [Ray] against one of these is way cheaper than checking it against
+/// the shape, so they're good for ruling out shapes that the ray can't possibly hit.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug)]
+pub struct BoundingBox {
+	pub min: Point3,
+	pub max: Point3,
+}
+impl BoundingBox {
+	/// A box with nothing in it. Growing it by anything gives you that thing.
+	pub const EMPTY: Self = Self { min: Point3::all(Float::INFINITY), max: Point3::all(Float::NEG_INFINITY) };
+	
+	/// Make a box with `a` and `b` as opposite corners.
+	pub fn new(a: Point3, b: Point3) -> Self {
+		Self { min: a.min(b), max: a.max(b) }
+	}
+	
+	/// Make a box around `center`, stretching out `half_size` in each direction.
+	pub fn around(center: Point3, half_size: Vec3) -> Self {
+		Self::new(center - half_size, center + half_size)
+	}
+	
+	pub fn union(self, other: Self) -> Self {
+		Self { min: self.min.min(other.min), max: self.max.max(other.max) }
+	}
+	
+	/// Get the box where `self` and `other` overlap.
+	pub fn intersection(self, other: Self) -> Self {
+		Self { min: self.min.max(other.min), max: self.max.min(other.max) }
+	}
+	
+	pub fn grow(self, p: Point3) -> Self {
+		Self { min: self.min.min(p), max: self.max.max(p) }
+	}
+	
+	pub fn size(self) -> Vec3 {
+		self.max - self.min
+	}
+	
+	pub fn centroid(self) -> Point3 {
+		(self.min + self.max) / 2.0
+	}
+	
+	pub fn surface_area(self) -> Float {
+		let Vec3 { x, y, z } = self.size().max(Vec3::ZERO);
+		2.0 * (x * y + y * z + z * x)
+	}
+	
+	/// Get the box around this box after it's been transformed.
+	pub fn transform(self, transform: &Transform) -> Self {
+		let mut result = Self::EMPTY;
+		for i in 0..8 {
+			let corner = Point3::new(
+				if i & 1 == 0 { self.min.x } else { self.max.x },
+				if i & 2 == 0 { self.min.y } else { self.max.y },
+				if i & 4 == 0 { self.min.z } else { self.max.z },
+			);
+			result = result.grow(transform.point(corner));
+		}
+		result
+	}
+	
+	/// Check if a [Ray] passes through the box within `t_range`, and if so,
+	/// how far along the ray it enters.
+	pub fn ray_hits(&self, t_range: Range<Float>, ray: Ray) -> Option<Float> {
+		self.ray_span(t_range, ray).map(|(t_enter, _)| t_enter)
+	}
+	
+	/// Check if a [Ray] passes through the box within `t_range`, and if so,
+	/// how far along the ray it enters and exits.
+	pub fn ray_span(&self, t_range: Range<Float>, ray: Ray) -> Option<(Float, Float)> {
+		let (mut t_min, mut t_max) = (t_range.start, t_range.end);
+		
+		let min: [Float; 3] = self.min.into();
+		let max: [Float; 3] = self.max.into();
+		let position: [Float; 3] = ray.position.into();
+		let direction: [Float; 3] = ray.direction.into();
+		
+		for axis in 0..3 {
+			let inv_d = direction[axis].recip();
+			let mut t0 = (min[axis] - position[axis]) * inv_d;
+			let mut t1 = (max[axis] - position[axis]) * inv_d;
+			if inv_d < 0.0 { std::mem::swap(&mut t0, &mut t1); }
+			
+			// Written this way around so NaNs (from 0 * infinity) get ignored.
+			t_min = t0.max(t_min);
+			t_max = t1.min(t_max);
+			if t_max < t_min { return None; }
+		}
+		
+		Some((t_min, t_max))
+	}
+}
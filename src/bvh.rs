@@ -0,0 +1,174 @@
+use crate::vector::Float;
+use crate::ray::Ray;
+use crate::bounding_box::BoundingBox;
+
+use std::ops::Range;
+
+/// A bounding volume hierarchy: a tree of [BoundingBox]es around a list of items.
+/// 
+/// It doesn't own the items, it just remembers them by their index. That way the
+/// same code can sort out [World](crate::world::World) objects and solids in a
+/// [Group](crate::solid::group::Group).
+#[derive(Clone, Debug, Default)]
+pub struct Bvh {
+	nodes: Vec<Node>,
+	/// Item indices, in the order the leaves refer to them.
+	items: Vec<usize>,
+	/// Items without a bounding box, which have to be checked every time.
+	unbounded: Vec<usize>,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Node {
+	bounds: BoundingBox,
+	/// For leaves, where their items start in `items`. For branches, the index
+	/// of the second child. (The first child always comes right after its parent.)
+	offset: usize,
+	/// How many items a leaf has. Zero for branches.
+	count: usize,
+	/// Which axis a branch was split along.
+	axis: usize,
+}
+
+const MAX_LEAF_SIZE: usize = 4;
+const MAX_DEPTH: usize = 48;
+const SAH_BUCKETS: usize = 12;
+
+impl Bvh {
+	/// Build a tree from each item's bounding box. Items are referred to by their
+	/// position in `boxes`, and items with no box are always checked.
+	pub fn build(boxes: &[Option<BoundingBox>]) -> Self {
+		let (bounded, unbounded): (Vec<usize>, Vec<usize>) =
+			(0..boxes.len()).partition(|&i| boxes[i].is_some());
+		let boxes: Vec<BoundingBox> = boxes.iter().map(|b| b.unwrap_or(BoundingBox::EMPTY)).collect();
+		
+		let mut bvh = Self { nodes: Vec::new(), items: bounded, unbounded };
+		if !bvh.items.is_empty() {
+			bvh.build_node(&boxes, 0, bvh.items.len(), 0);
+		}
+		bvh
+	}
+	
+	/// The box around everything, unless something in here is unbounded.
+	pub fn bounds(&self) -> Option<BoundingBox> {
+		if !self.unbounded.is_empty() { return None; }
+		Some(self.nodes.first().map_or(BoundingBox::EMPTY, |n| n.bounds))
+	}
+	
+	fn build_node(&mut self, boxes: &[BoundingBox], start: usize, end: usize, depth: usize) -> usize {
+		let index = self.nodes.len();
+		let items = &self.items[start..end];
+		
+		let bounds = items.iter().fold(BoundingBox::EMPTY, |b, &i| b.union(boxes[i]));
+		self.nodes.push(Node { bounds, offset: start, count: end - start, axis: 0 });
+		
+		if end - start <= MAX_LEAF_SIZE || depth >= MAX_DEPTH { return index; }
+		
+		// Split along the axis where the centers are most spread out.
+		let centroid_bounds = items.iter().fold(BoundingBox::EMPTY, |b, &i| b.grow(boxes[i].centroid()));
+		let extent: [Float; 3] = centroid_bounds.size().into();
+		let axis = (0..3).max_by(|&a, &b| extent[a].total_cmp(&extent[b])).unwrap_or(0);
+		
+		// Everything's in the same spot, so there's no good way to split them up.
+		if extent[axis] <= 0.0 { return index; }
+		
+		let low = <[Float; 3]>::from(centroid_bounds.min)[axis];
+		let bucket_of = |i: usize| {
+			let c = <[Float; 3]>::from(boxes[i].centroid())[axis];
+			let b = ((c - low) / extent[axis] * SAH_BUCKETS as Float) as usize;
+			b.min(SAH_BUCKETS - 1)
+		};
+		
+		// Sort items into buckets, then use the surface area heuristic to
+		// pick which bucket boundary makes for the cheapest split.
+		let mut buckets = [(0usize, BoundingBox::EMPTY); SAH_BUCKETS];
+		for &i in items {
+			let bucket = &mut buckets[bucket_of(i)];
+			*bucket = (bucket.0 + 1, bucket.1.union(boxes[i]));
+		}
+		
+		let cost_of = |side: &[(usize, BoundingBox)]| {
+			let (count, b) = side.iter().fold((0, BoundingBox::EMPTY), |(n, b), &(m, c)| (n + m, b.union(c)));
+			count as Float * b.surface_area()
+		};
+		let split = (1..SAH_BUCKETS)
+			.map(|split| {
+				let (left, right) = buckets.split_at(split);
+				(cost_of(left) + cost_of(right), split)
+			})
+			.min_by(|a, b| a.0.total_cmp(&b.0))
+			.map_or(SAH_BUCKETS / 2, |(_, split)| split);
+		
+		// The first and last buckets are never empty, so neither side ends up empty either.
+		let mut mid = start;
+		for k in start..end {
+			if bucket_of(self.items[k]) < split {
+				self.items.swap(k, mid);
+				mid += 1;
+			}
+		}
+		
+		self.build_node(boxes, start, mid, depth + 1);
+		let second = self.build_node(boxes, mid, end, depth + 1);
+		
+		self.nodes[index] = Node { bounds, offset: second, count: 0, axis };
+		index
+	}
+	
+	/// Shoot a [Ray] through the tree. Every item whose box gets hit is passed to `hit_item`
+	/// along with the range to look in, which should return what it hit and how far along the ray.
+	/// 
+	/// Returns the closest thing any item hit.
+	pub fn ray_hits<T>(&self, t_range: Range<Float>, ray: Ray, mut hit_item: impl FnMut(usize, Range<Float>) -> Option<(T, Float)>) -> Option<T> {
+		let mut closest = None;
+		let mut t_end = t_range.end;
+		
+		let mut check = |items: &[usize], t_end: &mut Float| {
+			for &item in items {
+				if let Some((hit, t)) = hit_item(item, t_range.start..*t_end) {
+					*t_end = t;
+					closest = Some(hit);
+				}
+			}
+		};
+		
+		check(&self.unbounded, &mut t_end);
+		
+		if !self.nodes.is_empty() {
+			let negative: [bool; 3] = <[Float; 3]>::from(ray.direction).map(|d| d < 0.0);
+			
+			// No recursion, just a stack of nodes we still need to look at.
+			let mut stack = [0usize; MAX_DEPTH + 1];
+			let mut stack_len = 0;
+			let mut node_index = 0;
+			
+			loop {
+				let node = &self.nodes[node_index];
+				
+				if node.bounds.ray_hits(t_range.start..t_end, ray).is_some() {
+					if node.count > 0 {
+						check(&self.items[node.offset..node.offset + node.count], &mut t_end);
+					} else {
+						// Look at the nearer child first, so the farther one's more likely to get skipped.
+						let (near, far) = if negative[node.axis] {
+							(node.offset, node_index + 1)
+						} else {
+							(node_index + 1, node.offset)
+						};
+						
+						stack[stack_len] = far;
+						stack_len += 1;
+						node_index = near;
+						continue;
+					}
+				}
+				
+				if stack_len == 0 { break; }
+				stack_len -= 1;
+				node_index = stack[stack_len];
+			}
+		}
+		
+		closest
+	}
+}
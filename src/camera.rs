@@ -8,6 +8,7 @@ pub struct CameraLens {
 	pub focus_dist: Float,
 }
 impl CameraLens {
+	#[allow(dead_code)]
 	pub const fn new(aperture: Float, focus_dist: Float) -> Self {
 		Self { aperture, focus_dist }
 	}
@@ -27,6 +28,8 @@ pub struct Camera {
 	pub vertical: Vec3,
 	pub u: Vec3, pub v: Vec3, pub w: Vec3,
 	pub camera_lens: Option<CameraLens>,
+	/// When the shutter opens and closes. Each ray gets a random time in between.
+	pub shutter: (Float, Float),
 }
 impl Camera {
 	/// Sets up the camera. FOV should be in degrees.
@@ -44,43 +47,90 @@ impl Camera {
 			let vertical = lens.focus_dist * viewport_height * v;
 			let lower_left_corner = origin - horizontal/2.0 - vertical/2.0 - lens.focus_dist * w;
 			
-			Self { origin, lower_left_corner, horizontal, vertical, u, v, w, camera_lens }
+			Self { origin, lower_left_corner, horizontal, vertical, u, v, w, camera_lens, shutter: (0.0, 0.0) }
 		} else {
 			let horizontal = viewport_width * u;
 			let vertical = viewport_height * v;
 			let lower_left_corner = origin - horizontal/2.0 - vertical/2.0 - w;
 			
-			Self { origin, lower_left_corner, horizontal, vertical, u, v, w, camera_lens }
+			Self { origin, lower_left_corner, horizontal, vertical, u, v, w, camera_lens, shutter: (0.0, 0.0) }
 		}
 	}
 	
+	/// Keep the shutter open from `open` to `close`, so anything moving in between gets blurred.
+	pub const fn with_shutter(mut self, open: Float, close: Float) -> Self {
+		self.shutter = (open, close); self
+	}
+	
 	/// Gets a normalized ray from the Camera's view.
 	pub fn get_ray(&self, st: Vec2) -> Ray {
 		let (s, t) = st.into();
+		let time = Float::mul_add(util::random_float(), self.shutter.1 - self.shutter.0, self.shutter.0);
+		let origin = self.lens_point();
 		
-		if let Some(lens) = self.camera_lens {
-			let rd = lens.aperture / 2.0 * util::random_in_unit_disk();
-			let offset = self.u * rd.x + self.v * rd.y;
-			
-			Ray::new(
-				self.origin + offset, 
-				(
-					self.lower_left_corner +
-					s * self.horizontal +
-					t * self.vertical
-					- self.origin - offset
-				).normalize()
-			)
-		} else {
-			Ray::new(
-				self.origin, 
-				(
-					self.lower_left_corner +
-					s * self.horizontal +
-					t * self.vertical
-					- self.origin
-				).normalize()
-			)
-		}
+		Ray::new(
+			origin,
+			(
+				self.lower_left_corner +
+				s * self.horizontal +
+				t * self.vertical
+				- origin
+			).normalize(),
+			time
+		)
+	}
+	
+	/// Pick somewhere on the lens for a ray to start from.
+	fn lens_point(&self) -> Point3 {
+		let Some(lens) = self.camera_lens else { return self.origin };
+		let rd = lens.aperture / 2.0 * util::random_in_unit_disk();
+		self.origin + self.u * rd.x + self.v * rd.y
+	}
+	
+	/// How far in front of the camera `lower_left_corner` is.
+	fn focus_dist(&self) -> Float {
+		self.camera_lens.map_or(1.0, |lens| lens.focus_dist)
+	}
+	
+	/// How big the picture would be, one unit in front of the camera.
+	fn film_area(&self) -> Float {
+		self.horizontal.magnitude() * self.vertical.magnitude() / self.focus_dist().powi(2)
+	}
+	
+	/// Find where a ray starting at `origin` on the lens and going in `direction`
+	/// shows up in the picture, if it does. This is the opposite of [Camera::get_ray].
+	pub fn get_uv(&self, origin: Point3, direction: Vec3) -> Option<Vec2> {
+		let cos_theta = -direction.normalize().dot(self.w);
+		if cos_theta <= 0.0 { return None; }
+		
+		// Follow it out to where it's in focus.
+		let distance = (self.focus_dist() + (origin - self.origin).dot(self.w)) / cos_theta;
+		let offset = origin + direction.normalize() * distance - self.lower_left_corner;
+		let s = offset.dot(self.horizontal) / self.horizontal.squared_magnitude();
+		let t = offset.dot(self.vertical) / self.vertical.squared_magnitude();
+		
+		((0.0..1.0).contains(&s) && (0.0..1.0).contains(&t)).then(|| Vec2::new(s, t))
+	}
+	
+	/// The pdf of [Camera::get_ray] going in `direction` (with an evenly random `st`),
+	/// per unit solid angle.
+	pub fn direction_pdf(&self, origin: Point3, direction: Vec3) -> Float {
+		if self.get_uv(origin, direction).is_none() { return 0.0; }
+		let cos_theta = -direction.normalize().dot(self.w);
+		(self.film_area() * cos_theta.powi(3)).recip()
+	}
+	
+	/// Pick a spot on the lens to see `point` from, for when light's been followed out
+	/// from a light and needs to get to the camera.
+	/// 
+	/// Returns where in the picture it shows up, the spot on the lens, and how much
+	/// any light coming from `point` counts for in the picture (ignoring what's in the way).
+	pub fn sample_towards(&self, point: Point3) -> Option<(Vec2, Point3, Float)> {
+		let origin = self.lens_point();
+		let to_point = point - origin;
+		let uv = self.get_uv(origin, to_point)?;
+		
+		let cos_theta = -to_point.normalize().dot(self.w);
+		Some((uv, origin, (self.film_area() * cos_theta.powi(3) * to_point.squared_magnitude()).recip()))
 	}
 }
@@ -0,0 +1,335 @@
+use crate::vector::{Vec3, Color, Float};
+use crate::util;
+
+use std::io::{self, BufRead, BufReader};
+use std::fs::File;
+use std::path::Path;
+
+/// Whatever's infinitely far away, lighting up everything from every direction.
+/// Rays that don't hit anything end up here.
+pub trait Environment {
+	/// How much light comes from `direction`, which doesn't have to be normalized.
+	fn radiance(&self, direction: Vec3) -> Color;
+	
+	/// Whether [Environment::sample] ever picks anything, so it's worth looking for light that way.
+	fn is_sampleable(&self) -> bool { false }
+	
+	/// Pick a direction to look for light in, favoring the brightest ones.
+	/// 
+	/// Returns the (normalized) direction, how much light comes from there, and the pdf
+	/// of picking it, per unit solid angle. Environments that are too even to be worth
+	/// sampling like this can leave it as `None`, and only get found by rays that escape.
+	fn sample(&self) -> Option<(Vec3, Color, Float)> { None }
+	
+	/// The pdf of [Environment::sample] picking `direction`.
+	fn pdf(&self, _direction: Vec3) -> Float { 0.0 }
+}
+
+/// A plain gradient from `bottom` (straight down) to `top` (straight up).
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug)]
+pub struct Gradient {
+	pub bottom: Color,
+	pub top: Color,
+}
+impl Environment for Gradient {
+	fn radiance(&self, direction: Vec3) -> Color {
+		let t = 0.5 * (direction.normalize().y + 1.0);
+		Color::lerp(self.bottom, self.top, t)
+	}
+}
+
+/// A panoramic HDR photo wrapped around the whole scene, in the equirectangular
+/// (latitude-longitude) layout. The top row is straight up, and the middle of the image is +X.
+/// 
+/// Directions get picked in proportion to how bright they are, so small bright
+/// things (like the sun, or studio lights) light the scene without much noise.
+pub struct EnvironmentMap {
+	pub width: usize,
+	pub height: usize,
+	pixels: Vec<Color>,
+	/// Spins the map around the Y axis, in degrees.
+	pub rotation: Float,
+	/// Scales how bright the whole thing is.
+	pub intensity: Float,
+	/// Picks a row, and then a column in that row.
+	rows: Distribution,
+	columns: Vec<Distribution>,
+}
+impl EnvironmentMap {
+	/// Make a map out of `pixels`, going left to right and then top to bottom.
+	pub fn new(width: usize, height: usize, pixels: Vec<Color>) -> Self {
+		assert_eq!(pixels.len(), width * height, "the map should have width * height pixels");
+		
+		// Rows near the poles get squished into less of the sphere, so they're less likely.
+		let columns: Vec<_> = pixels.chunks(width).enumerate().map(|(y, row)| {
+			let sin_theta = Float::sin((y as Float + 0.5) / height as Float * std::f64::consts::PI as Float);
+			Distribution::new(row.iter().map(|c| c.luminance().max(0.0) * sin_theta))
+		}).collect();
+		let rows = Distribution::new(columns.iter().map(Distribution::total));
+		
+		Self { width, height, pixels, rotation: 0.0, intensity: 1.0, rows, columns }
+	}
+	
+	/// Load a Radiance `.hdr` file.
+	pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+		let (width, height, pixels) = read_hdr(BufReader::new(File::open(path)?))?;
+		Ok(Self::new(width, height, pixels))
+	}
+	
+	pub fn rotation(mut self, degrees: Float) -> Self {
+		self.rotation = degrees; self
+	}
+	
+	pub fn intensity(mut self, intensity: Float) -> Self {
+		self.intensity = intensity; self
+	}
+	
+	fn pixel(&self, x: usize, y: usize) -> Color {
+		self.pixels[y * self.width + x] * self.intensity
+	}
+	
+	/// Spin a direction around the Y axis.
+	fn spin(v: Vec3, degrees: Float) -> Vec3 {
+		let (sin, cos) = degrees.to_radians().sin_cos();
+		Vec3::new(cos * v.x + sin * v.z, v.y, cos * v.z - sin * v.x)
+	}
+	
+	/// Where a direction lands on the map, with both coordinates in [0, 1].
+	fn uv(&self, direction: Vec3) -> (Float, Float) {
+		let pi = std::f64::consts::PI as Float;
+		let d = Self::spin(direction.normalize(), -self.rotation);
+		let u = 0.5 + Float::atan2(d.z, d.x) / (2.0 * pi);
+		let v = Float::acos(d.y.clamp(-1.0, 1.0)) / pi;
+		(u, v)
+	}
+	
+	fn direction(&self, u: Float, v: Float) -> Vec3 {
+		let pi = std::f64::consts::PI as Float;
+		let (sin_phi, cos_phi) = ((u - 0.5) * 2.0 * pi).sin_cos();
+		let (sin_theta, cos_theta) = (v * pi).sin_cos();
+		Self::spin(Vec3::new(sin_theta * cos_phi, cos_theta, sin_theta * sin_phi), self.rotation)
+	}
+	
+	fn texel(&self, (u, v): (Float, Float)) -> (usize, usize) {
+		let x = ((u * self.width as Float) as usize).min(self.width - 1);
+		let y = ((v * self.height as Float) as usize).min(self.height - 1);
+		(x, y)
+	}
+	
+	/// Turn the pdf of picking a point on the map into one per unit solid angle.
+	/// The whole map covers 2π by π radians, and it gets squished by `sin(theta)`.
+	fn solid_angle_pdf(&self, map_pdf: Float, v: Float) -> Float {
+		let pi = std::f64::consts::PI as Float;
+		let sin_theta = Float::sin(v * pi);
+		if sin_theta <= 0.0 { return 0.0; }
+		map_pdf / (2.0 * pi * pi * sin_theta)
+	}
+}
+impl Environment for EnvironmentMap {
+	fn radiance(&self, direction: Vec3) -> Color {
+		let (x, y) = self.texel(self.uv(direction));
+		self.pixel(x, y)
+	}
+	
+	fn is_sampleable(&self) -> bool {
+		self.rows.total() > 0.0
+	}
+	
+	fn sample(&self) -> Option<(Vec3, Color, Float)> {
+		let (y, row_pdf) = self.rows.sample()?;
+		let (x, column_pdf) = self.columns[y].sample()?;
+		
+		// Somewhere inside of the pixel.
+		let u = (x as Float + util::random_float()) / self.width as Float;
+		let v = (y as Float + util::random_float()) / self.height as Float;
+		
+		let map_pdf = row_pdf * column_pdf * (self.width * self.height) as Float;
+		let pdf = self.solid_angle_pdf(map_pdf, v);
+		if pdf == 0.0 { return None; }
+		
+		Some((self.direction(u, v), self.pixel(x, y), pdf))
+	}
+	
+	fn pdf(&self, direction: Vec3) -> Float {
+		let (u, v) = self.uv(direction);
+		let (x, y) = self.texel((u, v));
+		let map_pdf = self.rows.pdf(y) * self.columns[y].pdf(x) * (self.width * self.height) as Float;
+		self.solid_angle_pdf(map_pdf, v)
+	}
+}
+
+/// Picks indices at random, in proportion to how much each one weighs.
+struct Distribution {
+	/// The running total of the weights, up to and including each index.
+	cdf: Vec<Float>,
+}
+impl Distribution {
+	fn new(weights: impl Iterator<Item = Float>) -> Self {
+		let cdf = weights.scan(0.0, |sum, w| { *sum += w; Some(*sum) }).collect();
+		Self { cdf }
+	}
+	
+	fn total(&self) -> Float {
+		self.cdf.last().copied().unwrap_or(0.0)
+	}
+	
+	/// Returns the index, and the chance of having picked it.
+	/// If everything weighs nothing, there's nothing to pick.
+	fn sample(&self) -> Option<(usize, Float)> {
+		let total = self.total();
+		if total <= 0.0 { return None; }
+		
+		let target = util::random_float() * total;
+		let i = self.cdf.partition_point(|&c| c <= target).min(self.cdf.len() - 1);
+		Some((i, self.pdf(i)))
+	}
+	
+	fn pdf(&self, i: usize) -> Float {
+		let total = self.total();
+		if total <= 0.0 { return 0.0; }
+		
+		let before = if i == 0 { 0.0 } else { self.cdf[i - 1] };
+		(self.cdf[i] - before) / total
+	}
+}
+
+/// Read a Radiance RGBE image, returning its width, height and pixels.
+/// 
+/// Each pixel is stored as 8 bits of red, green and blue, and an exponent they all share.
+/// Rows are usually run-length encoded, with each channel stored one after another.
+fn read_hdr(mut reader: impl BufRead) -> io::Result<(usize, usize, Vec<Color>)> {
+	let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+	
+	let mut line = String::new();
+	reader.read_line(&mut line)?;
+	if !line.starts_with("#?") { return Err(invalid("not a Radiance .hdr file")); }
+	
+	// The header goes until an empty line.
+	loop {
+		line.clear();
+		if reader.read_line(&mut line)? == 0 { return Err(invalid("the header never ends")); }
+		let line = line.trim();
+		if line.is_empty() { break; }
+		if let Some(format) = line.strip_prefix("FORMAT=") {
+			if format != "32-bit_rle_rgbe" { return Err(invalid("only RGBE .hdr files are supported")); }
+		}
+	}
+	
+	line.clear();
+	reader.read_line(&mut line)?;
+	let (height, width) = match line.split_whitespace().collect::<Vec<_>>()[..] {
+		["-Y", h, "+X", w] => (h.parse().ok(), w.parse().ok()),
+		_ => return Err(invalid("only top-to-bottom, left-to-right .hdr files are supported")),
+	};
+	let (Some(height), Some(width)): (Option<usize>, Option<usize>) = (height, width) else {
+		return Err(invalid("couldn't read the .hdr file's size"));
+	};
+	
+	let mut pixels = Vec::with_capacity(width * height);
+	let mut row = vec![[0u8; 4]; width];
+	
+	for _ in 0..height {
+		let mut start = [0u8; 4];
+		reader.read_exact(&mut start)?;
+		
+		let encoded = (8..0x8000).contains(&width) && start[0] == 2 && start[1] == 2 && start[2] & 0x80 == 0;
+		if encoded {
+			if ((start[2] as usize) << 8 | start[3] as usize) != width {
+				return Err(invalid("an .hdr row is the wrong length"));
+			}
+			
+			for channel in 0..4 {
+				let mut x = 0;
+				while x < width {
+					let mut count = [0u8; 1];
+					reader.read_exact(&mut count)?;
+					let (count, run) = if count[0] > 128 { (count[0] as usize - 128, true) } else { (count[0] as usize, false) };
+					if count == 0 || x + count > width { return Err(invalid("an .hdr row is the wrong length")); }
+					
+					if run {
+						let mut value = [0u8; 1];
+						reader.read_exact(&mut value)?;
+						for pixel in &mut row[x..x + count] { pixel[channel] = value[0]; }
+					} else {
+						for pixel in &mut row[x..x + count] {
+							let mut value = [0u8; 1];
+							reader.read_exact(&mut value)?;
+							pixel[channel] = value[0];
+						}
+					}
+					x += count;
+				}
+			}
+		} else {
+			// Not encoded, so that was just the first pixel.
+			row[0] = start;
+			for pixel in &mut row[1..] { reader.read_exact(pixel)?; }
+		}
+		
+		pixels.extend(row.iter().map(|&[r, g, b, e]| {
+			if e == 0 { return Color::ZERO; }
+			let scale = Float::powi(2.0, e as i32 - (128 + 8));
+			Color::new(r as Float, g as Float, b as Float) * scale
+		}));
+	}
+	
+	Ok((width, height, pixels))
+}
+
+pub mod sky;
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	#[test]
+	fn hdr_rows_decode() {
+		let mut file = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 2 +X 8\n".to_vec();
+		// The first row is run-length encoded, one channel at a time.
+		file.extend([2, 2, 0, 8]);
+		file.extend([128 + 8, 128]);
+		file.extend([8, 0, 16, 32, 48, 64, 80, 96, 112]);
+		file.extend([128 + 4, 0, 128 + 4, 64]);
+		file.extend([128 + 8, 129]);
+		// The second one isn't, so it's just the pixels.
+		for x in 0..8 { file.extend([x * 16, 0, 0, 130]); }
+		
+		let (width, height, pixels) = read_hdr(&file[..]).unwrap();
+		assert_eq!((width, height), (8, 2));
+		
+		// An exponent of 129 makes the channels go from 0 to 2, in steps of 1/128.
+		for (x, &pixel) in pixels[..8].iter().enumerate() {
+			let blue = if x < 4 { 0.0 } else { 0.5 };
+			assert_eq!(pixel, Color::new(1.0, x as Float / 8.0, blue));
+		}
+		for (x, &pixel) in pixels[8..].iter().enumerate() {
+			assert_eq!(pixel, Color::new(x as Float / 4.0, 0.0, 0.0));
+		}
+	}
+	
+	#[test]
+	fn distribution_pdf_sums_to_one() {
+		let distribution = Distribution::new([1.0, 0.0, 3.0, 2.5, 0.5].into_iter());
+		let sum: Float = (0..5).map(|i| distribution.pdf(i)).sum();
+		assert!((sum - 1.0).abs() < 1e-12);
+		assert_eq!(distribution.pdf(1), 0.0);
+		
+		for _ in 0..100 {
+			let (i, pdf) = distribution.sample().unwrap();
+			assert_ne!(i, 1);
+			assert_eq!(pdf, distribution.pdf(i));
+		}
+	}
+	
+	#[test]
+	fn map_pdf_matches_sample() {
+		let pixels = (0..32).map(|i| Color::new(i as Float, (i % 5) as Float, 1.0)).collect();
+		let map = EnvironmentMap::new(8, 4, pixels).rotation(30.0);
+		
+		for _ in 0..1000 {
+			let (direction, radiance, pdf) = map.sample().unwrap();
+			assert!((map.pdf(direction) - pdf).abs() < 1e-6 * pdf, "{} vs {pdf}", map.pdf(direction));
+			assert_eq!(map.radiance(direction), radiance);
+		}
+	}
+}
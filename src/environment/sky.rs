@@ -0,0 +1,221 @@
+use crate::vector::{Vec3, Color, Float};
+use crate::spectrum;
+use crate::util;
+
+use super::Environment;
+
+/// How big the sun looks, as the angle from its middle to its edge.
+const SUN_RADIUS: Float = 0.2666;
+
+/// How bright the sun is before the atmosphere gets to it, in the same units as the sky.
+const SUN_LUMINANCE: Float = 2.0e6;
+
+/// A clear daytime sky and the sun, from "A Practical Analytic Model for Daylight"
+/// (Preetham, Shirley & Smits, 1999).
+/// 
+/// +Y is up, -Z is north, and +X is east. The model only really works while the sun is up.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug)]
+pub struct Sky {
+	/// Pointing towards the sun.
+	sun: Vec3,
+	/// Everything's in kilocandelas per square meter, where the midday sun lights things up
+	/// with about a hundred thousand lux. The default of 0.02 brings something white in full
+	/// sun down to a bit under 1.
+	pub intensity: Float,
+	/// The sky straight up, as luminance and then x and y chromaticity.
+	zenith: [Float; 3],
+	/// The coefficients of Perez's formula, for luminance, x and y.
+	perez: [[Float; 5]; 3],
+	sun_radiance: Color,
+}
+impl Sky {
+	/// `turbidity` is how hazy it is, from 2 (perfectly clear) to about 10 (hazy).
+	pub fn new(sun: Vec3, turbidity: Float) -> Self {
+		let sun = sun.normalize();
+		let t = turbidity;
+		let theta_s = Float::acos(sun.y.clamp(0.0, 1.0));
+		
+		// Polynomials in the turbidity and the sun's angle from the zenith.
+		let chi = (4.0 / 9.0 - t / 120.0) * (std::f64::consts::PI as Float - 2.0 * theta_s);
+		let zenith_luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+		let chromaticity = |c: [[Float; 4]; 3]| {
+			let cubic = |k: [Float; 4]| ((k[0] * theta_s + k[1]) * theta_s + k[2]) * theta_s + k[3];
+			t.powi(2) * cubic(c[0]) + t * cubic(c[1]) + cubic(c[2])
+		};
+		let zenith_x = chromaticity([
+			[ 0.00166, -0.00375,  0.00209, 0.0    ],
+			[-0.02903,  0.06377, -0.03202, 0.00394],
+			[ 0.11693, -0.21196,  0.06052, 0.25886],
+		]);
+		let zenith_y = chromaticity([
+			[ 0.00275, -0.00610,  0.00317, 0.0    ],
+			[-0.04214,  0.08970, -0.04153, 0.00516],
+			[ 0.15346, -0.26756,  0.06670, 0.26688],
+		]);
+		
+		let perez = [
+			[ 0.1787 * t - 1.4630, -0.3554 * t + 0.4275, -0.0227 * t + 5.3251,  0.1206 * t - 2.5771, -0.0670 * t + 0.3703],
+			[-0.0193 * t - 0.2592, -0.0665 * t + 0.0008, -0.0004 * t + 0.2125, -0.0641 * t - 0.8989, -0.0033 * t + 0.0452],
+			[-0.0167 * t - 0.2608, -0.0950 * t + 0.0092, -0.0079 * t + 0.2102, -0.0441 * t - 1.6537, -0.0109 * t + 0.0529],
+		];
+		
+		Self {
+			sun, intensity: 0.02,
+			zenith: [zenith_luminance, zenith_x, zenith_y],
+			perez,
+			sun_radiance: Self::sun_transmittance(theta_s, turbidity) * SUN_LUMINANCE,
+		}
+	}
+	
+	/// Put the sun where it'd be at some place and time. `latitude` and `longitude` are
+	/// in degrees (north and east are positive), and `hour` is the time of day in UTC.
+	/// 
+	/// Uses the approximations from the appendix of Preetham's paper, which are
+	/// good to within a degree or so.
+	pub fn at(latitude: Float, longitude: Float, day_of_year: Float, hour: Float, turbidity: Float) -> Self {
+		let pi = std::f64::consts::PI as Float;
+		let latitude = latitude.to_radians();
+		
+		// Solar time, where noon is when the sun's highest.
+		let solar_time = hour + longitude / 15.0
+			+ 0.170 * Float::sin(4.0 * pi * (day_of_year - 80.0) / 373.0)
+			- 0.129 * Float::sin(2.0 * pi * (day_of_year - 8.0) / 355.0);
+		let declination = 0.4093 * Float::sin(2.0 * pi * (day_of_year - 81.0) / 368.0);
+		let (sin_hour, cos_hour) = (pi * solar_time / 12.0).sin_cos();
+		
+		let elevation = Float::asin(
+			latitude.sin() * declination.sin() - latitude.cos() * declination.cos() * cos_hour
+		);
+		// From the south, going towards the west.
+		let azimuth = Float::atan2(
+			-declination.cos() * sin_hour,
+			latitude.cos() * declination.sin() - latitude.sin() * declination.cos() * cos_hour
+		);
+		
+		let sun = Vec3::new(
+			-elevation.cos() * azimuth.sin(),
+			elevation.sin(),
+			elevation.cos() * azimuth.cos()
+		);
+		Self::new(sun, turbidity)
+	}
+	
+	pub const fn intensity(mut self, intensity: Float) -> Self {
+		self.intensity = intensity; self
+	}
+	
+	/// How much of the sun's light makes it through the air, at red, green and blue.
+	/// This only counts scattering off of air molecules and haze, from the appendix of the paper.
+	fn sun_transmittance(theta_s: Float, turbidity: Float) -> Color {
+		// How much air the light goes through, relative to straight up.
+		let air_mass = (theta_s.cos() + 0.15 * (93.885 - theta_s.to_degrees()).powf(-1.253)).recip();
+		let beta = 0.04608 * turbidity - 0.04586;
+		
+		// In micrometers.
+		let transmittance = |wavelength: Float| {
+			let rayleigh = -0.008735 * wavelength.powf(-4.08);
+			let haze = -beta * wavelength.powf(-1.3);
+			Float::exp((rayleigh + haze) * air_mass)
+		};
+		Color::new(transmittance(0.65), transmittance(0.55), transmittance(0.45))
+	}
+	
+	/// Perez's formula for how the sky changes from the zenith, with `theta` being
+	/// the angle from straight up and `gamma` the angle from the sun.
+	fn perez(coefficients: [Float; 5], cos_theta: Float, gamma: Float) -> Float {
+		let [a, b, c, d, e] = coefficients;
+		(1.0 + a * Float::exp(b / cos_theta)) * (1.0 + c * Float::exp(d * gamma) + e * gamma.cos().powi(2))
+	}
+	
+	fn sky_radiance(&self, direction: Vec3) -> Color {
+		// Anything below the horizon is the same as right at it.
+		let cos_theta = direction.y.max(1e-3);
+		let gamma = Float::acos(direction.dot(self.sun).clamp(-1.0, 1.0));
+		let theta_s = Float::acos(self.sun.y.clamp(0.0, 1.0));
+		
+		let [luminance, x, y] = [0, 1, 2].map(|i| {
+			let perez = self.perez[i];
+			self.zenith[i] * Self::perez(perez, cos_theta, gamma) / Self::perez(perez, 1.0, theta_s)
+		});
+		
+		let xyz = Vec3::new(x * luminance / y, luminance, (1.0 - x - y) * luminance / y);
+		spectrum::xyz_to_rgb(xyz).max(Color::ZERO)
+	}
+	
+	fn cos_sun_radius() -> Float {
+		SUN_RADIUS.to_radians().cos()
+	}
+	
+	/// The pdf of picking any one direction on the sun.
+	fn sun_pdf() -> Float {
+		(2.0 * std::f64::consts::PI as Float * (1.0 - Self::cos_sun_radius())).recip()
+	}
+}
+impl Environment for Sky {
+	fn radiance(&self, direction: Vec3) -> Color {
+		let direction = direction.normalize();
+		let sun = if self.sun.y > 0.0 && direction.dot(self.sun) >= Self::cos_sun_radius() {
+			self.sun_radiance
+		} else {
+			Color::ZERO
+		};
+		(self.sky_radiance(direction) + sun) * self.intensity
+	}
+	
+	fn is_sampleable(&self) -> bool {
+		self.sun.y > 0.0
+	}
+	
+	/// Only the sun gets sampled, since it's small and bright. The rest of the sky is
+	/// smooth enough that rays bouncing off of things find it well enough.
+	fn sample(&self) -> Option<(Vec3, Color, Float)> {
+		if self.sun.y <= 0.0 { return None; }
+		
+		// Pick somewhere on the sun, evenly.
+		let direction = util::random_in_cone(self.sun, Self::cos_sun_radius());
+		Some((direction, self.radiance(direction), Self::sun_pdf()))
+	}
+	
+	fn pdf(&self, direction: Vec3) -> Float {
+		if self.sun.y <= 0.0 || direction.normalize().dot(self.sun) < Self::cos_sun_radius() { return 0.0; }
+		Self::sun_pdf()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	#[test]
+	fn sky_is_finite_everywhere() {
+		for elevation in [1.0, 10.0, 45.0, 89.0 as Float] {
+			let elevation = elevation.to_radians();
+			for turbidity in [2.0, 5.0, 10.0] {
+				let sky = Sky::new(Vec3::new(elevation.cos(), elevation.sin(), 0.0), turbidity);
+				let edges = [Vec3::Y, -Vec3::Y, Vec3::X, -Vec3::X, Vec3::Z];
+				for direction in edges.into_iter().chain((0..1000).map(|_| util::random_unit_vector())) {
+					let radiance = sky.radiance(direction);
+					assert!(<[Float; 3]>::from(radiance).iter().all(|c| c.is_finite() && *c >= 0.0), "{radiance:?} going {direction:?}");
+				}
+			}
+		}
+	}
+	
+	#[test]
+	fn sky_is_brightest_around_the_sun() {
+		let elevation = Float::to_radians(30.0);
+		let sky = Sky::new(Vec3::new(elevation.cos(), elevation.sin(), 0.0), 3.0);
+		
+		// Just off the edge of the sun, where the sky's glowing around it.
+		let next_to_sun = Vec3::new((elevation + 0.01).cos(), (elevation + 0.01).sin(), 0.0);
+		let around_sun = sky.radiance(next_to_sun).luminance();
+		for _ in 0..1000 {
+			let direction = util::random_unit_vector();
+			if direction.dot(sky.sun) > 0.999 { continue; }
+			let luminance = sky.radiance(direction).luminance();
+			assert!(luminance < around_sun, "it's {luminance} going {direction:?}, but only {around_sun} next to the sun");
+		}
+		
+		assert!(sky.radiance(sky.sun).luminance() > 100.0 * around_sun);
+	}
+}
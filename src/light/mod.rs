@@ -0,0 +1,219 @@
+use crate::vector::{Vec3, Point3, Color, Float};
+use crate::bounding_box::BoundingBox;
+use crate::util;
+
+use tree::LightBounds;
+
+/// A light that's not part of any [Object](crate::world::Object).
+/// 
+/// These are infinitely small (or infinitely far away), so rays bouncing around can never
+/// bump into them. The only way to find their light is to go looking for it with shadow rays.
+pub trait Light {
+	/// Find the light arriving at `point`, if any.
+	fn sample(&self, point: Point3) -> Option<LightSample>;
+	
+	/// Where the light is and which way it shines, for deciding when it's worth looking at.
+	/// Lights that are infinitely far away don't have any bounds.
+	fn bounds(&self) -> Option<LightBounds>;
+	
+	/// Send some light out into the world, for following it the other way around.
+	/// 
+	/// `scene` is a ball around everything (its center and radius), so lights that are
+	/// infinitely far away know where to send their light from.
+	fn emit(&self, scene: (Point3, Float)) -> Option<Emission>;
+	
+	/// The pdfs of [Light::emit] sending light out going in `direction`, for its position and its direction.
+	/// Whichever one is always the same (like the position of a [PointLight]) is 1.
+	fn emit_pdf(&self, direction: Vec3, scene: (Point3, Float)) -> (Float, Float);
+}
+
+/// Some light arriving at a point from a [Light].
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug)]
+pub struct LightSample {
+	/// Pointing from the point to the light, normalized.
+	pub direction: Vec3,
+	/// How far away the light is, along `direction`.
+	pub distance: Float,
+	/// How much light gets there, if nothing's in the way.
+	pub light: Color,
+}
+
+/// Some light sent out from a [Light].
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug)]
+pub struct Emission {
+	pub position: Point3,
+	/// Which way it's going, normalized.
+	pub direction: Vec3,
+	/// How much light it's carrying: intensity for lights at a point, and irradiance for faraway ones.
+	pub light: Color,
+	/// The pdf of picking `position`, per unit area.
+	pub position_pdf: Float,
+	/// The pdf of picking `direction`, per unit solid angle.
+	pub direction_pdf: Float,
+}
+
+/// Shines evenly in every direction from one spot.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug)]
+pub struct PointLight {
+	pub position: Point3,
+	/// How bright it is, in any direction. It falls off with the square of the distance.
+	pub intensity: Color,
+}
+impl Light for PointLight {
+	fn sample(&self, point: Point3) -> Option<LightSample> {
+		let to_light = self.position - point;
+		let distance = to_light.magnitude();
+		if distance == 0.0 { return None; }
+		
+		Some(LightSample {
+			direction: to_light / distance,
+			distance,
+			light: self.intensity / distance.powi(2),
+		})
+	}
+	
+	fn bounds(&self) -> Option<LightBounds> {
+		Some(LightBounds {
+			bounds: BoundingBox::new(self.position, self.position),
+			power: 4.0 * std::f64::consts::PI as Float * self.intensity.luminance(),
+			// It shines every which way.
+			axis: Vec3::Y,
+			cos_theta_o: -1.0,
+			cos_theta_e: 0.0,
+		})
+	}
+	
+	fn emit(&self, _scene: (Point3, Float)) -> Option<Emission> {
+		Some(Emission {
+			position: self.position,
+			direction: util::random_unit_vector(),
+			light: self.intensity,
+			position_pdf: 1.0,
+			direction_pdf: (4.0 * std::f64::consts::PI as Float).recip(),
+		})
+	}
+	
+	fn emit_pdf(&self, _direction: Vec3, _scene: (Point3, Float)) -> (Float, Float) {
+		(1.0, (4.0 * std::f64::consts::PI as Float).recip())
+	}
+}
+
+/// A [PointLight] that only shines in a cone, like a flashlight or a stage light.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug)]
+pub struct SpotLight {
+	pub position: Point3,
+	/// Which way it's pointing.
+	pub direction: Vec3,
+	pub intensity: Color,
+	/// Inside of this angle from `direction` (in degrees), it's at full brightness.
+	pub inner_angle: Float,
+	/// Past this angle, there's no light at all. In between, it fades out smoothly.
+	pub outer_angle: Float,
+}
+impl SpotLight {
+	/// How much of the light shines out in `direction` (going away from the light), from 0 to 1.
+	fn falloff(&self, direction: Vec3) -> Float {
+		let cos_angle = direction.normalize().dot(self.direction.normalize());
+		let (cos_inner, cos_outer) = (self.inner_angle.to_radians().cos(), self.outer_angle.to_radians().cos());
+		if cos_angle <= cos_outer { return 0.0; }
+		
+		// Smoothstep between the two angles.
+		let t = ((cos_angle - cos_outer) / (cos_inner - cos_outer)).clamp(0.0, 1.0);
+		t * t * (3.0 - 2.0 * t)
+	}
+}
+impl Light for SpotLight {
+	fn sample(&self, point: Point3) -> Option<LightSample> {
+		let to_light = self.position - point;
+		let distance = to_light.magnitude();
+		if distance == 0.0 { return None; }
+		let direction = to_light / distance;
+		
+		let falloff = self.falloff(-direction);
+		if falloff == 0.0 { return None; }
+		
+		Some(LightSample {
+			direction,
+			distance,
+			light: self.intensity * falloff / distance.powi(2),
+		})
+	}
+	
+	fn bounds(&self) -> Option<LightBounds> {
+		let cos_outer = self.outer_angle.to_radians().cos();
+		Some(LightBounds {
+			bounds: BoundingBox::new(self.position, self.position),
+			power: 2.0 * std::f64::consts::PI as Float * (1.0 - cos_outer) * self.intensity.luminance(),
+			axis: self.direction.normalize(),
+			cos_theta_o: self.inner_angle.to_radians().cos(),
+			cos_theta_e: (self.outer_angle - self.inner_angle).to_radians().cos(),
+		})
+	}
+	
+	/// Only sends light out inside the outer cone, evenly.
+	fn emit(&self, scene: (Point3, Float)) -> Option<Emission> {
+		let cos_outer = self.outer_angle.to_radians().cos();
+		let direction = util::random_in_cone(self.direction.normalize(), cos_outer);
+		let (position_pdf, direction_pdf) = self.emit_pdf(direction, scene);
+		
+		Some(Emission {
+			position: self.position,
+			direction,
+			light: self.intensity * self.falloff(direction),
+			position_pdf, direction_pdf,
+		})
+	}
+	
+	fn emit_pdf(&self, direction: Vec3, _scene: (Point3, Float)) -> (Float, Float) {
+		let cos_outer = self.outer_angle.to_radians().cos();
+		if direction.normalize().dot(self.direction.normalize()) < cos_outer { return (1.0, 0.0); }
+		(1.0, (2.0 * std::f64::consts::PI as Float * (1.0 - cos_outer)).recip())
+	}
+}
+
+/// Light from so far away that it all comes from the same direction and
+/// never gets any dimmer, like the sun.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug)]
+pub struct DirectionalLight {
+	/// Which way the light's going.
+	pub direction: Vec3,
+	/// How much light lands on a surface facing straight at it.
+	pub irradiance: Color,
+}
+impl Light for DirectionalLight {
+	fn sample(&self, _point: Point3) -> Option<LightSample> {
+		Some(LightSample {
+			direction: -self.direction.normalize(),
+			distance: Float::INFINITY,
+			light: self.irradiance,
+		})
+	}
+	
+	fn bounds(&self) -> Option<LightBounds> {
+		None
+	}
+	
+	/// Sends light out from a disk just outside of the `scene`, facing the light's direction.
+	fn emit(&self, (center, radius): (Point3, Float)) -> Option<Emission> {
+		let direction = self.direction.normalize();
+		let (tangent, bitangent) = direction.orthonormal_basis();
+		
+		// Somewhere on the disk, evenly.
+		let r = util::random_float().sqrt();
+		let (sin, cos) = (2.0 * std::f64::consts::PI as Float * util::random_float()).sin_cos();
+		
+		Some(Emission {
+			position: center + radius * (r * cos * tangent + r * sin * bitangent - direction),
+			direction,
+			light: self.irradiance,
+			position_pdf: (std::f64::consts::PI as Float * radius.powi(2)).recip(),
+			direction_pdf: 1.0,
+		})
+	}
+	
+	fn emit_pdf(&self, _direction: Vec3, (_, radius): (Point3, Float)) -> (Float, Float) {
+		((std::f64::consts::PI as Float * radius.powi(2)).recip(), 1.0)
+	}
+}
+
+pub mod tree;
@@ -0,0 +1,255 @@
+use crate::vector::{Vec3, Point3, Float};
+use crate::bounding_box::BoundingBox;
+use crate::util;
+
+use std::collections::HashMap;
+
+/// Where some light (or a whole bunch of lights) is, how much of it there is, and which
+/// way it shines. Light shines out of a cone of `cos_theta_o` around the `axis`, and
+/// spreads out up to `cos_theta_e` past the edge of that.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug)]
+pub struct LightBounds {
+	pub bounds: BoundingBox,
+	/// Roughly how much light it gives off in total.
+	pub power: Float,
+	pub axis: Vec3,
+	pub cos_theta_o: Float,
+	pub cos_theta_e: Float,
+}
+impl LightBounds {
+	/// Bounds around both sets of lights.
+	fn union(self, other: Self) -> Self {
+		if self.power == 0.0 { return other; }
+		if other.power == 0.0 { return self; }
+		
+		let (axis, cos_theta_o) = union_cones((self.axis, self.cos_theta_o), (other.axis, other.cos_theta_o));
+		Self {
+			bounds: self.bounds.union(other.bounds),
+			power: self.power + other.power,
+			axis, cos_theta_o,
+			cos_theta_e: self.cos_theta_e.min(other.cos_theta_e),
+		}
+	}
+	
+	/// Roughly how much of the light makes it to `point`, going by how much
+	/// there is, how far away it is, and whether it's even shining that way.
+	/// 
+	/// From "Importance Sampling of Many Lights with Adaptive Tree Splitting"
+	/// (Conty Estevez & Kulla, 2018), the way pbrt does it.
+	fn importance(&self, point: Point3) -> Float {
+		let center = self.bounds.centroid();
+		let radius2 = (self.bounds.size().magnitude() / 2.0).powi(2);
+		let distance2 = (point - center).squared_magnitude();
+		
+		// Being right next to (or inside of) the lights shouldn't make them infinitely important.
+		let clamped2 = distance2.max(radius2).max(Float::EPSILON);
+		
+		// How far off the point is from where the light's shining, taking out
+		// the cone of light and the size of the bounds as seen from the point.
+		let pi = std::f64::consts::PI as Float;
+		let theta_w = if distance2 > 0.0 {
+			Float::acos(self.axis.dot((point - center).normalize()).clamp(-1.0, 1.0))
+		} else { 0.0 };
+		let theta_b = if distance2 > radius2 { Float::asin((radius2 / distance2).sqrt()) } else { pi };
+		let theta_o = Float::acos(self.cos_theta_o.clamp(-1.0, 1.0));
+		
+		let cos_theta = Float::cos((theta_w - theta_o - theta_b).max(0.0));
+		if cos_theta <= self.cos_theta_e { return 0.0; }
+		
+		self.power * cos_theta / clamped2
+	}
+}
+
+/// The smallest cone around both cones, as an axis and the cosine of its angle.
+fn union_cones((a, cos_a): (Vec3, Float), (b, cos_b): (Vec3, Float)) -> (Vec3, Float) {
+	let pi = std::f64::consts::PI as Float;
+	let theta_a = Float::acos(cos_a.clamp(-1.0, 1.0));
+	let theta_b = Float::acos(cos_b.clamp(-1.0, 1.0));
+	let theta_d = Float::acos(a.dot(b).clamp(-1.0, 1.0));
+	
+	// One cone might already cover the other.
+	if (theta_d + theta_b).min(pi) <= theta_a { return (a, cos_a); }
+	if (theta_d + theta_a).min(pi) <= theta_b { return (b, cos_b); }
+	
+	let theta_o = (theta_a + theta_d + theta_b) / 2.0;
+	if theta_o >= pi { return (a, -1.0); }
+	
+	// Turn `a` towards `b`, so the new cone just touches the far sides of both.
+	let normal = Vec3::cross(a, b);
+	if normal.squared_magnitude() == 0.0 { return (a, -1.0); }
+	let towards_b = Vec3::cross(normal.normalize(), a);
+	let (sin_r, cos_r) = (theta_o - theta_a).sin_cos();
+	
+	(cos_r * a + sin_r * towards_b, theta_o.cos())
+}
+
+/// Something light comes from.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Emitter {
+	/// One of the [World](crate::world::World)'s lights, by its index.
+	Light(usize),
+	/// One of the World's objects that glows, by its index.
+	Object(usize),
+}
+
+/// A tree of lights, for picking ones that are likely to matter more often than
+/// ones that are far away, dim, or pointing somewhere else.
+/// 
+/// Each branch holds [LightBounds] around everything under it, so picking a light
+/// is just walking down the tree, picking each way by how important it looks.
+#[derive(Clone, Debug, Default)]
+pub struct LightTree {
+	nodes: Vec<Node>,
+	/// Lights without bounds, which are infinitely far away.
+	infinite: Vec<usize>,
+	/// The way down the tree to each leaf, one bit per branch,
+	/// starting from the lowest bit. A 0 goes to the first child.
+	trails: HashMap<Emitter, u64>,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Node {
+	bounds: LightBounds,
+	kind: NodeKind,
+}
+
+#[derive(Copy, Clone, Debug)]
+enum NodeKind {
+	/// Holds the index of the second child. (The first child always comes right after its parent.)
+	Branch(usize),
+	Leaf(Emitter),
+}
+
+impl LightTree {
+	/// Build a tree out of `emitters`, and the `infinite` lights that can't go in it.
+	pub fn build(mut emitters: Vec<(Emitter, LightBounds)>, infinite: Vec<usize>) -> Self {
+		let mut tree = Self { nodes: Vec::new(), infinite, trails: HashMap::new() };
+		if !emitters.is_empty() {
+			tree.build_node(&mut emitters, 0, 0);
+		}
+		tree
+	}
+	
+	fn build_node(&mut self, emitters: &mut [(Emitter, LightBounds)], trail: u64, depth: u32) -> LightBounds {
+		if let [(emitter, bounds)] = *emitters {
+			self.nodes.push(Node { bounds, kind: NodeKind::Leaf(emitter) });
+			self.trails.insert(emitter, trail);
+			return bounds;
+		}
+		
+		// Split them in half, along the axis where they're most spread out.
+		let centroid = |bounds: &LightBounds| <[Float; 3]>::from(bounds.bounds.centroid());
+		let centroid_bounds = emitters.iter().fold(BoundingBox::EMPTY, |b, (_, l)| b.grow(l.bounds.centroid()));
+		let extent: [Float; 3] = centroid_bounds.size().into();
+		let axis = (0..3).max_by(|&a, &b| extent[a].total_cmp(&extent[b])).unwrap_or(0);
+		emitters.sort_by(|(_, a), (_, b)| centroid(a)[axis].total_cmp(&centroid(b)[axis]));
+		
+		let index = self.nodes.len();
+		self.nodes.push(Node { bounds: emitters[0].1, kind: NodeKind::Branch(0) });
+		
+		let (first, second) = emitters.split_at_mut(emitters.len() / 2);
+		let first = self.build_node(first, trail, depth + 1);
+		let second_index = self.nodes.len();
+		let second = self.build_node(second, trail | 1 << depth, depth + 1);
+		
+		let bounds = first.union(second);
+		self.nodes[index] = Node { bounds, kind: NodeKind::Branch(second_index) };
+		bounds
+	}
+	
+	/// The chance of picking an infinite light instead of going down the tree.
+	fn infinite_chance(&self) -> Float {
+		let tree = if self.nodes.is_empty() { 0 } else { 1 };
+		if self.infinite.is_empty() { return 0.0; }
+		self.infinite.len() as Float / (self.infinite.len() + tree) as Float
+	}
+	
+	/// Whether there's nothing in the tree to pick.
+	pub fn is_empty(&self) -> bool {
+		self.nodes.is_empty() && self.infinite.is_empty()
+	}
+	
+	/// Pick something to look for light from at `point`, along with the chance of having picked it.
+	pub fn sample(&self, point: Point3) -> Option<(Emitter, Float)> {
+		self.sample_by(|bounds| bounds.importance(point))
+	}
+	
+	/// The chance of [LightTree::sample] picking `emitter`.
+	pub fn pmf(&self, point: Point3, emitter: Emitter) -> Float {
+		self.pmf_by(emitter, |bounds| bounds.importance(point))
+	}
+	
+	/// Pick something to send light out from, by how much light it gives off,
+	/// along with the chance of having picked it.
+	pub fn sample_power(&self) -> Option<(Emitter, Float)> {
+		self.sample_by(|bounds| bounds.power)
+	}
+	
+	/// The chance of [LightTree::sample_power] picking `emitter`.
+	pub fn power_pmf(&self, emitter: Emitter) -> Float {
+		self.pmf_by(emitter, |bounds| bounds.power)
+	}
+	
+	/// Walk down the tree, picking each way by its `weight`.
+	fn sample_by(&self, weight: impl Fn(&LightBounds) -> Float) -> Option<(Emitter, Float)> {
+		let infinite_chance = self.infinite_chance();
+		if util::random_float() < infinite_chance {
+			let count = self.infinite.len();
+			let i = ((util::random_float() * count as Float) as usize).min(count - 1);
+			return Some((Emitter::Light(self.infinite[i]), infinite_chance / count as Float));
+		}
+		if self.nodes.is_empty() { return None; }
+		
+		let mut pmf = 1.0 - infinite_chance;
+		let mut index = 0;
+		loop {
+			match self.nodes[index].kind {
+				NodeKind::Leaf(emitter) => return Some((emitter, pmf)),
+				NodeKind::Branch(second) => {
+					let a = weight(&self.nodes[index + 1].bounds);
+					let b = weight(&self.nodes[second].bounds);
+					if a + b <= 0.0 { return None; }
+					
+					let chance = a / (a + b);
+					if util::random_float() < chance {
+						index += 1;
+						pmf *= chance;
+					} else {
+						index = second;
+						pmf *= 1.0 - chance;
+					}
+				},
+			}
+		}
+	}
+	
+	/// The chance of [LightTree::sample_by] picking `emitter` with the same `weight`.
+	fn pmf_by(&self, emitter: Emitter, weight: impl Fn(&LightBounds) -> Float) -> Float {
+		if let Emitter::Light(i) = emitter {
+			if self.infinite.contains(&i) { return self.infinite_chance() / self.infinite.len() as Float; }
+		}
+		let Some(&(mut trail)) = self.trails.get(&emitter) else { return 0.0 };
+		
+		let mut pmf = 1.0 - self.infinite_chance();
+		let mut index = 0;
+		loop {
+			match self.nodes[index].kind {
+				NodeKind::Leaf(_) => return pmf,
+				NodeKind::Branch(second) => {
+					let a = weight(&self.nodes[index + 1].bounds);
+					let b = weight(&self.nodes[second].bounds);
+					if a + b <= 0.0 { return 0.0; }
+					
+					if trail & 1 == 0 {
+						index += 1;
+						pmf *= a / (a + b);
+					} else {
+						index = second;
+						pmf *= b / (a + b);
+					}
+					trail >>= 1;
+				},
+			}
+		}
+	}
+}
@@ -11,22 +11,61 @@ use image::{RgbImage, Rgb};
 
 mod util;
 mod vector;
+mod noise;
+
+mod spectrum;
 
 mod ray;
+mod bounding_box;
+mod bvh;
 
 mod solid;
+mod microfacet;
+mod texture;
 mod material;
 mod world;
+mod medium;
+mod environment;
+mod light;
+mod scene;
 
 mod camera;
 mod raytracer;
 
-use vector::{Float, Vec2, Vec3, Point3, Color};
-use material::{Lambertian, Metal, Dielectric};
-use world::{World, Object};
+use vector::{Float, Vec3, Point3, Color, Mat4, Transform};
+use material::{Material, Lambertian, Metal, Conductor, AnisotropicConductor, Dielectric, RoughDielectric, Dispersion, Isotropic, HenyeyGreenstein, DiffuseLight};
+use material::principled::Principled;
+use material::mix::Mix;
+use material::coated::Coated;
+use material::bump::{NormalMapped, Bumped};
+use material::subsurface::Subsurface;
+use material::thin_dielectric::ThinDielectric;
+use microfacet::ComplexIor;
+use texture::{Checker, Marble, Remapped, ImageTexture};
+use world::{World, Object, AlphaMask};
+use environment::{EnvironmentMap, Gradient};
+use environment::sky::Sky;
+use light::{PointLight, SpotLight, DirectionalLight};
 use camera::{Camera, CameraLens};
 use solid::sphere::Sphere;
-use raytracer::Raytracer;
+use solid::moving_sphere::MovingSphere;
+use solid::cylinder::Cylinder;
+use solid::cone::Cone;
+use solid::capsule::Capsule;
+use solid::torus::Torus;
+use solid::transformed::Transformed;
+use solid::Hittable;
+use solid::group::Group;
+use solid::csg::Csg;
+use solid::sdf::{self, RayMarched};
+use solid::heightfield::Heightfield;
+use solid::constant_medium::ConstantMedium;
+use solid::heterogeneous_medium::HeterogeneousMedium;
+use medium::{Fog, VoxelGrid, NoiseField};
+use bounding_box::BoundingBox;
+use scene::Node;
+use raytracer::{Raytracer, Integrator};
+use raytracer::bidirectional::Lighting;
 
 //////////////////
 
@@ -34,34 +73,109 @@ const RENDER_THREADS: usize = 8;
 
 const UP: Vec3 = Vec3::ZERO.set_y(1.0);
 
+type MakeScene = fn() -> World;
+
+/// Where the camera goes to look at a scene.
+#[derive(Copy, Clone, Debug)]
+struct View {
+	origin: Point3,
+	look_at: Point3,
+	/// How much it sees from top to bottom, in degrees.
+	fov: Float,
+	/// How blurry things get away from `look_at`. With 0, everything's sharp.
+	aperture: Float,
+}
+impl View {
+	const fn new(origin: Point3, look_at: Point3, fov: Float) -> Self {
+		Self { origin, look_at, fov, aperture: 0.1 }
+	}
+	
+	const fn aperture(mut self, aperture: Float) -> Self {
+		self.aperture = aperture; self
+	}
+}
+
+/// Off to the side and a bit above, zoomed in on the middle. Most scenes fit in this.
+const SIDE_VIEW: View = View::new(Point3::new(13.0, 4.0, 3.0), Point3::ZERO, 10.0);
+/// The same, but from the front, for scenes with things lined up left to right.
+const FRONT_VIEW: View = View::new(Point3::new(3.0, 4.0, 13.0), Point3::ZERO, 10.0);
+
+/// Every scene, by the name to pick it with on the command line, and where to look at it from.
+const SCENES: &[(&str, MakeScene, View)] = &[
+	("basic", basic_scene, SIDE_VIEW),
+	("random", random_scene, View::new(Point3::new(13.0, 2.0, 3.0), Point3::ZERO, 20.0)),
+	("lab", lab_scene, SIDE_VIEW),
+	("instanced", instanced_scene, SIDE_VIEW),
+	("assembled", assembled_scene, SIDE_VIEW),
+	("csg", csg_scene, SIDE_VIEW),
+	("sdf", sdf_scene, SIDE_VIEW),
+	("terrain", terrain_scene, View::new(Point3::new(3.5, 2.5, 3.5), Point3::new(0.0, -0.3, 0.0), 40.0).aperture(0.0)),
+	("motion", motion_scene, SIDE_VIEW),
+	("smoke", smoke_scene, SIDE_VIEW),
+	("volume", volume_scene, SIDE_VIEW),
+	("glass", glass_scene, SIDE_VIEW),
+	("dispersion", dispersion_scene, FRONT_VIEW),
+	("microfacet", microfacet_scene, FRONT_VIEW),
+	("principled", principled_scene, FRONT_VIEW),
+	("layered", layered_scene, FRONT_VIEW),
+	("bump", bump_scene, FRONT_VIEW),
+	("mask", mask_scene, FRONT_VIEW),
+	("subsurface", subsurface_scene, FRONT_VIEW),
+	("bubble", bubble_scene, SIDE_VIEW),
+	("brushed", brushed_scene, SIDE_VIEW),
+	("environment", environment_scene, SIDE_VIEW),
+	("sky", sky_scene, SIDE_VIEW),
+	("lights", lights_scene, SIDE_VIEW),
+	("many_lights", many_lights_scene, SIDE_VIEW),
+	("caustics", caustics_scene, SIDE_VIEW),
+	("room", room_scene, View::new(Point3::new(3.5, 0.25, 0.0), Point3::new(0.0, 0.2, 0.0), 40.0).aperture(0.0)),
+];
+
 fn main() {
+	// Follow wavelengths around instead of colors with `--spectral`, which dispersion needs.
+	let spectral = std::env::args().any(|arg| arg == "--spectral");
+	
+	// Pick a scene, and maybe an integrator, like `cargo run --release -- room bidirectional`.
+	let mut args = std::env::args().skip(1).filter(|arg| arg != "--spectral");
+	let scene = args.next().unwrap_or_else(|| "basic".to_string());
+	let Some(&(_, make_scene, view)) = SCENES.iter().find(|(name, ..)| *name == scene) else {
+		let names: Vec<_> = SCENES.iter().map(|(name, ..)| *name).collect();
+		fail(&format!("there's no scene called {scene:?}, try one of {}", names.join(", ")));
+	};
+	let integrator = args.next().map_or(Ok(Integrator::default()), |name| name.parse());
+	
 	let raytracer = Raytracer {
 		screen: (1920 / 2, 1080 / 2).into(),
-		max_depth: 24, samples: 32
+		max_depth: 24, samples: 32,
+		spectral,
+		integrator: integrator.unwrap_or_else(|error| fail(&error.to_string())),
 	};
 	
 	let mut image = RgbImage::new(
 		raytracer.screen.width as u32, raytracer.screen.height as u32
 	);
 	
-	let world = basic_scene();
-	// let world = random_scene();
+	let mut world = make_scene();
+	
+	// Sort everything into a tree, so rays don't have to check every object.
+	world.build_bvh();
+	world.build_light_tree();
 	
 	// Put world inside an Arc, to share it with threads.
 	// Don't need any mutation, it'll all be nice and fast.
 	let world = Arc::new(world);
+	let lighting = Lighting::new(&world);
 	
 	// Construct our camera.
-	let origin = Point3::new(13.0, 4.0, 3.0);
-	let look_at = Point3::ZERO;
-	let lens = CameraLens::new_from_dist(0.1, origin, look_at);
+	let View { origin, look_at, fov, aperture } = view;
+	let lens = (aperture > 0.0).then(|| CameraLens::new_from_dist(aperture, origin, look_at));
 	
 	let camera = Camera::new(
 		origin, look_at, UP,
-		10.0,
+		fov,
 		raytracer.screen.aspect_ratio(),
-		Some(lens)
-	);
+		lens
+	).with_shutter(0.0, 1.0);
 	
 	let start_of_op = Instant::now();
 	
@@ -72,15 +186,14 @@ fn main() {
 		let tx = tx.clone();
 		
 		thread::spawn(move || {
+			let mut splats = Vec::new();
+			
 			for y in (0..raytracer.screen.height).rev().skip(thread_no).step_by(RENDER_THREADS) {
-				let yr = 1.0 - (y as Float / raytracer.screen.height as Float);
-				
 				for x in 0..raytracer.screen.width {
-					let xr = x as Float / raytracer.screen.width as Float;
-					let uv = Vec2::new(xr, yr);
+					let uv = raytracer.screen.uv(x, y);
 					
-					let pixel = raytracer.get_pixel(&t_world, &camera, uv);
-					tx.send((x, y, pixel)).unwrap();
+					let pixel = raytracer.get_pixel(&t_world, &camera, lighting, uv, &mut splats);
+					tx.send((x, y, pixel, std::mem::take(&mut splats))).unwrap();
 				}
 			}
 		});
@@ -93,6 +206,9 @@ fn main() {
 	let mut pixels_plotted = 0usize;
 	let total_pixels = raytracer.screen.width * raytracer.screen.height;
 	
+	// Light can land on any pixel, so everything gets added up before it's averaged.
+	let mut film = vec![Color::ZERO; total_pixels];
+	
 	{
 		
 		// Lock everyone else out of the stdout.
@@ -103,8 +219,12 @@ fn main() {
 		// it will deadlock the application!!
 		
 		// Draw the picture
-		while let Ok((x, y, pixel)) = rx.recv() {
-			image.put_pixel(x as u32, y as u32, Rgb(pixel.into()));
+		while let Ok((x, y, pixel, splats)) = rx.recv() {
+			film[y * raytracer.screen.width + x] += pixel;
+			for (uv, light) in splats {
+				let Some((x, y)) = raytracer.screen.pixel(uv) else { continue };
+				film[y * raytracer.screen.width + x] += light;
+			}
 			
 			pixels_plotted += 1;
 			if pixels_plotted & 0xFFF == 0 { // (every so often)
@@ -117,15 +237,31 @@ fn main() {
 	let duration = start_of_op.elapsed();
 	eprintln!("\r100.00% done. Took {duration:.2?}.");
 	
+	// Finally, average all the samples and calculate the gamma-correct color.
+	for (i, color) in film.into_iter().enumerate() {
+		let (x, y) = (i % raytracer.screen.width, i / raytracer.screen.width);
+		image.put_pixel(x as u32, y as u32, Rgb(color.gamma_accurate_average(raytracer.samples).into()));
+	}
+	
 	image.save("output.png").expect("couldn't save...");
 }
 
-#[allow(dead_code)]
+/// Stop with a message, for things that went wrong on the user's end, where a panic's too much.
+fn fail(message: &str) -> ! {
+	eprintln!("{message}");
+	std::process::exit(1);
+}
+
+/// Load a file that a scene needs, or stop and say which one, since there's no going on without it.
+fn load<T, E: std::fmt::Display>(path: &'static str, load: impl FnOnce(&'static str) -> Result<T, E>) -> T {
+	load(path).unwrap_or_else(|error| fail(&format!("couldn't load {path}, which this scene needs: {error}")))
+}
+
 fn basic_scene() -> World {
-	let mut world = World { objects: Vec::new(), ..Default::default() };
+	let mut world = World::default();
 	
 	// Like in real life, everything sits on a really big sphere.
-	world.objects.push(Object::new(
+	world.push(Object::new(
 		Box::new(Sphere::new(Vec3::new(0.0, -1000.5, -1.0), 1000.0)),
 		Box::new(Lambertian { albedo: Color::all(0.5) }))
 	);
@@ -133,42 +269,41 @@ fn basic_scene() -> World {
 	// Scatter 81 balls around, for fun.
 	for i in -8..=8 {
 		for j in -8..=8 {
-			world.objects.push(Object::new(
+			world.push(Object::new(
 				Box::new(Sphere::new(Vec3::new(i as Float, 0.5 + (i as Float).sin() * (j as Float).cos(), j as Float) / 2.0, 0.2)),
 				if util::random_float() < 0.8 {
 					Box::new(Lambertian { albedo: (Vec3::ONE + util::random_color()) / 2.0 })
 				} else {
-					Box::new(Dielectric { refractive_index: 1.5 })
+					Box::new(Dielectric::new(1.5))
 				}
 			));
 		}
 	}
 	
-	world.objects.push(Object::new(
+	world.push(Object::new(
 		Box::new(Sphere::new(-Vec3::Z, 0.5)),
 		Box::new(Metal { albedo: Color::new(1.0, 0.25, 0.5), fuzz: 0.125 })
 	));
-	world.objects.push(Object::new(
+	world.push(Object::new(
 		Box::new(Sphere::new(Vec3::ZERO, 0.5)),
 		Box::new(Metal { albedo: Color::new(0.25, 1.0, 0.5), fuzz: 0.0 })
 	));
-	world.objects.push(Object::new(
+	world.push(Object::new(
 		Box::new(Sphere::new( Vec3::Z, 0.5)),
 		Box::new(Metal { albedo: Color::new(0.5, 0.25, 1.0), fuzz: 0.25 })
 	));
-	// world.objects.push(Object::new(
+	// world.push(Object::new(
 	// 	Box::new(Sphere::new(Vec3::new(1.0, 0.75, 0.0), 0.5)),
-	// 	Box::new(Dielectric { refractive_index: 1.5 })
+	// 	Box::new(Dielectric::new(1.5))
 	// ));
 	
 	world
 }
 
-#[allow(dead_code)]
 fn random_scene() -> World {
-	let mut world = World { objects: Vec::new(), ..Default::default() };
+	let mut world = World::default();
 	
-	world.objects.push(Object::new(
+	world.push(Object::new(
 		Box::new(Sphere::new(Vec3::new(0.0, -1000.5, -1.0), 1000.0)),
 		Box::new(Lambertian { albedo: Color::all(0.5) })
 	));
@@ -185,7 +320,7 @@ fn random_scene() -> World {
 	}
 	
 	fn random_dielectric_mat() -> Dielectric {
-		Dielectric { refractive_index: 1.5 }
+		Dielectric::new(1.5)
 	}
 	
 	for a in -11..=11 {
@@ -197,7 +332,7 @@ fn random_scene() -> World {
 			);
 			let shape = Sphere::new(center, 0.2);
 			
-			world.objects.push(Object::new(Box::new(shape), {
+			world.push(Object::new(Box::new(shape), {
 				let choose_mat = util::random_float();
 				if choose_mat < 0.8 {
 					Box::new(random_lambertian_mat())
@@ -210,18 +345,853 @@ fn random_scene() -> World {
 		}
 	}
 	
-	world.objects.push(Object::new(
+	world.push(Object::new(
 		Box::new(Sphere::new(Point3::new(0.0, 1.0, 0.0), 1.0)),
-		Box::new(Dielectric { refractive_index: 1.5 })
+		Box::new(Dielectric::new(1.5))
 	));
-	world.objects.push(Object::new(
+	world.push(Object::new(
 		Box::new(Sphere::new(Point3::new(-4.0, 1.0, 0.0), 1.0)),
 		Box::new(Lambertian { albedo: Color::new(0.4, 0.2, 0.1) })
 	));
-	world.objects.push(Object::new(
+	world.push(Object::new(
 		Box::new(Sphere::new(Point3::new(4.0, 1.0, 0.0), 1.0)),
 		Box::new(Metal { albedo: Color::new(0.7, 0.6, 0.5), fuzz: 0.0 })
 	));
 	
 	world
 }
+
+fn lab_scene() -> World {
+	let mut world = World::default();
+	
+	world.push(Object::new(
+		Box::new(Sphere::new(Vec3::new(0.0, -1000.5, -1.0), 1000.0)),
+		Box::new(Lambertian { albedo: Color::all(0.5) })
+	));
+	
+	// A glass beaker, and a funnel sitting in it.
+	world.push(Object::new(
+		Box::new(Cylinder::new(Point3::new(0.0, -0.5, 0.0), Point3::new(0.0, 0.3, 0.0), 0.4)),
+		Box::new(Dielectric::new(1.5))
+	));
+	world.push(Object::new(
+		Box::new(Cone::frustum(Point3::new(0.0, 0.1, 0.0), Point3::new(0.0, 0.7, 0.0), 0.05, 0.3).uncapped()),
+		Box::new(Metal { albedo: Color::all(0.8), fuzz: 0.1 })
+	));
+	
+	// Some plumbing along the back.
+	world.push(Object::new(
+		Box::new(Cylinder::new(Point3::new(-3.0, 0.0, -1.5), Point3::new(3.0, 0.0, -1.5), 0.15).uncapped()),
+		Box::new(Metal { albedo: Color::new(0.8, 0.5, 0.3), fuzz: 0.2 })
+	));
+	world.push(Object::new(
+		Box::new(Torus::new(Point3::new(1.0, 0.0, -1.5), Vec3::X, 0.2, 0.05)),
+		Box::new(Metal { albedo: Color::all(0.6), fuzz: 0.05 })
+	));
+	
+	// A stopper and a cone, just sitting around.
+	world.push(Object::new(
+		Box::new(Capsule::new(Point3::new(1.0, -0.35, 0.5), Point3::new(1.6, -0.35, 0.8), 0.15)),
+		Box::new(Lambertian { albedo: Color::new(0.7, 0.2, 0.2) })
+	));
+	world.push(Object::new(
+		Box::new(Cone::new(Point3::new(-1.2, -0.5, 0.5), Point3::new(-1.2, 0.4, 0.5), 0.35)),
+		Box::new(Lambertian { albedo: Color::new(0.2, 0.4, 0.7) })
+	));
+	
+	world
+}
+
+fn instanced_scene() -> World {
+	let mut world = World::default();
+	
+	world.push(Object::new(
+		Box::new(Sphere::new(Vec3::new(0.0, -1000.5, -1.0), 1000.0)),
+		Box::new(Lambertian { albedo: Color::all(0.5) })
+	));
+	
+	// One ring, shared between every instance.
+	let ring: Arc<dyn Hittable + Send + Sync> = Arc::new(Torus::new(Point3::ZERO, Vec3::Y, 0.15, 0.04));
+	
+	for i in -5..5 {
+		for j in -5..5 {
+			let transform = Transform::rotate(Vec3::X, 90.0)
+				.then(Transform::scale(Vec3::new(1.0, 1.0 + util::random_float(), 1.0)))
+				.then(Transform::rotate(Vec3::Y, 360.0 * util::random_float()))
+				.then(Transform::translate(Vec3::new(i as Float, -0.3, j as Float) * 0.4));
+			
+			world.push(Object::new(
+				Box::new(Transformed::new(ring.clone(), transform)),
+				Box::new(Lambertian { albedo: (Vec3::ONE + util::random_color()) / 2.0 })
+			));
+		}
+	}
+	
+	// A leaning one, to show off that any invertible matrix works.
+	let shear = Transform::new(Mat4([
+		[1.0, 0.5, 0.0, 0.0],
+		[0.0, 1.0, 0.0, 0.0],
+		[0.0, 0.0, 1.0, 0.0],
+		[0.0, 0.0, 0.0, 1.0],
+	])).expect("shears are invertible");
+	world.push(Object::new(
+		Box::new(Transformed::new(ring, Transform::scale(Vec3::all(2.0)).then(shear))),
+		Box::new(Metal { albedo: Color::all(0.8), fuzz: 0.0 })
+	));
+	
+	world
+}
+
+fn assembled_scene() -> World {
+	let mut world = World::default();
+	
+	world.push(Object::new(
+		Box::new(Sphere::new(Vec3::new(0.0, -1000.5, -1.0), 1000.0)),
+		Box::new(Lambertian { albedo: Color::all(0.5) })
+	));
+	
+	// A water molecule, built once and shared by every copy of it.
+	let hydrogen = Point3::new(0.08, 0.06, 0.0);
+	let molecule: Arc<dyn Hittable + Send + Sync> = Arc::new(Group::new(vec![
+		Arc::new(Sphere::new(Point3::ZERO, 0.07)),
+		Arc::new(Sphere::new(hydrogen, 0.04)),
+		Arc::new(Sphere::new(hydrogen.set_x(-hydrogen.x), 0.04)),
+		Arc::new(Capsule::new(Point3::ZERO, hydrogen, 0.015)),
+		Arc::new(Capsule::new(Point3::ZERO, hydrogen.set_x(-hydrogen.x), 0.015)),
+	]));
+	
+	let plastic: Arc<dyn Material + Send + Sync> = Arc::new(Lambertian { albedo: Color::new(0.3, 0.5, 0.9) });
+	let chrome: Arc<dyn Material + Send + Sync> = Arc::new(Metal { albedo: Color::all(0.9), fuzz: 0.0 });
+	
+	// A tray of molecules, all plastic except for the odd chrome one.
+	let mut tray = Node::new(Transform::translate(Vec3::new(0.0, -0.4, 0.0))).material(plastic);
+	for i in -10..10 {
+		for j in -10..10 {
+			let transform = Transform::rotate(util::random_color() - Vec3::all(0.5), 360.0 * util::random_float())
+				.then(Transform::translate(Vec3::new(i as Float, 0.0, j as Float) * 0.2));
+			
+			let mut node = Node::with_geometry(molecule.clone(), transform);
+			if util::random_float() < 0.1 {
+				node = node.material(chrome.clone());
+			}
+			tray = tray.child(node);
+		}
+	}
+	
+	// The same tray a few more times, stacked up.
+	let tray = Arc::new(tray);
+	let mut stack = Node::new(Transform::IDENTITY);
+	for level in 0..3 {
+		let lift = Transform::rotate(Vec3::Y, 15.0 * level as Float)
+			.then(Transform::translate(Vec3::new(0.0, 0.3 * level as Float, 0.0)));
+		stack = stack.child(Node::new(lift).child(tray.clone()));
+	}
+	stack.add_to_world(&mut world);
+	
+	world
+}
+
+fn csg_scene() -> World {
+	let mut world = World::default();
+	
+	world.push(Object::new(
+		Box::new(Sphere::new(Vec3::new(0.0, -1000.5, -1.0), 1000.0)),
+		Box::new(Lambertian { albedo: Color::all(0.5) })
+	));
+	
+	// A lens is just where two spheres overlap.
+	world.push(Object::new(
+		Box::new(Csg::intersection(
+			Box::new(Sphere::new(Point3::new(0.85, 0.1, -0.8), 1.0)),
+			Box::new(Sphere::new(Point3::new(-0.85, 0.1, -0.8), 1.0))
+		).expect("spheres are closed")),
+		Box::new(Dielectric::new(1.5))
+	));
+	
+	// A bolt with a hole drilled through it, and a notch taken out of its head.
+	let bolt = Csg::union(
+		Box::new(Cylinder::new(Point3::new(0.0, -0.5, 0.3), Point3::new(0.0, -0.35, 0.3), 0.3)),
+		Box::new(Cylinder::new(Point3::new(0.0, -0.35, 0.3), Point3::new(0.0, 0.4, 0.3), 0.12))
+	).expect("capped cylinders are closed");
+	let bolt = Csg::difference(
+		Box::new(bolt),
+		Box::new(Cylinder::new(Point3::new(-0.5, 0.2, 0.3), Point3::new(0.5, 0.2, 0.3), 0.06))
+	).expect("capped cylinders are closed");
+	let bolt = Csg::difference(
+		Box::new(bolt),
+		Box::new(Transformed::new(
+			Arc::new(Torus::new(Point3::ZERO, Vec3::Y, 0.3, 0.06)),
+			Transform::translate(Point3::new(0.0, -0.35, 0.3))
+		))
+	).expect("tori are closed");
+	world.push(Object::new(
+		Box::new(bolt),
+		Box::new(Metal { albedo: Color::new(0.8, 0.7, 0.5), fuzz: 0.15 })
+	));
+	
+	world
+}
+
+fn sdf_scene() -> World {
+	let mut world = World::default();
+	
+	world.push(Object::new(
+		Box::new(Sphere::new(Vec3::new(0.0, -1000.5, -1.0), 1000.0)),
+		Box::new(Lambertian { albedo: Color::all(0.5) })
+	));
+	
+	world.push(Object::new(
+		Box::new(RayMarched::new(sdf::Mandelbulb::new(Point3::new(0.0, 0.1, 0.0), 0.5))
+			.epsilon(1e-3)
+			.step_scale(0.8)
+			.max_steps(512)),
+		Box::new(Lambertian { albedo: Color::new(0.8, 0.4, 0.2) })
+	));
+	
+	// A gooey blob, and something halfway between a box and a donut.
+	world.push(Object::new(
+		Box::new(RayMarched::new(sdf::SmoothUnion {
+			a: sdf::Sphere { center: Point3::new(0.0, -0.3, 1.0), radius: 0.2 },
+			b: sdf::SmoothUnion {
+				a: sdf::Capsule { a: Point3::new(0.0, -0.4, 0.7), b: Point3::new(0.0, -0.1, 1.3), radius: 0.08 },
+				b: sdf::Torus { center: Point3::new(0.0, -0.4, 1.0), major_radius: 0.25, minor_radius: 0.05 },
+				k: 0.1,
+			},
+			k: 0.15,
+		})),
+		Box::new(Metal { albedo: Color::new(0.4, 0.8, 0.4), fuzz: 0.05 })
+	));
+	world.push(Object::new(
+		Box::new(RayMarched::new(sdf::Blend {
+			a: sdf::RoundCuboid { center: Point3::new(0.0, -0.3, -1.0), half_size: Vec3::all(0.2), radius: 0.05 },
+			b: sdf::Torus { center: Point3::new(0.0, -0.3, -1.0), major_radius: 0.2, minor_radius: 0.08 },
+			t: 0.5,
+		})),
+		Box::new(Lambertian { albedo: Color::new(0.2, 0.3, 0.8) })
+	));
+	world.push(Object::new(
+		Box::new(RayMarched::new(sdf::Cuboid { center: Point3::new(-1.0, -0.35, 0.0), half_size: Vec3::all(0.15) })),
+		Box::new(Dielectric::new(1.5))
+	));
+	
+	world
+}
+
+fn terrain_scene() -> World {
+	let mut world = World::default();
+	
+	// Any grayscale image works, and it can be as big as you like.
+	let terrain = load("heightmap.png", |path| Heightfield::load(
+		path,
+		Point3::new(-2.0, -0.5, -2.0),
+		Vec3::new(4.0, 0.6, 4.0)
+	));
+	
+	world.push(Object::new(
+		Box::new(terrain),
+		Box::new(Lambertian { albedo: Color::new(0.4, 0.5, 0.3) })
+	));
+	world.push(Object::new(
+		Box::new(Sphere::new(Point3::new(0.0, 0.3, 0.0), 0.2)),
+		Box::new(Metal { albedo: Color::all(0.8), fuzz: 0.0 })
+	));
+	
+	world
+}
+
+fn motion_scene() -> World {
+	let mut world = World::default();
+	
+	world.push(Object::new(
+		Box::new(Sphere::new(Vec3::new(0.0, -1000.5, -1.0), 1000.0)),
+		Box::new(Lambertian { albedo: Color::all(0.5) })
+	));
+	
+	// Balls bouncing up while the shutter's open.
+	for i in -4..=4 {
+		for j in -4..=4 {
+			let start = Point3::new(i as Float, 0.0, j as Float) * 0.3 - Vec3::Y * 0.35;
+			let end = start + Vec3::Y * 0.3 * util::random_float();
+			
+			world.push(Object::new(
+				Box::new(MovingSphere::new(start, end, 0.0, 1.0, 0.1)),
+				Box::new(Lambertian { albedo: (Vec3::ONE + util::random_color()) / 2.0 })
+			));
+		}
+	}
+	
+	world
+}
+
+fn smoke_scene() -> World {
+	let mut world = World::default();
+	world.fog = Some(Fog::new(0.02, Color::all(0.9)));
+	
+	world.push(Object::new(
+		Box::new(Sphere::new(Vec3::new(0.0, -1000.5, -1.0), 1000.0)),
+		Box::new(Lambertian { albedo: Color::all(0.5) })
+	));
+	
+	// A box full of smoke, and a ball full of mist.
+	world.push(Object::new(
+		Box::new(ConstantMedium::new(
+			Box::new(RayMarched::new(sdf::Cuboid { center: Point3::new(0.0, -0.1, -0.6), half_size: Vec3::all(0.4) })),
+			4.0
+		)),
+		Box::new(Isotropic { albedo: Color::all(0.2) })
+	));
+	world.push(Object::new(
+		Box::new(ConstantMedium::new(Box::new(Sphere::new(Point3::new(0.0, 0.0, 0.6), 0.5)), 2.0)),
+		Box::new(Isotropic { albedo: Color::new(0.8, 0.9, 1.0) })
+	));
+	
+	world
+}
+
+fn volume_scene() -> World {
+	let mut world = World::default();
+	
+	world.push(Object::new(
+		Box::new(Sphere::new(Vec3::new(0.0, -1000.5, -1.0), 1000.0)),
+		Box::new(Lambertian { albedo: Color::all(0.5) })
+	));
+	
+	// A puffy cloud, which scatters mostly forwards like real ones do.
+	world.push(Object::new(
+		Box::new(HeterogeneousMedium::new(
+			Box::new(Sphere::new(Point3::new(0.0, 0.1, 0.6), 0.6)),
+			Box::new(NoiseField::new(12.0, 4.0))
+		)),
+		Box::new(HenyeyGreenstein { albedo: Color::all(0.95), g: 0.6 })
+	));
+	
+	// Smoke from a simulation, written out as a voxel grid.
+	let bounds = BoundingBox::new(Point3::new(-0.4, -0.5, -1.2), Point3::new(0.4, 0.5, -0.2));
+	let smoke = load("smoke.vox", |path| VoxelGrid::load(path, bounds));
+	world.push(Object::new(
+		Box::new(HeterogeneousMedium::new(
+			Box::new(RayMarched::new(sdf::Cuboid { center: bounds.centroid(), half_size: bounds.size() / 2.0 })),
+			Box::new(smoke)
+		)),
+		Box::new(HenyeyGreenstein { albedo: Color::new(0.6, 0.5, 0.4), g: -0.2 })
+	));
+	
+	world
+}
+
+fn glass_scene() -> World {
+	let mut world = World::default();
+	
+	world.push(Object::new(
+		Box::new(Sphere::new(Vec3::new(0.0, -1000.5, -1.0), 1000.0)),
+		Box::new(Lambertian { albedo: Color::all(0.8) })
+	));
+	
+	// The same green glass, at a few thicknesses. Bigger ones get deeper green.
+	let green = Dielectric::tinted(1.5, Color::new(0.4, 0.8, 0.5), 0.3);
+	for (i, radius) in [0.08, 0.15, 0.25, 0.4].into_iter().enumerate() {
+		world.push(Object::new(
+			Box::new(Sphere::new(Point3::new(0.0, radius - 0.5, 1.2 - 0.8 * i as Float), radius)),
+			Box::new(green)
+		));
+	}
+	
+	world
+}
+
+/// Meant for rendering with `--spectral`, so the glass splits light into colors.
+fn dispersion_scene() -> World {
+	let mut world = World::default();
+	
+	world.push(Object::new(
+		Box::new(Sphere::new(Vec3::new(0.0, -1000.5, -1.0), 1000.0)),
+		Box::new(Lambertian { albedo: Color::all(0.8) })
+	));
+	
+	// Dark bars on the ground, so the colored fringes around their edges show up.
+	for i in -6..=6 {
+		let z = i as Float * 0.3;
+		world.push(Object::new(
+			Box::new(Cylinder::new(Point3::new(-4.0, -0.5, z), Point3::new(4.0, -0.5, z), 0.05)),
+			Box::new(Lambertian { albedo: Color::all(0.05) })
+		));
+	}
+	
+	world.push(Object::new(
+		Box::new(Sphere::new(Point3::new(-1.1, 0.0, 0.0), 0.5)),
+		Box::new(Dielectric::dispersive(Dispersion::BK7))
+	));
+	world.push(Object::new(
+		Box::new(Sphere::new(Point3::new(1.1, 0.0, 0.0), 0.5)),
+		Box::new(Dielectric::dispersive(Dispersion::SF11))
+	));
+	
+	// Water doesn't spread colors out much, so it's fine with Cauchy's approximation.
+	world.push(Object::new(
+		Box::new(Sphere::new(Point3::new(0.0, -0.3, 1.0), 0.2)),
+		Box::new(Dielectric::dispersive(Dispersion::Cauchy { a: 1.3199, b: 0.00653 }))
+	));
+	
+	// A brilliant cut, roughly: a cone underneath, and a flat-topped one on top.
+	let pavilion = Cone::new(Point3::new(0.0, 0.0, 0.0), Point3::new(0.0, -0.45, 0.0), 0.5);
+	let crown = Cone::frustum(Point3::new(0.0, 0.0, 0.0), Point3::new(0.0, 0.2, 0.0), 0.5, 0.3);
+	world.push(Object::new(
+		Box::new(Csg::union(Box::new(pavilion), Box::new(crown)).expect("capped cones are closed")),
+		Box::new(Dielectric::dispersive(Dispersion::DIAMOND))
+	));
+	
+	world
+}
+
+fn microfacet_scene() -> World {
+	let mut world = World::default();
+	
+	world.push(Object::new(
+		Box::new(Sphere::new(Vec3::new(0.0, -1000.5, -1.0), 1000.0)),
+		Box::new(Lambertian { albedo: Color::new(0.5, 0.5, 0.55) })
+	));
+	
+	// Metals in the back, getting rougher from left to right.
+	let metals = [ComplexIor::GOLD, ComplexIor::COPPER, ComplexIor::ALUMINIUM, ComplexIor::SILVER];
+	for (i, ior) in metals.into_iter().enumerate() {
+		let x = i as Float * 0.8 - 1.2;
+		world.push(Object::new(
+			Box::new(Sphere::new(Point3::new(x, 0.0, -0.6), 0.35)),
+			Box::new(Conductor { ior, roughness: i as Float * 0.2 })
+		));
+	}
+	
+	// Glass in the front, getting more frosted from left to right.
+	for i in 0..4 {
+		let x = i as Float * 0.8 - 1.2;
+		world.push(Object::new(
+			Box::new(Sphere::new(Point3::new(x, -0.15, 0.5), 0.35)),
+			Box::new(RoughDielectric::new(1.5, i as Float * 0.15))
+		));
+	}
+	
+	world
+}
+
+fn principled_scene() -> World {
+	let mut world = World::default();
+	
+	let floor = Checker { even: Color::all(0.8), odd: Color::all(0.2), size: 0.5 };
+	world.push(Object::new(
+		Box::new(Sphere::new(Vec3::new(0.0, -1000.5, -1.0), 1000.0)),
+		Box::new(Principled::new(floor).roughness(0.3))
+	));
+	
+	let materials = [
+		// Car paint.
+		Principled::new(Color::new(0.6, 0.05, 0.05)).roughness(0.4).clearcoat(1.0).clearcoat_roughness(0.05),
+		// Brushed-ish gold.
+		Principled::new(Color::new(1.0, 0.78, 0.34)).metallic(1.0).roughness(0.25),
+		// Velvet.
+		Principled::new(Color::new(0.15, 0.05, 0.3)).roughness(1.0).sheen(1.0).sheen_tint(0.8),
+		// Polished marble, with the veins a little rougher than the rest.
+		Principled::new(Color::all(0.85)).roughness(Marble::new(4.0)).specular(0.6),
+		// Tinted glass.
+		Principled::new(Color::new(0.7, 0.9, 1.0)).transmission(1.0).roughness(0.05).ior(1.45),
+	];
+	
+	for (i, material) in materials.into_iter().enumerate() {
+		let x = i as Float * 0.6 - 1.2;
+		world.push(Object::new(
+			Box::new(Sphere::new(Point3::new(x, -0.25, 0.0), 0.25)),
+			Box::new(material)
+		));
+	}
+	
+	// Any image works here. Spheres wrap it around like a globe.
+	let globe = load("globe.png", ImageTexture::load);
+	world.push(Object::new(
+		Box::new(Sphere::new(Point3::new(0.0, 0.25, -1.0), 0.75)),
+		Box::new(Principled::new(globe).roughness(0.6).specular_tint(0.5))
+	));
+	
+	world
+}
+
+fn layered_scene() -> World {
+	let mut world = World::default();
+	
+	world.push(Object::new(
+		Box::new(Sphere::new(Vec3::new(0.0, -1000.5, -1.0), 1000.0)),
+		Box::new(Lambertian { albedo: Color::all(0.6) })
+	));
+	
+	// Metallic car paint: a few flakes of aluminium in red paint, under a clear coat.
+	let flakes = Mix::new(
+		Lambertian { albedo: Color::new(0.5, 0.02, 0.02) },
+		Conductor { ior: ComplexIor::ALUMINIUM, roughness: 0.4 },
+		0.1
+	);
+	world.push(Object::new(
+		Box::new(Sphere::new(Point3::new(-0.6, 0.0, 0.0), 0.5)),
+		Box::new(Coated::new(flakes, 1.5))
+	));
+	
+	// Varnished wood: light and dark grain, under a slightly amber coat.
+	let wood = Mix::new(
+		Lambertian { albedo: Color::new(0.55, 0.35, 0.2) },
+		Lambertian { albedo: Color::new(0.3, 0.15, 0.07) },
+		Marble::new(6.0)
+	);
+	world.push(Object::new(
+		Box::new(Sphere::new(Point3::new(0.6, 0.0, 0.0), 0.5)),
+		Box::new(Coated::new(wood, 1.5).roughness(0.1).tint(Color::new(0.95, 0.85, 0.7)))
+	));
+	
+	world
+}
+
+fn bump_scene() -> World {
+	let mut world = World::default();
+	
+	world.push(Object::new(
+		Box::new(Sphere::new(Vec3::new(0.0, -1000.5, -1.0), 1000.0)),
+		Box::new(Lambertian { albedo: Color::all(0.6) })
+	));
+	
+	// Hammered copper, from noise.
+	world.push(Object::new(
+		Box::new(Sphere::new(Point3::new(-0.6, 0.0, 0.0), 0.5)),
+		Box::new(Bumped::new(Conductor { ior: ComplexIor::COPPER, roughness: 0.2 }, Marble::new(8.0), 0.01))
+	));
+	
+	// Any tangent-space normal map works here.
+	let map = load("normal.png", ImageTexture::load);
+	world.push(Object::new(
+		Box::new(Sphere::new(Point3::new(0.6, 0.0, 0.0), 0.5)),
+		Box::new(NormalMapped::new(Principled::new(Color::new(0.7, 0.3, 0.2)).roughness(0.4), map).strength(1.5))
+	));
+	
+	world
+}
+
+fn mask_scene() -> World {
+	let mut world = World::default();
+	
+	world.push(Object::new(
+		Box::new(Sphere::new(Vec3::new(0.0, -1000.5, -1.0), 1000.0)),
+		Box::new(Lambertian { albedo: Color::all(0.6) })
+	));
+	
+	// A cage: a sphere with checkered holes cut out of it, and another sphere inside.
+	let holes = Checker { even: 1.0, odd: 0.0, size: 0.15 };
+	world.push(Object::new(
+		Box::new(Sphere::new(Point3::new(-0.6, 0.0, 0.0), 0.5)),
+		Box::new(Lambertian { albedo: Color::new(0.2, 0.3, 0.6) })
+	).with_mask(AlphaMask::cutoff(holes, 0.5)));
+	world.push(Object::new(
+		Box::new(Sphere::new(Point3::new(-0.6, 0.0, 0.0), 0.25)),
+		Box::new(Lambertian { albedo: Color::new(0.8, 0.6, 0.2) })
+	));
+	
+	// A ghost, that's only half there.
+	world.push(Object::new(
+		Box::new(Sphere::new(Point3::new(0.6, 0.0, 0.0), 0.5)),
+		Box::new(Lambertian { albedo: Color::new(0.9, 0.9, 0.9) })
+	).with_mask(AlphaMask::stochastic(0.4)));
+	
+	world
+}
+
+fn subsurface_scene() -> World {
+	let mut world = World::default();
+	
+	world.push(Object::new(
+		Box::new(Sphere::new(Vec3::new(0.0, -1000.5, -1.0), 1000.0)),
+		Box::new(Lambertian { albedo: Color::all(0.6) })
+	));
+	
+	// Each unit is a few millimeters here, so light doesn't get very far in.
+	let materials = [
+		Subsurface::marble(4.0),
+		Subsurface::skin(4.0),
+		Subsurface::ketchup(4.0),
+		Subsurface::whole_milk(4.0),
+		// Wax, scattering mostly forwards.
+		Subsurface::new(1.45, Color::new(0.2, 0.5, 1.5), Color::all(8.0)).g(0.6),
+	];
+	
+	for (i, material) in materials.into_iter().enumerate() {
+		let x = i as Float * 0.6 - 1.2;
+		world.push(Object::new(
+			Box::new(Sphere::new(Point3::new(x, -0.25, 0.0), 0.25)),
+			Box::new(material)
+		));
+	}
+	
+	world
+}
+
+fn bubble_scene() -> World {
+	let mut world = World::default();
+	
+	world.push(Object::new(
+		Box::new(Sphere::new(Vec3::new(0.0, -1000.5, -1.0), 1000.0)),
+		Box::new(Lambertian { albedo: Color::all(0.3) })
+	));
+	
+	// Soap films swirl around between a few hundred nanometers thick and a lot thinner.
+	let swirls = Remapped { texture: Marble::new(2.0), min: 150.0, max: 700.0 };
+	world.push(Object::new(
+		Box::new(Sphere::new(Point3::new(-0.5, 0.1, 0.0), 0.5)),
+		Box::new(ThinDielectric::new(1.33).film(swirls))
+	));
+	world.push(Object::new(
+		Box::new(Sphere::new(Point3::new(0.55, -0.15, 0.3), 0.3)),
+		Box::new(ThinDielectric::new(1.33).film(400.0))
+	));
+	
+	// A sheet of glass, without the interference.
+	world.push(Object::new(
+		Box::new(Cylinder::new(Point3::new(0.0, -0.5, -1.0), Point3::new(0.0, 0.7, -1.0), 0.8).uncapped()),
+		Box::new(ThinDielectric::new(1.5))
+	));
+	world.push(Object::new(
+		Box::new(Sphere::new(Point3::new(0.0, -0.2, -1.0), 0.3)),
+		Box::new(Lambertian { albedo: Color::new(0.8, 0.2, 0.2) })
+	));
+	
+	world
+}
+
+fn brushed_scene() -> World {
+	let mut world = World::default();
+	
+	world.push(Object::new(
+		Box::new(Sphere::new(Vec3::new(0.0, -1000.5, -1.0), 1000.0)),
+		Box::new(Lambertian { albedo: Color::all(0.4) })
+	));
+	
+	// Spheres' `u` goes around the equator, so these are brushed one way, then the other,
+	// then every which way.
+	let swirls = Remapped { texture: Marble::new(3.0), min: 0.0, max: 180.0 };
+	let materials = [
+		AnisotropicConductor::new(ComplexIor::ALUMINIUM, 0.05, 0.5),
+		AnisotropicConductor::new(ComplexIor::ALUMINIUM, 0.5, 0.05),
+		AnisotropicConductor::new(ComplexIor::COPPER, 0.05, 0.4).rotation(swirls),
+	];
+	
+	for (i, material) in materials.into_iter().enumerate() {
+		let z = 1.1 - i as Float * 1.1;
+		world.push(Object::new(
+			Box::new(Sphere::new(Point3::new(0.0, 0.0, z), 0.5)),
+			Box::new(material)
+		));
+	}
+	
+	world
+}
+
+fn environment_scene() -> World {
+	let mut world = World::default();
+	
+	world.environment = Box::new(
+		load("environment.hdr", EnvironmentMap::load).rotation(90.0).intensity(1.5)
+	);
+	
+	world.push(Object::new(
+		Box::new(Sphere::new(Vec3::new(0.0, -1000.5, -1.0), 1000.0)),
+		Box::new(Lambertian { albedo: Color::all(0.5) })
+	));
+	
+	world.push(Object::new(
+		Box::new(Sphere::new(Point3::new(0.0, 0.0, 1.1), 0.5)),
+		Box::new(Lambertian { albedo: Color::new(0.8, 0.3, 0.3) })
+	));
+	world.push(Object::new(
+		Box::new(Sphere::new(Point3::new(0.0, 0.0, 0.0), 0.5)),
+		Box::new(Conductor { ior: ComplexIor::GOLD, roughness: 0.3 })
+	));
+	world.push(Object::new(
+		Box::new(Sphere::new(Point3::new(0.0, 0.0, -1.1), 0.5)),
+		Box::new(Dielectric::new(1.5))
+	));
+	
+	world
+}
+
+fn sky_scene() -> World {
+	let mut world = World::default();
+	
+	// An early summer morning in Boulder, Colorado.
+	world.environment = Box::new(Sky::at(40.0, -105.3, 172.0, 14.5, 3.0).intensity(0.03));
+	
+	world.push(Object::new(
+		Box::new(Sphere::new(Vec3::new(0.0, -1000.5, -1.0), 1000.0)),
+		Box::new(Lambertian { albedo: Color::all(0.5) })
+	));
+	
+	world.push(Object::new(
+		Box::new(Cylinder::new(Point3::new(-1.0, -0.5, -1.5), Point3::new(-1.0, 2.0, -1.5), 0.3)),
+		Box::new(Lambertian { albedo: Color::all(0.8) })
+	));
+	world.push(Object::new(
+		Box::new(Sphere::new(Point3::new(0.0, 0.0, 0.6), 0.5)),
+		Box::new(Lambertian { albedo: Color::new(0.7, 0.6, 0.5) })
+	));
+	world.push(Object::new(
+		Box::new(Sphere::new(Point3::new(0.0, 0.0, -0.6), 0.5)),
+		Box::new(Conductor { ior: ComplexIor::ALUMINIUM, roughness: 0.1 })
+	));
+	
+	world
+}
+
+fn lights_scene() -> World {
+	let mut world = World::default();
+	
+	// Nighttime, so the lights do all the work.
+	world.environment = Box::new(Gradient { bottom: Color::ZERO, top: Color::new(0.005, 0.01, 0.02) });
+	
+	world.push(Object::new(
+		Box::new(Sphere::new(Vec3::new(0.0, -1000.5, -1.0), 1000.0)),
+		Box::new(Lambertian { albedo: Color::all(0.5) })
+	));
+	
+	for (i, z) in [-1.2, 0.0, 1.2].into_iter().enumerate() {
+		let material: Box<dyn Material + Send + Sync> = match i {
+			0 => Box::new(Lambertian { albedo: Color::new(0.8, 0.3, 0.3) }),
+			1 => Box::new(Conductor { ior: ComplexIor::GOLD, roughness: 0.3 }),
+			_ => Box::new(Lambertian { albedo: Color::new(0.3, 0.3, 0.8) }),
+		};
+		world.push(Object::new(Box::new(Sphere::new(Point3::new(0.0, 0.0, z), 0.5)), material));
+	}
+	
+	// Moonlight.
+	world.push_light(Box::new(DirectionalLight {
+		direction: Vec3::new(-1.0, -2.0, 1.0),
+		irradiance: Color::new(0.1, 0.12, 0.2),
+	}));
+	// A warm lamp, and a spotlight shining down on the gold ball.
+	world.push_light(Box::new(PointLight {
+		position: Point3::new(1.5, 1.0, -2.0),
+		intensity: Color::new(3.0, 2.0, 1.0),
+	}));
+	world.push_light(Box::new(SpotLight {
+		position: Point3::new(0.5, 3.0, 0.0),
+		direction: Vec3::new(-0.5, -3.0, 0.0),
+		intensity: Color::all(20.0),
+		inner_angle: 10.0,
+		outer_angle: 15.0,
+	}));
+	
+	world
+}
+
+fn many_lights_scene() -> World {
+	let mut world = World::default();
+	
+	// Dark out, so it's all lit by the balls.
+	world.environment = Box::new(Gradient { bottom: Color::ZERO, top: Color::new(0.005, 0.01, 0.02) });
+	
+	world.push(Object::new(
+		Box::new(Sphere::new(Vec3::new(0.0, -1000.5, -1.0), 1000.0)),
+		Box::new(Lambertian { albedo: Color::all(0.5) })
+	));
+	
+	// Same balls as the basic scene, but some of them glow.
+	for i in -8..=8 {
+		for j in -8..=8 {
+			let color = (Vec3::ONE + util::random_color()) / 2.0;
+			world.push(Object::new(
+				Box::new(Sphere::new(Vec3::new(i as Float, 0.5 + (i as Float).sin() * (j as Float).cos(), j as Float) / 2.0, 0.2)),
+				if util::random_float() < 0.2 {
+					Box::new(DiffuseLight { emit: color * 4.0 })
+				} else {
+					Box::new(Lambertian { albedo: color })
+				}
+			));
+		}
+	}
+	
+	world.push(Object::new(
+		Box::new(Sphere::new(Vec3::ZERO, 0.5)),
+		Box::new(Conductor { ior: ComplexIor::GOLD, roughness: 0.2 })
+	));
+	
+	world
+}
+
+/// Meant for rendering with the bidirectional integrator, since the only
+/// light is a tiny ball that the path tracer hardly ever finds through the glass.
+fn caustics_scene() -> World {
+	let mut world = World::default();
+	
+	world.environment = Box::new(Gradient { bottom: Color::ZERO, top: Color::new(0.005, 0.01, 0.02) });
+	
+	world.push(Object::new(
+		Box::new(Sphere::new(Vec3::new(0.0, -1000.5, -1.0), 1000.0)),
+		Box::new(Lambertian { albedo: Color::all(0.8) })
+	));
+	
+	world.push(Object::new(
+		Box::new(Sphere::new(Point3::new(0.0, -0.1, -0.3), 0.4)),
+		Box::new(Dielectric::new(1.5))
+	));
+	world.push(Object::new(
+		Box::new(Sphere::new(Point3::new(0.3, -0.3, 0.6), 0.2)),
+		Box::new(Dielectric::tinted(1.5, Color::new(0.9, 0.6, 0.3), 0.2))
+	));
+	
+	// Up out of view, behind the glass.
+	world.push(Object::new(
+		Box::new(Sphere::new(Point3::new(-1.5, 1.5, -0.8), 0.05)),
+		Box::new(DiffuseLight { emit: Color::new(1.0, 0.9, 0.8) * 1000.0 })
+	));
+	
+	world
+}
+
+/// A room with one side open to the camera, lit by a lamp that's shaded so
+/// it only shines on the ceiling. Meant for the bidirectional integrator too.
+fn room_scene() -> World {
+	let mut world = World::default();
+	
+	world.environment = Box::new(Gradient { bottom: Color::ZERO, top: Color::ZERO });
+	
+	let white = Lambertian { albedo: Color::all(0.75) };
+	world.push(Object::new(
+		Box::new(Sphere::new(Vec3::new(0.0, -1000.5, -1.0), 1000.0)),
+		Box::new(white)
+	));
+	
+	// The walls and ceiling are thin slabs.
+	let walls: [(Point3, Point3, Color); 4] = [
+		(Point3::new(-1.1, 0.2, 0.0), Point3::new(-1.0, 0.2, 0.0), Color::all(0.75)),
+		(Point3::new(0.0, 0.2, -1.1), Point3::new(0.0, 0.2, -1.0), Color::new(0.75, 0.25, 0.25)),
+		(Point3::new(0.0, 0.2, 1.0), Point3::new(0.0, 0.2, 1.1), Color::new(0.25, 0.75, 0.25)),
+		(Point3::new(0.0, 0.9, 0.0), Point3::new(0.0, 1.0, 0.0), Color::all(0.75)),
+	];
+	for (a, b, albedo) in walls {
+		world.push(Object::new(
+			Box::new(Cylinder::new(a, b, 1.5)),
+			Box::new(Lambertian { albedo })
+		));
+	}
+	
+	world.push(Object::new(
+		Box::new(Sphere::new(Point3::new(0.2, -0.15, 0.4), 0.35)),
+		Box::new(Dielectric::new(1.5))
+	));
+	world.push(Object::new(
+		Box::new(Sphere::new(Point3::new(-0.4, -0.2, -0.4), 0.3)),
+		Box::new(Lambertian { albedo: Color::new(0.3, 0.4, 0.8) })
+	));
+	
+	// The lamp, with a shade underneath it.
+	world.push(Object::new(
+		Box::new(Sphere::new(Point3::new(-0.5, 0.75, 0.0), 0.08)),
+		Box::new(DiffuseLight { emit: Color::new(1.0, 0.85, 0.7) * 100.0 })
+	));
+	world.push(Object::new(
+		Box::new(Cylinder::new(Point3::new(-0.5, 0.62, 0.0), Point3::new(-0.5, 0.64, 0.0), 0.3)),
+		Box::new(white)
+	));
+	
+	world
+}
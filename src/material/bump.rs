@@ -0,0 +1,112 @@
+use crate::vector::{Vec3, Point2, Color, Float};
+use crate::ray::{Ray, HitInfo};
+use crate::medium::Interior;
+use crate::texture::{Texture, BoxedTexture, ImageTexture};
+
+use super::Material;
+
+/// Get the normal of a hit pointing out of the solid, no matter which side it was hit from.
+fn outward_normal(hit: &HitInfo) -> Vec3 {
+	if hit.front_face { hit.normal } else { -hit.normal }
+}
+
+/// Swap in a new outward normal, flipping it to face the same side the old one did.
+fn with_outward_normal(hit: HitInfo, outward_normal: Vec3) -> HitInfo {
+	let normal = if hit.front_face { outward_normal } else { -outward_normal };
+	HitInfo { normal, ..hit }
+}
+
+/// Gives a `base` material some bumpy detail from a tangent-space normal map,
+/// where red, green and blue go along `u`, along `v`, and straight out of the surface.
+pub struct NormalMapped {
+	pub base: Box<dyn Material + Send + Sync>,
+	pub map: ImageTexture,
+	/// Scales how far the normals lean. 1 is what the map says.
+	pub strength: Float,
+}
+impl NormalMapped {
+	pub fn new(base: impl Material + Send + Sync + 'static, map: ImageTexture) -> Self {
+		Self { base: Box::new(base), map, strength: 1.0 }
+	}
+	
+	pub const fn strength(mut self, strength: Float) -> Self {
+		self.strength = strength; self
+	}
+	
+	fn mapped(&self, hit: HitInfo) -> HitInfo {
+		let n = outward_normal(&hit);
+		
+		// Straighten the tangents out so they're at right angles to the normal.
+		let along_u = hit.dpdu - n * n.dot(hit.dpdu);
+		if along_u.squared_magnitude() == 0.0 { return hit; }
+		let tangent = along_u.normalize();
+		let bitangent = Vec3::cross(n, tangent);
+		// Mirrored uvs go the other way around.
+		let bitangent = if bitangent.dot(hit.dpdv) < 0.0 { -bitangent } else { bitangent };
+		
+		let c = 2.0 * self.map.raw(hit.uv) - Color::ONE;
+		let mapped = self.strength * (c.x * tangent + c.y * bitangent) + c.z * n;
+		
+		with_outward_normal(hit, mapped.normalize())
+	}
+}
+impl Material for NormalMapped {
+	fn scatter(&self, ray: Ray, hit: HitInfo) -> Option<(Color, Ray)> {
+		self.base.scatter(ray, self.mapped(hit))
+	}
+	
+	fn eval(&self, ray: Ray, hit: HitInfo, direction: Vec3) -> Option<(Color, Float)> {
+		self.base.eval(ray, self.mapped(hit), direction)
+	}
+	
+	fn interior(&self) -> Option<Interior> {
+		self.base.interior()
+	}
+}
+
+/// Gives a `base` material some bumpy detail from a height texture,
+/// by tilting the normal the way the heights slope.
+pub struct Bumped {
+	pub base: Box<dyn Material + Send + Sync>,
+	pub height: BoxedTexture<Float>,
+	/// How far a height of 1 sticks out, relative to the size of the whole texture.
+	pub scale: Float,
+}
+impl Bumped {
+	pub fn new(base: impl Material + Send + Sync + 'static, height: impl Texture<Float> + Send + Sync + 'static, scale: Float) -> Self {
+		Self { base: Box::new(base), height: Box::new(height), scale }
+	}
+	
+	fn bumped(&self, hit: HitInfo) -> HitInfo {
+		let n = outward_normal(&hit);
+		
+		// Find the slope by stepping a little bit along `u` and `v`.
+		const STEP: Float = 1e-3;
+		let height_at = |du: Float, dv: Float| self.height.value(
+			hit.uv + Point2::new(du, dv),
+			hit.position + du * hit.dpdu + dv * hit.dpdv
+		);
+		let h = height_at(0.0, 0.0);
+		let dhdu = self.scale * (height_at(STEP, 0.0) - h) / STEP;
+		let dhdv = self.scale * (height_at(0.0, STEP) - h) / STEP;
+		
+		// Push the surface out along the normal by the height, and see which way it faces then.
+		let bumped = Vec3::cross(hit.dpdu + dhdu * n, hit.dpdv + dhdv * n).normalize();
+		let bumped = if bumped.dot(n) < 0.0 { -bumped } else { bumped };
+		
+		with_outward_normal(hit, bumped)
+	}
+}
+impl Material for Bumped {
+	fn scatter(&self, ray: Ray, hit: HitInfo) -> Option<(Color, Ray)> {
+		self.base.scatter(ray, self.bumped(hit))
+	}
+	
+	fn eval(&self, ray: Ray, hit: HitInfo, direction: Vec3) -> Option<(Color, Float)> {
+		self.base.eval(ray, self.bumped(hit), direction)
+	}
+	
+	fn interior(&self) -> Option<Interior> {
+		self.base.interior()
+	}
+}
@@ -0,0 +1,102 @@
+use crate::vector::{Vec3, Color, Float};
+use crate::ray::{Ray, HitInfo};
+use crate::microfacet::{self, Frame, Ggx};
+use crate::util;
+
+use super::Material;
+
+/// A clear coat of varnish or lacquer over some other material.
+/// 
+/// Light either bounces off the coat, as much as the Fresnel term says, or goes
+/// through it to the `base`. Anything the base scatters has to make it back out
+/// through the coat, getting tinted on the way in and out.
+pub struct Coated {
+	pub base: Box<dyn Material + Send + Sync>,
+	pub refractive_index: Float,
+	/// From 0 (perfectly smooth) to 1 (totally matte).
+	pub roughness: Float,
+	/// What's left after light goes straight through the coat once.
+	/// Light coming in at an angle goes through more of it.
+	pub tint: Color,
+}
+impl Coated {
+	pub fn new(base: impl Material + Send + Sync + 'static, refractive_index: Float) -> Self {
+		Self { base: Box::new(base), refractive_index, roughness: 0.0, tint: Color::ONE }
+	}
+	
+	pub const fn roughness(mut self, roughness: Float) -> Self {
+		self.roughness = roughness; self
+	}
+	
+	pub const fn tint(mut self, tint: Color) -> Self {
+		self.tint = tint; self
+	}
+}
+impl Coated {
+	/// What's left of light that went through the coat to the base and back out, at `cos_o` and `cos_i`
+	/// to the normal, after some got reflected back in on its way out (call that lost) and tinted.
+	fn through_coat(&self, cos_o: Float, cos_i: Float, eta: Float) -> Color {
+		let escaped = 1.0 - microfacet::fresnel_dielectric(cos_i, eta);
+		
+		// How far it went through the coat, compared to going straight through.
+		let distance = cos_o.recip() + cos_i.recip();
+		let tint = Color::new(self.tint.x.powf(distance), self.tint.y.powf(distance), self.tint.z.powf(distance));
+		
+		tint * escaped
+	}
+}
+impl Material for Coated {
+	fn scatter(&self, ray: Ray, hit: HitInfo) -> Option<(Color, Ray)> {
+		// Light hitting the coat from inside the object is going from the coat out into the air.
+		let eta = if hit.front_face { self.refractive_index.recip() } else { self.refractive_index };
+		let frame = Frame::new(hit.normal);
+		let ggx = Ggx::from_roughness(self.roughness);
+		
+		let wo = frame.to_local(-ray.direction.normalize());
+		
+		// Pick the coat by how much it reflects on the whole, so that eval can tell how likely it was.
+		// Then the microfacet it bounces off of might reflect more or less than that.
+		let coat = microfacet::fresnel_dielectric(wo.z, eta);
+		if util::random_float() < coat {
+			let m = ggx.sample_visible(wo);
+			let wi = (-wo).reflect(m);
+			if wi.z <= 0.0 { return None; }
+			
+			let weight = microfacet::fresnel_dielectric(wo.dot(m), eta) / coat * ggx.g2(wo, wi) / ggx.g1(wo);
+			let scattered = Ray::new(hit.position, frame.to_world(wi), ray.time);
+			return Some((Color::all(weight), scattered));
+		}
+		
+		let (attenuation, scattered) = self.base.scatter(ray, hit)?;
+		let cos_i = Vec3::dot(scattered.direction.normalize(), hit.normal);
+		// The base sent it back into the surface (like glass would), so it never comes back out of the coat.
+		if cos_i <= 0.0 { return Some((attenuation, scattered)); }
+		
+		Some((attenuation * self.through_coat(wo.z, cos_i, eta), scattered))
+	}
+	
+	/// Only works if the base can be looked up too.
+	fn eval(&self, ray: Ray, hit: HitInfo, direction: Vec3) -> Option<(Color, Float)> {
+		let (base, base_pdf) = self.base.eval(ray, hit, direction)?;
+		
+		let eta = if hit.front_face { self.refractive_index.recip() } else { self.refractive_index };
+		let frame = Frame::new(hit.normal);
+		let ggx = Ggx::from_roughness(self.roughness);
+		
+		let wo = frame.to_local(-ray.direction.normalize());
+		let wi = frame.to_local(direction.normalize());
+		if wo.z <= 0.0 { return Some((Color::ZERO, 0.0)); }
+		
+		let coat = microfacet::fresnel_dielectric(wo.z, eta);
+		if wi.z <= 0.0 {
+			return Some((base * (1.0 - coat), base_pdf * (1.0 - coat)));
+		}
+		
+		let (coat_pdf, m) = ggx.reflection_pdf(wo, wi);
+		let reflected = coat_pdf * microfacet::fresnel_dielectric(wo.dot(m), eta) * ggx.g2(wo, wi) / ggx.g1(wo);
+		
+		let value = Color::all(reflected) + base * self.through_coat(wo.z, wi.z, eta) * (1.0 - coat);
+		let pdf = coat * coat_pdf + (1.0 - coat) * base_pdf;
+		Some((value, pdf))
+	}
+}
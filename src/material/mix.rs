@@ -0,0 +1,46 @@
+use crate::vector::{Vec3, Color, Float};
+use crate::ray::{Ray, HitInfo};
+use crate::medium::Interior;
+use crate::texture::{Texture, BoxedTexture};
+use crate::util;
+
+use super::Material;
+
+/// Randomly picks between two materials for each ray, by a `weight` that
+/// can change across the surface. A weight of 0 is all `a`, and 1 is all `b`.
+pub struct Mix {
+	pub a: Box<dyn Material + Send + Sync>,
+	pub b: Box<dyn Material + Send + Sync>,
+	pub weight: BoxedTexture<Float>,
+}
+impl Mix {
+	pub fn new(
+		a: impl Material + Send + Sync + 'static,
+		b: impl Material + Send + Sync + 'static,
+		weight: impl Texture<Float> + Send + Sync + 'static
+	) -> Self {
+		Self { a: Box::new(a), b: Box::new(b), weight: Box::new(weight) }
+	}
+}
+impl Material for Mix {
+	fn scatter(&self, ray: Ray, hit: HitInfo) -> Option<(Color, Ray)> {
+		if util::random_float() < self.weight.value(hit.uv, hit.position) {
+			self.b.scatter(ray, hit)
+		} else {
+			self.a.scatter(ray, hit)
+		}
+	}
+	
+	/// Only works if both materials can be looked up, since it could've been either one.
+	fn eval(&self, ray: Ray, hit: HitInfo, direction: Vec3) -> Option<(Color, Float)> {
+		let weight = self.weight.value(hit.uv, hit.position);
+		let (a, a_pdf) = self.a.eval(ray, hit, direction)?;
+		let (b, b_pdf) = self.b.eval(ray, hit, direction)?;
+		Some((Color::lerp(a, b, weight), Float::mul_add(b_pdf - a_pdf, weight, a_pdf)))
+	}
+	
+	/// There's no way to mix what's inside, so this just goes with whichever has something.
+	fn interior(&self) -> Option<Interior> {
+		self.a.interior().or(self.b.interior())
+	}
+}
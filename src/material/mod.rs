@@ -0,0 +1,534 @@
+use crate::vector::{Vec3, Color, Float};
+use crate::ray::{Ray, HitInfo};
+use crate::medium::{self, Interior};
+use crate::microfacet::{Frame, Ggx, ComplexIor};
+use crate::microfacet;
+use crate::texture::{Texture, BoxedTexture};
+use crate::util; // Because then it's obvious that "HEY THIS IS FROM UTIL!"
+
+use std::sync::Arc;
+
+pub trait Material {
+	fn scatter(&self, ray: Ray, hit: HitInfo) -> Option<(Color, Ray)>;
+	
+	/// How much light coming in from `direction` gets sent back along the [Ray] (with the
+	/// cosine already in it), and the pdf of [Material::scatter] picking that direction.
+	/// 
+	/// This lets the raytracer go looking for light, instead of waiting to bump into it.
+	/// Materials that only scatter in a few exact directions (like mirrors and glass) can't
+	/// be looked up like this, so they leave it as `None`.
+	fn eval(&self, _ray: Ray, _hit: HitInfo, _direction: Vec3) -> Option<(Color, Float)> { None }
+	
+	/// How much light it gives off from the side of the surface `hit` is on.
+	fn emitted(&self, _hit: &HitInfo) -> Color { Color::ZERO }
+	
+	/// The most light it gives off anywhere, for figuring out which lights are worth looking at.
+	/// This should only be zero if it doesn't glow at all.
+	fn max_emitted(&self) -> Color { Color::ZERO }
+	
+	/// What's inside objects made of this material, for ones that let light through.
+	fn interior(&self) -> Option<Interior> { None }
+}
+
+/// Lets one material get shared between a bunch of [Object](crate::world::Object)s.
+impl<M: Material + ?Sized> Material for Arc<M> {
+	fn scatter(&self, ray: Ray, hit: HitInfo) -> Option<(Color, Ray)> {
+		M::scatter(self, ray, hit)
+	}
+	
+	fn eval(&self, ray: Ray, hit: HitInfo, direction: Vec3) -> Option<(Color, Float)> {
+		M::eval(self, ray, hit, direction)
+	}
+	
+	fn emitted(&self, hit: &HitInfo) -> Color {
+		M::emitted(self, hit)
+	}
+	
+	fn max_emitted(&self) -> Color {
+		M::max_emitted(self)
+	}
+	
+	fn interior(&self) -> Option<Interior> {
+		M::interior(self)
+	}
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Lambertian {
+	pub albedo: Color,
+}
+impl Material for Lambertian {
+	fn scatter(&self, ray: Ray, hit: HitInfo) -> Option<(Color, Ray)> {
+		let scatter_direction = hit.normal + util::random_unit_vector();
+		let scattered = Ray::new(hit.position, scatter_direction, ray.time);
+		let attenuation = self.albedo;
+		
+		Some((attenuation, scattered))
+	}
+	
+	fn eval(&self, _ray: Ray, hit: HitInfo, direction: Vec3) -> Option<(Color, Float)> {
+		let pdf = hit.normal.dot(direction.normalize()).max(0.0) / std::f64::consts::PI as Float;
+		Some((self.albedo * pdf, pdf))
+	}
+}
+
+/// Glows evenly out of the front of the surface, and doesn't reflect anything.
+#[derive(Copy, Clone, Debug)]
+pub struct DiffuseLight {
+	pub emit: Color,
+}
+impl Material for DiffuseLight {
+	fn scatter(&self, _ray: Ray, _hit: HitInfo) -> Option<(Color, Ray)> {
+		None
+	}
+	
+	fn emitted(&self, hit: &HitInfo) -> Color {
+		if hit.front_face { self.emit } else { Color::ZERO }
+	}
+	
+	fn max_emitted(&self) -> Color {
+		self.emit
+	}
+}
+
+/// Scatters light evenly in every direction, for the insides of
+/// [ConstantMedium](crate::solid::constant_medium::ConstantMedium)s.
+#[derive(Copy, Clone, Debug)]
+pub struct Isotropic {
+	pub albedo: Color,
+}
+impl Material for Isotropic {
+	fn scatter(&self, ray: Ray, hit: HitInfo) -> Option<(Color, Ray)> {
+		let scattered = Ray::new(hit.position, util::random_unit_vector(), ray.time);
+		let attenuation = self.albedo;
+		
+		Some((attenuation, scattered))
+	}
+	
+	fn eval(&self, _ray: Ray, _hit: HitInfo, _direction: Vec3) -> Option<(Color, Float)> {
+		let pdf = (4.0 * std::f64::consts::PI as Float).recip();
+		Some((self.albedo * pdf, pdf))
+	}
+}
+
+/// Scatters light mostly forwards (when `g` is positive) or mostly backwards
+/// (when it's negative), like the particles in clouds and smoke.
+/// See [medium::sample_henyey_greenstein].
+#[derive(Copy, Clone, Debug)]
+pub struct HenyeyGreenstein {
+	pub albedo: Color,
+	pub g: Float,
+}
+impl Material for HenyeyGreenstein {
+	fn scatter(&self, ray: Ray, hit: HitInfo) -> Option<(Color, Ray)> {
+		let direction = medium::sample_henyey_greenstein(ray.direction, self.g);
+		let scattered = Ray::new(hit.position, direction, ray.time);
+		let attenuation = self.albedo;
+		
+		Some((attenuation, scattered))
+	}
+	
+	fn eval(&self, ray: Ray, _hit: HitInfo, direction: Vec3) -> Option<(Color, Float)> {
+		let pdf = medium::henyey_greenstein(ray.direction.normalize().dot(direction.normalize()), self.g);
+		Some((self.albedo * pdf, pdf))
+	}
+}
+
+/// A quick and dirty metal. It's not physically based: the `fuzz` can send light
+/// into the surface, and it reflects the same amount at every angle.
+/// For something more realistic, use a [Conductor].
+#[derive(Copy, Clone, Debug)]
+pub struct Metal {
+	pub albedo: Color,
+	pub fuzz: Float,
+}
+impl Material for Metal {
+	fn scatter(&self, ray: Ray, hit: HitInfo) -> Option<(Color, Ray)> {
+		let reflected = ray.direction.normalize().reflect(hit.normal);
+		let scattered = Ray::new(hit.position, reflected + self.fuzz * util::random_in_unit_sphere(), ray.time);
+		let attenuation = self.albedo;
+		
+		Some((attenuation, scattered))
+	}
+}
+
+/// A rough metal, using the GGX microfacet model.
+/// 
+/// Its color comes from its complex refractive index, so it gets
+/// brighter and whiter towards grazing angles, like real metal does.
+#[derive(Copy, Clone, Debug)]
+pub struct Conductor {
+	pub ior: ComplexIor,
+	/// From 0 (a perfect mirror) to 1 (totally matte).
+	pub roughness: Float,
+}
+impl Material for Conductor {
+	fn scatter(&self, ray: Ray, hit: HitInfo) -> Option<(Color, Ray)> {
+		let frame = Frame::new(hit.normal);
+		let ggx = Ggx::from_roughness(self.roughness);
+		
+		let wo = frame.to_local(-ray.direction.normalize());
+		let m = ggx.sample_visible(wo);
+		let wi = (-wo).reflect(m);
+		// Bounced off a microfacet and into the surface, so it gets lost.
+		if wi.z <= 0.0 { return None; }
+		
+		let attenuation = self.ior.fresnel(wo.dot(m)) * ggx.g2(wo, wi) / ggx.g1(wo);
+		let scattered = Ray::new(hit.position, frame.to_world(wi), ray.time);
+		
+		Some((attenuation, scattered))
+	}
+	
+	fn eval(&self, ray: Ray, hit: HitInfo, direction: Vec3) -> Option<(Color, Float)> {
+		let ggx = Ggx::from_roughness(self.roughness);
+		Some(eval_conductor(ggx, self.ior, Frame::new(hit.normal), ray, direction))
+	}
+}
+
+/// [Material::eval] for metals that pick directions with [Ggx::sample_visible].
+fn eval_conductor(ggx: Ggx, ior: ComplexIor, frame: Frame, ray: Ray, direction: Vec3) -> (Color, Float) {
+	let wo = frame.to_local(-ray.direction.normalize());
+	let wi = frame.to_local(direction.normalize());
+	
+	let (pdf, m) = ggx.reflection_pdf(wo, wi);
+	if pdf == 0.0 { return (Color::ZERO, 0.0); }
+	
+	// Which is just the pdf times the weight that scatter gives it.
+	(ior.fresnel(wo.dot(m)) * pdf * ggx.g2(wo, wi) / ggx.g1(wo), pdf)
+}
+
+/// Brushed or machined metal, which is rougher in one direction than the other.
+/// 
+/// `roughness_u` goes along the surface's `u` direction (see [HitInfo::dpdu]),
+/// after spinning it around by `rotation` degrees.
+pub struct AnisotropicConductor {
+	pub ior: ComplexIor,
+	pub roughness_u: Float,
+	pub roughness_v: Float,
+	pub rotation: BoxedTexture<Float>,
+}
+impl AnisotropicConductor {
+	pub fn new(ior: ComplexIor, roughness_u: Float, roughness_v: Float) -> Self {
+		Self { ior, roughness_u, roughness_v, rotation: Box::new(0.0) }
+	}
+	
+	pub fn rotation(mut self, degrees: impl Texture<Float> + Send + Sync + 'static) -> Self {
+		self.rotation = Box::new(degrees); self
+	}
+	
+	fn frame(&self, hit: &HitInfo) -> Frame {
+		Frame::from_tangent(hit.normal, hit.dpdu).rotated(self.rotation.value(hit.uv, hit.position))
+	}
+}
+impl Material for AnisotropicConductor {
+	fn scatter(&self, ray: Ray, hit: HitInfo) -> Option<(Color, Ray)> {
+		let frame = self.frame(&hit);
+		let ggx = Ggx::anisotropic(self.roughness_u, self.roughness_v);
+		
+		let wo = frame.to_local(-ray.direction.normalize());
+		let m = ggx.sample_visible(wo);
+		let wi = (-wo).reflect(m);
+		if wi.z <= 0.0 { return None; }
+		
+		let attenuation = self.ior.fresnel(wo.dot(m)) * ggx.g2(wo, wi) / ggx.g1(wo);
+		let scattered = Ray::new(hit.position, frame.to_world(wi), ray.time);
+		
+		Some((attenuation, scattered))
+	}
+	
+	fn eval(&self, ray: Ray, hit: HitInfo, direction: Vec3) -> Option<(Color, Float)> {
+		let ggx = Ggx::anisotropic(self.roughness_u, self.roughness_v);
+		Some(eval_conductor(ggx, self.ior, self.frame(&hit), ray, direction))
+	}
+}
+
+/// Frosted glass, using the GGX microfacet model. With a `roughness` of 0,
+/// it's the same as a [Dielectric].
+#[derive(Copy, Clone, Debug)]
+pub struct RoughDielectric {
+	pub refractive_index: Float,
+	/// From 0 (perfectly clear) to 1 (completely frosted).
+	pub roughness: Float,
+	pub interior: Option<Interior>,
+}
+impl RoughDielectric {
+	pub const fn new(refractive_index: Float, roughness: Float) -> Self {
+		Self { refractive_index, roughness, interior: None }
+	}
+}
+impl Material for RoughDielectric {
+	fn scatter(&self, ray: Ray, hit: HitInfo) -> Option<(Color, Ray)> {
+		let eta = if hit.front_face { self.refractive_index.recip() } else { self.refractive_index };
+		let frame = Frame::new(hit.normal);
+		let ggx = Ggx::from_roughness(self.roughness);
+		
+		let wo = frame.to_local(-ray.direction.normalize());
+		let m = ggx.sample_visible(wo);
+		let cos_o = wo.dot(m);
+		
+		// Reflect or refract off of the microfacet, by how much the Fresnel term says gets reflected.
+		let wi = if util::random_float() < microfacet::fresnel_dielectric(cos_o, eta) {
+			let wi = (-wo).reflect(m);
+			if wi.z <= 0.0 { return None; }
+			wi
+		} else {
+			let wi = util::refract(-wo, m, eta);
+			if wi.z >= 0.0 { return None; }
+			wi
+		};
+		
+		let attenuation = Color::all(ggx.g2(wo, wi) / ggx.g1(wo));
+		let scattered = Ray::new(hit.position, frame.to_world(wi), ray.time);
+		
+		Some((attenuation, scattered))
+	}
+	
+	fn eval(&self, ray: Ray, hit: HitInfo, direction: Vec3) -> Option<(Color, Float)> {
+		let eta = if hit.front_face { self.refractive_index.recip() } else { self.refractive_index };
+		let frame = Frame::new(hit.normal);
+		let ggx = Ggx::from_roughness(self.roughness);
+		
+		let wo = frame.to_local(-ray.direction.normalize());
+		let wi = frame.to_local(direction.normalize());
+		
+		// It picks the microfacet first, and then whether to reflect or refract off of it.
+		let pdf = if wi.z > 0.0 {
+			let (pdf, m) = ggx.reflection_pdf(wo, wi);
+			pdf * microfacet::fresnel_dielectric(wo.dot(m), eta)
+		} else {
+			let (pdf, m) = ggx.refraction_pdf(wo, wi, eta);
+			pdf * (1.0 - microfacet::fresnel_dielectric(wo.dot(m), eta))
+		};
+		if pdf == 0.0 { return Some((Color::ZERO, 0.0)); }
+		
+		Some((Color::all(pdf * ggx.g2(wo, wi) / ggx.g1(wo)), pdf))
+	}
+	
+	fn interior(&self) -> Option<Interior> {
+		self.interior
+	}
+}
+
+/// How a material's refractive index changes with the wavelength of light,
+/// which is what splits white light into a rainbow going through a prism.
+#[derive(Copy, Clone, Debug)]
+pub enum Dispersion {
+	/// `n = a + b / λ²`, with λ in micrometers. Simple, and good enough for most glass.
+	Cauchy { a: Float, b: Float },
+	/// `n² = 1 + Σ bᵢλ² / (λ² - cᵢ)`, with λ in micrometers.
+	/// Glass catalogs list their coefficients this way.
+	Sellmeier { b: [Float; 3], c: [Float; 3] },
+}
+impl Dispersion {
+	/// Ordinary crown glass (Schott N-BK7).
+	pub const BK7: Self = Self::Sellmeier {
+		b: [1.039_612_12, 0.231_792_344, 1.010_469_45],
+		c: [0.006_000_698_67, 0.020_017_914_4, 103.560_653],
+	};
+	/// Dense flint glass (Schott SF11), which spreads colors out a lot more.
+	pub const SF11: Self = Self::Sellmeier {
+		b: [1.737_596_95, 0.313_747_346, 1.898_781_01],
+		c: [0.013_188_707, 0.062_306_814_2, 155.236_29],
+	};
+	pub const DIAMOND: Self = Self::Sellmeier {
+		b: [0.3306, 4.3356, 0.0],
+		c: [0.030_625, 0.011_236, 0.0],
+	};
+	
+	/// The refractive index at a wavelength, in nanometers.
+	pub fn index_at(&self, nm: Float) -> Float {
+		let um2 = (nm / 1000.0).powi(2);
+		match *self {
+			Self::Cauchy { a, b } => a + b / um2,
+			Self::Sellmeier { b, c } => Float::sqrt(
+				1.0 + (0..3).map(|i| b[i] * um2 / (um2 - c[i])).sum::<Float>()
+			),
+		}
+	}
+}
+
+/// Glass, water, and other clear stuff that bends light going through it.
+/// 
+/// It's perfectly clear unless it's given an `interior` that absorbs some light,
+/// in which case thicker parts come out more deeply tinted than thin ones.
+/// 
+/// With some `dispersion`, each wavelength bends by a different amount when
+/// rendering spectrally. Otherwise, it always uses `refractive_index`.
+#[derive(Copy, Clone, Debug)]
+pub struct Dielectric {
+	pub refractive_index: Float,
+	pub interior: Option<Interior>,
+	pub dispersion: Option<Dispersion>,
+}
+impl Dielectric {
+	pub const fn new(refractive_index: Float) -> Self {
+		Self { refractive_index, interior: None, dispersion: None }
+	}
+	
+	/// Tinted glass, where light going `distance` through it comes out as `color`.
+	pub fn tinted(refractive_index: Float, color: Color, distance: Float) -> Self {
+		Self { refractive_index, interior: Some(Interior::from_transmission(color, distance)), dispersion: None }
+	}
+	
+	/// Glass that splits light into colors. Its `refractive_index` is the
+	/// one for yellow light (587.6nm), which is what glass catalogs go by.
+	pub fn dispersive(dispersion: Dispersion) -> Self {
+		Self { refractive_index: dispersion.index_at(587.6), interior: None, dispersion: Some(dispersion) }
+	}
+}
+impl Material for Dielectric {
+	fn scatter(&self, ray: Ray, hit: HitInfo) -> Option<(Color, Ray)> {
+		// Every wavelength goes its own way, so only the hero gets to keep following this path.
+		let (refractive_index, wavelengths) = match (self.dispersion, ray.wavelengths) {
+			(Some(dispersion), Some(wavelengths)) => (dispersion.index_at(wavelengths.hero()), Some(wavelengths.collapse())),
+			_ => (self.refractive_index, ray.wavelengths),
+		};
+		let etai_over_etat = if hit.front_face { 
+			refractive_index.recip()
+		} else {
+			refractive_index
+		};
+		
+		let unit_direction = ray.direction.normalize();
+		
+		let cos_theta = Float::min(Vec3::dot(-unit_direction, hit.normal), 1.0);
+		let sin_theta = Float::sqrt(1.0 - cos_theta.powi(2));
+		if etai_over_etat * sin_theta > 1.0 {
+			let reflected = Vec3::reflect(unit_direction, hit.normal);
+			let scattered = Ray::new(hit.position, reflected, ray.time).with_wavelengths(wavelengths);
+			
+			Some((Color::ONE, scattered))
+		} else {
+			let reflect_prob = util::schlick(cos_theta, etai_over_etat);
+			if util::random_float() < reflect_prob {
+				let reflected = Vec3::reflect(unit_direction, hit.normal);
+				let scattered = Ray::new(hit.position, reflected, ray.time).with_wavelengths(wavelengths);
+				
+				Some((Color::ONE, scattered))
+			} else {
+				let refracted = util::refract(unit_direction, hit.normal, etai_over_etat);
+				let scattered = Ray::new(hit.position, refracted, ray.time).with_wavelengths(wavelengths);
+				
+				Some((Color::ONE, scattered))
+			}
+		}
+	}
+	
+	fn interior(&self) -> Option<Interior> {
+		self.interior
+	}
+}
+
+pub mod principled;
+pub mod mix;
+pub mod coated;
+pub mod bump;
+pub mod subsurface;
+pub mod thin_dielectric;
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::vector::{Point2, Point3};
+	use principled::Principled;
+	use coated::Coated;
+	
+	/// Check that [Material::eval] agrees with what [Material::scatter] actually does,
+	/// by working out how much light it reflects, and how often it scatters, both ways.
+	fn check_eval(material: &dyn Material, front_face: bool) {
+		let hit = HitInfo {
+			position: Point3::ZERO, normal: Vec3::Z, t: 1.0, front_face,
+			uv: Point2::new(0.5, 0.5), dpdu: Vec3::X, dpdv: Vec3::Y,
+		};
+		let ray = Ray::new(Point3::new(-0.6, 0.2, 1.0), Vec3::new(0.6, -0.2, -1.0), 0.0);
+		let samples = 200_000;
+		
+		let (mut scattered, mut scatters) = (Color::ZERO, 0.0);
+		for _ in 0..samples {
+			if let Some((attenuation, _)) = material.scatter(ray, hit) {
+				scattered += attenuation;
+				scatters += 1.0;
+			}
+		}
+		
+		// Pick directions half the time like scatter does, and half the time evenly. Going by eval's
+		// pdf for the scatter half keeps it from being noisy, and if that's wrong, so is the answer.
+		let uniform = (4.0 * std::f64::consts::PI as Float).recip();
+		let (mut evaluated, mut total_pdf) = (Color::ZERO, 0.0);
+		for _ in 0..samples {
+			let direction = if util::random_float() < 0.5 {
+				util::random_unit_vector()
+			} else {
+				let Some((_, ray)) = material.scatter(ray, hit) else { continue };
+				ray.direction
+			};
+			
+			let (value, pdf) = material.eval(ray, hit, direction).unwrap();
+			let mixed_pdf = 0.5 * uniform + 0.5 * pdf;
+			evaluated += value / mixed_pdf;
+			total_pdf += pdf / mixed_pdf;
+		}
+		
+		let (scattered, evaluated) = (scattered / samples as Float, evaluated / samples as Float);
+		for (a, b) in <[Float; 3]>::from(scattered).into_iter().zip(<[Float; 3]>::from(evaluated)) {
+			assert!((a - b).abs() < 0.01, "scattering reflects {scattered:?}, but eval says {evaluated:?}");
+		}
+		let (scatters, total_pdf) = (scatters / samples as Float, total_pdf / samples as Float);
+		assert!((scatters - total_pdf).abs() < 0.01, "scatters {scatters} of the time, but the pdf adds up to {total_pdf}");
+	}
+	
+	#[test]
+	fn rough_dielectric_eval_matches_scatter() {
+		check_eval(&RoughDielectric::new(1.5, 0.5), true);
+		check_eval(&RoughDielectric::new(1.5, 0.5), false);
+	}
+	
+	#[test]
+	fn coated_eval_matches_scatter() {
+		let coated = Coated::new(Lambertian { albedo: Color::new(0.8, 0.4, 0.2) }, 1.5)
+			.roughness(0.4)
+			.tint(Color::new(0.9, 0.9, 0.6));
+		check_eval(&coated, true);
+	}
+	
+	#[test]
+	fn principled_eval_matches_scatter() {
+		let principled = Principled::new(Color::new(0.8, 0.4, 0.2))
+			.metallic(0.3)
+			.transmission(0.3)
+			.sheen(0.5)
+			.clearcoat(0.5)
+			.clearcoat_roughness(0.3);
+		check_eval(&principled, true);
+		check_eval(&principled, false);
+	}
+	
+	#[test]
+	fn anisotropic_highlights_stretch_along_dpdu() {
+		let hit = |dpdu: Vec3| HitInfo {
+			position: Point3::ZERO, normal: Vec3::Z, t: 1.0, front_face: true,
+			uv: Point2::new(0.5, 0.5), dpdu, dpdv: Vec3::Z.cross(dpdu),
+		};
+		let ray = Ray::new(Point3::new(0.0, 0.0, 1.0), -Vec3::Z, 0.0);
+		// How far the reflections spread out towards X, and towards Y.
+		let spread = |material: &AnisotropicConductor, dpdu: Vec3| {
+			let (mut x, mut y) = (0.0, 0.0);
+			for _ in 0..10_000 {
+				if let Some((_, scattered)) = material.scatter(ray, hit(dpdu)) {
+					let direction = scattered.direction.normalize();
+					(x, y) = (x + direction.x.abs(), y + direction.y.abs());
+				}
+			}
+			(x, y)
+		};
+		
+		let (x, y) = spread(&AnisotropicConductor::new(ComplexIor::SILVER, 0.4, 0.05), Vec3::X);
+		assert!(x > 4.0 * y, "spread {x} along X, and {y} along Y");
+		let (x, y) = spread(&AnisotropicConductor::new(ComplexIor::SILVER, 0.05, 0.4), Vec3::X);
+		assert!(y > 4.0 * x, "spread {x} along X, and {y} along Y");
+		
+		// Turning the surface's tangents turns the highlight with them.
+		let (x, y) = spread(&AnisotropicConductor::new(ComplexIor::SILVER, 0.4, 0.05), Vec3::Y);
+		assert!(y > 4.0 * x, "spread {x} along X, and {y} along Y");
+	}
+}
@@ -0,0 +1,279 @@
+use crate::vector::{Vec3, Color, Float};
+use crate::ray::{Ray, HitInfo};
+use crate::microfacet::{self, Frame, Ggx};
+use crate::texture::{Texture, BoxedTexture};
+use crate::util;
+
+use super::Material;
+
+/// One material that can be most anything, in the style of Disney's "principled" BSDF.
+/// Every parameter is a [Texture], so it can change across the surface.
+/// 
+/// It's built up in layers. From the top down, there's:
+/// - a clear coat, like the lacquer on a car,
+/// - metal, if it's `metallic`,
+/// - glass, if it's got some `transmission`,
+/// - and otherwise, a shiny coat of `specular` over diffuse `base_color`, with some `sheen`.
+/// 
+/// Set everything up with the builder methods, starting with [Principled::new].
+pub struct Principled {
+	pub base_color: BoxedTexture<Color>,
+	/// From 0 (plastic, wood, stone...) to 1 (metal). It should usually be one or the other.
+	pub metallic: BoxedTexture<Float>,
+	/// From 0 (perfectly smooth) to 1 (totally matte).
+	pub roughness: BoxedTexture<Float>,
+	/// How shiny non-metals are. The default of 0.5 is right for most things.
+	pub specular: BoxedTexture<Float>,
+	/// How much non-metal reflections take on the `base_color`.
+	pub specular_tint: BoxedTexture<Float>,
+	/// A soft glow at grazing angles, like on cloth.
+	pub sheen: BoxedTexture<Float>,
+	/// How much the sheen takes on the `base_color`.
+	pub sheen_tint: BoxedTexture<Float>,
+	pub clearcoat: BoxedTexture<Float>,
+	pub clearcoat_roughness: BoxedTexture<Float>,
+	/// From 0 (opaque) to 1 (glass).
+	pub transmission: BoxedTexture<Float>,
+	/// The refractive index, for when it's transmissive.
+	pub ior: BoxedTexture<Float>,
+}
+impl Principled {
+	pub fn new(base_color: impl Texture<Color> + Send + Sync + 'static) -> Self {
+		Self {
+			base_color: Box::new(base_color),
+			metallic: Box::new(0.0),
+			roughness: Box::new(0.5),
+			specular: Box::new(0.5),
+			specular_tint: Box::new(0.0),
+			sheen: Box::new(0.0),
+			sheen_tint: Box::new(0.5),
+			clearcoat: Box::new(0.0),
+			clearcoat_roughness: Box::new(0.03),
+			transmission: Box::new(0.0),
+			ior: Box::new(1.5),
+		}
+	}
+	
+	pub fn metallic(mut self, metallic: impl Texture<Float> + Send + Sync + 'static) -> Self {
+		self.metallic = Box::new(metallic); self
+	}
+	
+	pub fn roughness(mut self, roughness: impl Texture<Float> + Send + Sync + 'static) -> Self {
+		self.roughness = Box::new(roughness); self
+	}
+	
+	pub fn specular(mut self, specular: impl Texture<Float> + Send + Sync + 'static) -> Self {
+		self.specular = Box::new(specular); self
+	}
+	
+	pub fn specular_tint(mut self, specular_tint: impl Texture<Float> + Send + Sync + 'static) -> Self {
+		self.specular_tint = Box::new(specular_tint); self
+	}
+	
+	pub fn sheen(mut self, sheen: impl Texture<Float> + Send + Sync + 'static) -> Self {
+		self.sheen = Box::new(sheen); self
+	}
+	
+	pub fn sheen_tint(mut self, sheen_tint: impl Texture<Float> + Send + Sync + 'static) -> Self {
+		self.sheen_tint = Box::new(sheen_tint); self
+	}
+	
+	pub fn clearcoat(mut self, clearcoat: impl Texture<Float> + Send + Sync + 'static) -> Self {
+		self.clearcoat = Box::new(clearcoat); self
+	}
+	
+	pub fn clearcoat_roughness(mut self, clearcoat_roughness: impl Texture<Float> + Send + Sync + 'static) -> Self {
+		self.clearcoat_roughness = Box::new(clearcoat_roughness); self
+	}
+	
+	pub fn transmission(mut self, transmission: impl Texture<Float> + Send + Sync + 'static) -> Self {
+		self.transmission = Box::new(transmission); self
+	}
+	
+	pub fn ior(mut self, ior: impl Texture<Float> + Send + Sync + 'static) -> Self {
+		self.ior = Box::new(ior); self
+	}
+}
+/// Everything about a [Principled] material at one spot on the surface.
+struct Layers {
+	base_color: Color,
+	clearcoat: Float,
+	clearcoat_ggx: Ggx,
+	ggx: Ggx,
+	metallic: Float,
+	transmission: Float,
+	/// The ratio of refractive indices, from the side the light's coming from.
+	eta: Float,
+	/// How much the shiny coat over the diffuse base reflects straight on.
+	f0: Color,
+	sheen: Color,
+	/// What light going into it gets tinted by.
+	transmission_tint: Color,
+}
+impl Layers {
+	/// How much the clear coat reflects, for light at `cos_theta` to the normal.
+	fn clearcoat(&self, cos_theta: Float) -> Float {
+		self.clearcoat * microfacet::schlick(Color::all(0.04), cos_theta).x
+	}
+	
+	/// How much light the diffuse base sends from `wo` to `wi`, after
+	/// getting through the shiny coat on the way in and on the way out.
+	fn diffuse(&self, wo: Vec3, wi: Vec3) -> Color {
+		let half = (wo + wi).normalize();
+		let diffuse = self.base_color + self.sheen * (1.0 - wi.dot(half)).powi(5);
+		diffuse * (Color::ONE - microfacet::schlick(self.f0, wo.z)) * (Color::ONE - microfacet::schlick(self.f0, wi.z))
+	}
+}
+impl Principled {
+	fn layers(&self, hit: &HitInfo) -> Layers {
+		let (uv, p) = (hit.uv, hit.position);
+		let base_color = self.base_color.value(uv, p);
+		let tint = if base_color.luminance() > 0.0 { base_color / base_color.luminance() } else { Color::ONE };
+		let ior = self.ior.value(uv, p);
+		
+		Layers {
+			base_color,
+			clearcoat: self.clearcoat.value(uv, p),
+			clearcoat_ggx: Ggx::from_roughness(self.clearcoat_roughness.value(uv, p)),
+			ggx: Ggx::from_roughness(self.roughness.value(uv, p)),
+			metallic: self.metallic.value(uv, p),
+			transmission: self.transmission.value(uv, p),
+			eta: if hit.front_face { ior.recip() } else { ior },
+			f0: 0.08 * self.specular.value(uv, p) * Color::lerp(Color::ONE, tint, self.specular_tint.value(uv, p)),
+			sheen: self.sheen.value(uv, p) * Color::lerp(Color::ONE, tint, self.sheen_tint.value(uv, p)),
+			// Only tint light on its way in, so it doesn't get tinted twice.
+			transmission_tint: if hit.front_face { base_color } else { Color::ONE },
+		}
+	}
+	
+	/// Scatter off of everything under the clear coat, in the surface's local space.
+	fn scatter_base(layers: &Layers, wo: Vec3) -> Option<(Color, Vec3)> {
+		let ggx = layers.ggx;
+		let m = ggx.sample_visible(wo);
+		let cos_o = wo.dot(m);
+		let shadowing = |wi: Vec3| ggx.g2(wo, wi) / ggx.g1(wo);
+		
+		if util::random_float() < layers.metallic {
+			let wi = (-wo).reflect(m);
+			if wi.z <= 0.0 { return None; }
+			let fresnel = microfacet::schlick(layers.base_color, cos_o);
+			return Some((fresnel * shadowing(wi), wi));
+		}
+		
+		if util::random_float() < layers.transmission {
+			if util::random_float() < microfacet::fresnel_dielectric(cos_o, layers.eta) {
+				let wi = (-wo).reflect(m);
+				if wi.z <= 0.0 { return None; }
+				return Some((Color::all(shadowing(wi)), wi));
+			} else {
+				let wi = util::refract(-wo, m, layers.eta);
+				if wi.z >= 0.0 { return None; }
+				return Some((layers.transmission_tint * shadowing(wi), wi));
+			}
+		}
+		
+		// Everything else is a shiny coat over a diffuse base. Like the clear coat,
+		// it's picked by how much it reflects on the whole, so eval can tell how likely it was.
+		let fresnel = microfacet::schlick(layers.f0, wo.z);
+		let reflect_prob = (fresnel.x + fresnel.y + fresnel.z) / 3.0;
+		
+		if util::random_float() < reflect_prob {
+			let wi = (-wo).reflect(m);
+			if wi.z <= 0.0 { return None; }
+			return Some((microfacet::schlick(layers.f0, cos_o) / reflect_prob * shadowing(wi), wi));
+		}
+		
+		let wi = (Vec3::Z + util::random_unit_vector()).normalize();
+		Some((layers.diffuse(wo, wi) / (1.0 - reflect_prob), wi))
+	}
+	
+	/// [Material::eval] for everything under the clear coat, in the surface's local space.
+	fn eval_base(layers: &Layers, wo: Vec3, wi: Vec3) -> (Color, Float) {
+		let ggx = layers.ggx;
+		let shadowing = ggx.g2(wo, wi) / ggx.g1(wo);
+		let (reflect_pdf, m) = ggx.reflection_pdf(wo, wi);
+		let cos_o = wo.dot(m);
+		
+		// Each lobe adds its pdf (times how likely it was to be picked), and its pdf times
+		// the weight scatter would've given it.
+		let (mut value, mut pdf) = (Color::ZERO, 0.0);
+		let mut lobe = |chance: Float, lobe_pdf: Float, weight: Color| {
+			value += chance * lobe_pdf * weight;
+			pdf += chance * lobe_pdf;
+		};
+		
+		lobe(layers.metallic, reflect_pdf, microfacet::schlick(layers.base_color, cos_o) * shadowing);
+		
+		let transmission = (1.0 - layers.metallic) * layers.transmission;
+		if wi.z > 0.0 {
+			let fresnel = microfacet::fresnel_dielectric(cos_o, layers.eta);
+			lobe(transmission, reflect_pdf * fresnel, Color::all(shadowing));
+		} else {
+			let (refract_pdf, m) = ggx.refraction_pdf(wo, wi, layers.eta);
+			let fresnel = microfacet::fresnel_dielectric(wo.dot(m), layers.eta);
+			lobe(transmission, refract_pdf * (1.0 - fresnel), layers.transmission_tint * shadowing);
+		}
+		
+		let rest = (1.0 - layers.metallic) * (1.0 - layers.transmission);
+		let fresnel = microfacet::schlick(layers.f0, wo.z);
+		let reflect_prob = (fresnel.x + fresnel.y + fresnel.z) / 3.0;
+		if reflect_prob > 0.0 {
+			lobe(rest * reflect_prob, reflect_pdf, microfacet::schlick(layers.f0, cos_o) / reflect_prob * shadowing);
+		}
+		if reflect_prob < 1.0 && wi.z > 0.0 {
+			let diffuse_pdf = wi.z / std::f64::consts::PI as Float;
+			lobe(rest * (1.0 - reflect_prob), diffuse_pdf, layers.diffuse(wo, wi) / (1.0 - reflect_prob));
+		}
+		
+		(value, pdf)
+	}
+}
+impl Material for Principled {
+	fn scatter(&self, ray: Ray, hit: HitInfo) -> Option<(Color, Ray)> {
+		let layers = self.layers(&hit);
+		let frame = Frame::new(hit.normal);
+		let wo = frame.to_local(-ray.direction.normalize());
+		let scattered = |wi: Vec3| Ray::new(hit.position, frame.to_world(wi), ray.time);
+		
+		// Light that doesn't bounce off of a layer goes on to the one below it,
+		// so each layer gets picked by how much it reflects.
+		let clearcoat = layers.clearcoat(wo.z);
+		if util::random_float() < clearcoat {
+			let ggx = layers.clearcoat_ggx;
+			let m = ggx.sample_visible(wo);
+			let wi = (-wo).reflect(m);
+			if wi.z <= 0.0 { return None; }
+			let weight = layers.clearcoat(wo.dot(m)) / clearcoat * ggx.g2(wo, wi) / ggx.g1(wo);
+			return Some((Color::all(weight), scattered(wi)));
+		}
+		
+		let (mut weight, wi) = Self::scatter_base(&layers, wo)?;
+		// Whatever comes back out has to get through the clear coat again.
+		if wi.z > 0.0 { weight *= 1.0 - layers.clearcoat(wi.z); }
+		Some((weight, scattered(wi)))
+	}
+	
+	fn eval(&self, ray: Ray, hit: HitInfo, direction: Vec3) -> Option<(Color, Float)> {
+		let layers = self.layers(&hit);
+		let frame = Frame::new(hit.normal);
+		let wo = frame.to_local(-ray.direction.normalize());
+		let wi = frame.to_local(direction.normalize());
+		if wo.z <= 0.0 { return Some((Color::ZERO, 0.0)); }
+		
+		let clearcoat = layers.clearcoat(wo.z);
+		let (base, base_pdf) = Self::eval_base(&layers, wo, wi);
+		let mut value = base * (1.0 - clearcoat);
+		let mut pdf = base_pdf * (1.0 - clearcoat);
+		
+		if wi.z > 0.0 {
+			value *= 1.0 - layers.clearcoat(wi.z);
+			
+			let ggx = layers.clearcoat_ggx;
+			let (coat_pdf, m) = ggx.reflection_pdf(wo, wi);
+			value += Color::all(coat_pdf * layers.clearcoat(wo.dot(m)) * ggx.g2(wo, wi) / ggx.g1(wo));
+			pdf += clearcoat * coat_pdf;
+		}
+		
+		Some((value, pdf))
+	}
+}
@@ -0,0 +1,59 @@
+use crate::vector::{Color, Float};
+use crate::ray::{Ray, HitInfo};
+use crate::medium::Interior;
+
+use super::{Material, Dielectric};
+
+/// Translucent stuff like wax, skin, milk and marble, where light goes inside
+/// and bounces around for a while before coming back out somewhere else.
+/// 
+/// The surface is the same as a [Dielectric]'s. Everything else happens inside,
+/// in the [Raytracer](crate::raytracer::Raytracer), so it only works on closed solids.
+#[derive(Copy, Clone, Debug)]
+pub struct Subsurface {
+	pub refractive_index: Float,
+	pub interior: Interior,
+}
+impl Subsurface {
+	/// Both `absorption` and `scattering` are per unit of distance.
+	pub const fn new(refractive_index: Float, absorption: Color, scattering: Color) -> Self {
+		Self { refractive_index, interior: Interior::new(absorption, scattering, 0.0) }
+	}
+	
+	/// Which way light scatters inside. See [sample_henyey_greenstein](crate::medium::sample_henyey_greenstein).
+	pub const fn g(mut self, g: Float) -> Self {
+		self.interior.g = g; self
+	}
+	
+	/// Materials measured in "A Practical Model for Subsurface Light Transport"
+	/// (Jensen et al. 2001). Their numbers are per millimeter, so `mm` says how
+	/// many millimeters one unit is in the world.
+	fn measured(refractive_index: Float, absorption: Color, scattering: Color, mm: Float) -> Self {
+		Self::new(refractive_index, absorption * mm, scattering * mm)
+	}
+	
+	pub fn marble(mm: Float) -> Self {
+		Self::measured(1.5, Color::new(0.0021, 0.0041, 0.0071), Color::new(2.19, 2.62, 3.00), mm)
+	}
+	
+	pub fn skin(mm: Float) -> Self {
+		Self::measured(1.3, Color::new(0.032, 0.17, 0.48), Color::new(0.74, 0.88, 1.01), mm)
+	}
+	
+	pub fn whole_milk(mm: Float) -> Self {
+		Self::measured(1.3, Color::new(0.0011, 0.0024, 0.014), Color::new(2.55, 3.21, 3.77), mm)
+	}
+	
+	pub fn ketchup(mm: Float) -> Self {
+		Self::measured(1.3, Color::new(0.061, 0.97, 1.45), Color::new(0.18, 0.07, 0.03), mm)
+	}
+}
+impl Material for Subsurface {
+	fn scatter(&self, ray: Ray, hit: HitInfo) -> Option<(Color, Ray)> {
+		Dielectric::new(self.refractive_index).scatter(ray, hit)
+	}
+	
+	fn interior(&self) -> Option<Interior> {
+		Some(self.interior)
+	}
+}
@@ -0,0 +1,62 @@
+use crate::vector::{Color, Float};
+use crate::ray::{Ray, HitInfo};
+use crate::microfacet;
+use crate::texture::{Texture, BoxedTexture};
+use crate::util;
+
+use super::Material;
+
+/// Roughly the wavelengths of red, green and blue light, in nanometers.
+/// These stand in for the real ones when not rendering spectrally.
+const RGB_WAVELENGTHS: [Float; 3] = [650.0, 532.0, 450.0];
+
+/// A sheet of glass so thin that light going through it comes out going the same
+/// way it went in, like a window pane or a soap bubble. Unlike a [Dielectric](super::Dielectric),
+/// it doesn't need to be a closed solid, since there's nothing inside.
+/// 
+/// Give it a `film` thickness, and it'll shimmer with thin-film interference.
+pub struct ThinDielectric {
+	pub refractive_index: Float,
+	/// How thick the sheet is in nanometers, if it's thin enough for interference.
+	pub film: Option<BoxedTexture<Float>>,
+}
+impl ThinDielectric {
+	pub const fn new(refractive_index: Float) -> Self {
+		Self { refractive_index, film: None }
+	}
+	
+	pub fn film(mut self, thickness: impl Texture<Float> + Send + Sync + 'static) -> Self {
+		self.film = Some(Box::new(thickness)); self
+	}
+}
+impl Material for ThinDielectric {
+	fn scatter(&self, ray: Ray, hit: HitInfo) -> Option<(Color, Ray)> {
+		let unit_direction = ray.direction.normalize();
+		let cos_theta = Float::min(-unit_direction.dot(hit.normal), 1.0);
+		
+		let (reflectance, wavelengths) = match &self.film {
+			Some(thickness) => {
+				let thickness = thickness.value(hit.uv, hit.position);
+				// When rendering spectrally, each channel is already one of the ray's wavelengths,
+				// so the raytracer mustn't treat it like RGB.
+				let wavelengths = ray.wavelengths.map(|wavelengths| wavelengths.already_spectral());
+				let nm = wavelengths.map_or(RGB_WAVELENGTHS, |wavelengths| wavelengths.nm);
+				(Color::from(nm.map(|nm| microfacet::thin_film(cos_theta, self.refractive_index, thickness, nm))), wavelengths)
+			},
+			None => {
+				// Light bounces back and forth between both sides, and some gets out each time.
+				let r = microfacet::fresnel_dielectric(cos_theta, self.refractive_index.recip());
+				(Color::all(2.0 * r / (1.0 + r)), ray.wavelengths)
+			},
+		};
+		
+		let reflect_prob = (reflectance.x + reflectance.y + reflectance.z) / 3.0;
+		if util::random_float() < reflect_prob {
+			let reflected = unit_direction.reflect(hit.normal);
+			Some((reflectance / reflect_prob, Ray::new(hit.position, reflected, ray.time).with_wavelengths(wavelengths)))
+		} else {
+			let transmitted = (Color::ONE - reflectance) / (1.0 - reflect_prob);
+			Some((transmitted, Ray::new(hit.position, unit_direction, ray.time).with_wavelengths(wavelengths)))
+		}
+	}
+}
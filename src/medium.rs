@@ -0,0 +1,321 @@
+use crate::vector::{Vec3, Point3, Color, Float};
+use crate::ray::Ray;
+use crate::bounding_box::BoundingBox;
+use crate::noise::Perlin;
+use crate::util;
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Fog that fills the whole [World](crate::world::World), with the same density
+/// everywhere inside a big sphere of `radius` around the origin. Rays that make it
+/// out of that sphere see the sky.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug)]
+pub struct Fog {
+	pub density: Float,
+	pub albedo: Color,
+	pub radius: Float,
+}
+impl Fog {
+	pub const fn new(density: Float, albedo: Color) -> Self {
+		Self { density, albedo, radius: 100.0 }
+	}
+	
+	/// Check if a [Ray] gets scattered by the fog before reaching `t_max`, where
+	/// it would've hit something. (Use infinity if it didn't hit anything.)
+	/// 
+	/// Returns the attenuation and the scattered ray, just like a [Material](crate::material::Material).
+	pub fn scatter(&self, ray: Ray, t_max: Float) -> Option<(Color, Ray)> {
+		// Don't go past the edge of the fog.
+		let roots = util::solve_quadratic(
+			ray.direction.squared_magnitude(),
+			ray.position.dot(ray.direction),
+			ray.position.squared_magnitude() - self.radius.powi(2)
+		);
+		let (t_enter, t_exit) = roots?;
+		let t_max = t_max.min(t_exit);
+		// Rays that start outside the fog only pass through it once they get to the edge.
+		let t_start = t_enter.max(0.0);
+		
+		let speed = ray.direction.magnitude();
+		let t = t_start - util::random_float().ln() / self.density / speed;
+		if t >= t_max { return None; }
+		
+		// Fog scatters light evenly in every direction.
+		let scattered = Ray::new(ray.at(t), util::random_unit_vector(), ray.time);
+		Some((self.albedo, scattered))
+	}
+	
+	/// How much light makes it through the fog along a [Ray], up to `t_max`.
+	pub fn transmittance(&self, ray: Ray, t_max: Float) -> Float {
+		let roots = util::solve_quadratic(
+			ray.direction.squared_magnitude(),
+			ray.position.dot(ray.direction),
+			ray.position.squared_magnitude() - self.radius.powi(2)
+		);
+		let Some((t_enter, t_exit)) = roots else { return 1.0 };
+		// Only the part of the ray that's inside the fog counts.
+		let inside = (t_max.min(t_exit) - t_enter.max(0.0)).max(0.0);
+		
+		Float::exp(-self.density * ray.direction.magnitude() * inside)
+	}
+}
+
+/// The stuff inside of a closed object, which light passes through
+/// on its way from going in one side to coming out the other.
+/// 
+/// If it scatters light too, light takes a random walk around inside
+/// before it comes back out, like in wax, skin, or marble.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug)]
+pub struct Interior {
+	/// How much of each color gets absorbed per unit of distance.
+	pub absorption: Color,
+	/// How much of each color gets scattered per unit of distance.
+	pub scattering: Color,
+	/// Which way it scatters. See [sample_henyey_greenstein].
+	pub g: Float,
+}
+impl Interior {
+	pub const fn new(absorption: Color, scattering: Color, g: Float) -> Self {
+		Self { absorption, scattering, g }
+	}
+	
+	/// Absorb light so that `color` is what's left after going `distance` through it.
+	/// Anything thicker than that ends up more saturated, and anything thinner less so.
+	pub fn from_transmission(color: Color, distance: Float) -> Self {
+		let absorb = |c: Float| -Float::ln(c.max(1e-6)) / distance;
+		Self::new(Color::new(absorb(color.x), absorb(color.y), absorb(color.z)), Color::ZERO, 0.0)
+	}
+	
+	pub fn scatters(&self) -> bool {
+		self.scattering != Color::ZERO
+	}
+	
+	/// How much light is left after going `distance` through it (Beer-Lambert law).
+	/// This only counts absorption, so use [Interior::sample_scatter] if it [scatters](Interior::scatters).
+	pub fn transmittance(&self, distance: Float) -> Color {
+		(-self.absorption * distance).exp()
+	}
+	
+	/// Pick how far light gets before it scatters, or `None` if it makes it the
+	/// whole `distance` through. Either way, it comes with the weight of the sample.
+	pub fn sample_scatter(&self, distance: Float) -> (Color, Option<Float>) {
+		let extinction = self.absorption + self.scattering;
+		let average = |c: Color| (c.x + c.y + c.z) / 3.0;
+		
+		// Each color scatters at a different rate, so pick one to go by, and then
+		// weight it by how likely it would've been to land here going by any of them.
+		let rates: [Float; 3] = extinction.into();
+		let rate = rates[((util::random_float() * 3.0) as usize).min(2)];
+		let d = -util::random_float().ln() / rate;
+		
+		if d < distance {
+			let transmittance = (-extinction * d).exp();
+			(self.scattering * transmittance / average(extinction * transmittance), Some(d))
+		} else {
+			let transmittance = (-extinction * distance).exp();
+			(transmittance / average(transmittance), None)
+		}
+	}
+}
+
+/// Which closed objects a path has gone inside of, innermost last, so that it knows what
+/// it's traveling through even after bumping into something else in there.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Nesting {
+	objects: Vec<(usize, Option<Interior>)>,
+}
+impl Nesting {
+	/// What's inside of the innermost object, if it has anything in it.
+	pub fn interior(&self) -> Option<Interior> {
+		self.objects.last().and_then(|&(_, interior)| interior)
+	}
+	
+	/// Go into (or come back out of) the object at `index`, which has `interior` inside.
+	pub fn cross(&mut self, index: usize, interior: Option<Interior>, entering: bool) {
+		if entering {
+			self.objects.push((index, interior));
+		} else if let Some(i) = self.objects.iter().rposition(|&(object, _)| object == index) {
+			self.objects.remove(i);
+		}
+	}
+}
+
+/// How likely the Henyey-Greenstein phase function is to scatter light by an angle of
+/// `cos_theta` from the way it was going, per unit solid angle. See [sample_henyey_greenstein].
+pub fn henyey_greenstein(cos_theta: Float, g: Float) -> Float {
+	let denominator = 1.0 + g.powi(2) - 2.0 * g * cos_theta;
+	(1.0 - g.powi(2)) / (4.0 * std::f64::consts::PI as Float * denominator * denominator.sqrt())
+}
+
+/// Pick a new direction for light scattering off a particle, given the direction it
+/// was going in. This is the Henyey-Greenstein phase function: a `g` of 0 scatters
+/// evenly in every direction, positive values scatter mostly forwards, and
+/// negative values scatter mostly backwards.
+pub fn sample_henyey_greenstein(direction: Vec3, g: Float) -> Vec3 {
+	let (xi_1, xi_2) = (util::random_float(), util::random_float());
+	
+	let cos_theta = if g.abs() < 1e-3 {
+		1.0 - 2.0 * xi_1
+	} else {
+		let s = (1.0 - g.powi(2)) / (1.0 - g + 2.0 * g * xi_1);
+		(1.0 + g.powi(2) - s.powi(2)) / (2.0 * g)
+	};
+	let sin_theta = Float::sqrt(Float::max(0.0, 1.0 - cos_theta.powi(2)));
+	let phi = 2.0 * std::f64::consts::PI as Float * xi_2;
+	
+	let forward = direction.normalize();
+	let (tangent, bitangent) = forward.orthonormal_basis();
+	sin_theta * (phi.cos() * tangent + phi.sin() * bitangent) + cos_theta * forward
+}
+
+/// Something that says how thick a volume is at each point in space.
+pub trait DensityField {
+	fn density(&self, p: Point3) -> Float;
+	
+	/// The thickest it gets anywhere. Overestimating is fine, it just makes rendering slower.
+	fn max_density(&self) -> Float;
+}
+
+/// Densities stored on a 3D grid, stretched out to fill `bounds`,
+/// and smoothly interpolated between the grid points.
+#[derive(Clone, Debug)]
+pub struct VoxelGrid {
+	pub resolution: (usize, usize, usize),
+	pub bounds: BoundingBox,
+	densities: Vec<f32>,
+	max: Float,
+}
+impl VoxelGrid {
+	/// Make a grid out of densities listed in X, then Y, then Z order.
+	pub fn new(densities: Vec<f32>, resolution: (usize, usize, usize), bounds: BoundingBox) -> Self {
+		let (nx, ny, nz) = resolution;
+		assert!(nx >= 1 && ny >= 1 && nz >= 1, "voxel grids can't be empty");
+		assert_eq!(densities.len(), nx * ny * nz, "wrong number of densities for the resolution");
+		
+		let max = densities.iter().copied().fold(0.0, f32::max) as Float;
+		Self { resolution, bounds, densities, max }
+	}
+	
+	/// Load a grid from a text file. The first three numbers are the resolution
+	/// along X, Y and Z, and then every density follows, in X, then Y, then Z order.
+	/// Any whitespace works as a separator, so it's easy to write out from anywhere.
+	pub fn load(path: impl AsRef<Path>, bounds: BoundingBox) -> io::Result<Self> {
+		let text = fs::read_to_string(path)?;
+		let mut numbers = text.split_whitespace();
+		
+		let bad_data = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+		
+		let mut dimension = || -> io::Result<usize> {
+			numbers.next()
+				.ok_or_else(|| bad_data("missing grid resolution"))?
+				.parse().map_err(|_| bad_data("grid resolution should be whole numbers"))
+		};
+		let resolution = (dimension()?, dimension()?, dimension()?);
+		
+		let densities = numbers
+			.map(|n| n.parse::<f32>().map_err(|_| bad_data("densities should be numbers")))
+			.collect::<io::Result<Vec<f32>>>()?;
+		
+		let (nx, ny, nz) = resolution;
+		if nx * ny * nz == 0 || densities.len() != nx * ny * nz {
+			return Err(bad_data("number of densities doesn't match the grid resolution"));
+		}
+		
+		Ok(Self::new(densities, resolution, bounds))
+	}
+	
+	fn voxel(&self, x: usize, y: usize, z: usize) -> Float {
+		let (nx, ny, _) = self.resolution;
+		self.densities[(z * ny + y) * nx + x] as Float
+	}
+}
+impl DensityField for VoxelGrid {
+	fn density(&self, p: Point3) -> Float {
+		// Find where `p` is in grid coordinates, with grid points at the centers of voxels.
+		let (nx, ny, nz) = self.resolution;
+		let local = p - self.bounds.min;
+		let size = self.bounds.size();
+		let g = [
+			local.x / size.x * nx as Float - 0.5,
+			local.y / size.y * ny as Float - 0.5,
+			local.z / size.z * nz as Float - 0.5,
+		];
+		let n = [nx, ny, nz];
+		
+		if (0..3).any(|a| g[a] < -0.5 || g[a] > n[a] as Float - 0.5) { return 0.0; }
+		
+		// Trilinear interpolation between the 8 nearest grid points.
+		let lo = [0, 1, 2].map(|a| (g[a].floor() as isize).clamp(0, n[a] as isize - 1) as usize);
+		let hi = [0, 1, 2].map(|a| (lo[a] + 1).min(n[a] - 1));
+		let t = [0, 1, 2].map(|a| (g[a] - lo[a] as Float).clamp(0.0, 1.0));
+		
+		let mut density = 0.0;
+		for corner in 0..8 {
+			let pick = |a: usize| corner >> a & 1 == 1;
+			let weight: Float = (0..3).map(|a| if pick(a) { t[a] } else { 1.0 - t[a] }).product();
+			let [x, y, z] = [0, 1, 2].map(|a| if pick(a) { hi[a] } else { lo[a] });
+			density += weight * self.voxel(x, y, z);
+		}
+		density
+	}
+	
+	fn max_density(&self) -> Float {
+		self.max
+	}
+}
+
+/// A wispy, cloudy density made out of [Perlin] noise.
+#[derive(Clone, Debug)]
+pub struct NoiseField {
+	pub noise: Perlin,
+	/// The density where the noise is at its thickest.
+	pub density: Float,
+	/// How many times the noise repeats per unit. Bigger makes for smaller wisps.
+	pub frequency: Float,
+	pub octaves: usize,
+}
+impl NoiseField {
+	pub fn new(density: Float, frequency: Float) -> Self {
+		Self { noise: Perlin::new(), density, frequency, octaves: 5 }
+	}
+}
+impl DensityField for NoiseField {
+	fn density(&self, p: Point3) -> Float {
+		let n = self.noise.fractal(p * self.frequency, self.octaves);
+		// Leave gaps where the noise is low, so it looks like separate puffs.
+		self.density * Float::clamp(2.0 * n, 0.0, 1.0)
+	}
+	
+	fn max_density(&self) -> Float {
+		self.density
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	#[test]
+	fn fog_scatters_inside_its_sphere() {
+		let fog = Fog { density: 1.0, albedo: Color::ONE, radius: 1.0 };
+		// Starts 10 units away from the fog, so nothing can scatter before t = 9.
+		let ray = Ray::new(Point3::new(-10.0, 0.0, 0.0), Vec3::X, 0.0);
+		for _ in 0..1000 {
+			if let Some((_, scattered)) = fog.scatter(ray, Float::INFINITY) {
+				assert!(scattered.position.magnitude() <= 1.0 + 1e-9);
+			}
+		}
+	}
+	
+	#[test]
+	fn fog_only_dims_inside_its_sphere() {
+		let fog = Fog { density: 0.5, albedo: Color::ONE, radius: 1.0 };
+		// Goes in at t = 9 and out at t = 11, so it's in the fog for 2 units.
+		let ray = Ray::new(Point3::new(-10.0, 0.0, 0.0), Vec3::X, 0.0);
+		assert!((fog.transmittance(ray, Float::INFINITY) - Float::exp(-1.0)).abs() < 1e-9);
+		assert!((fog.transmittance(ray, 10.0) - Float::exp(-0.5)).abs() < 1e-9);
+		assert_eq!(fog.transmittance(ray, 5.0), 1.0);
+	}
+}
@@ -0,0 +1,233 @@
+use crate::vector::{Vec3, Color, Float};
+use crate::util;
+
+/// A set of axes around a surface normal, for working with directions
+/// as if the surface were flat and facing straight up along Z.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug)]
+pub struct Frame {
+	pub tangent: Vec3,
+	pub bitangent: Vec3,
+	pub normal: Vec3,
+}
+impl Frame {
+	pub fn new(normal: Vec3) -> Self {
+		let (tangent, bitangent) = normal.orthonormal_basis();
+		Self { tangent, bitangent, normal }
+	}
+	
+	/// Make a frame with its X axis following `tangent` as much as it can,
+	/// for when the direction along the surface matters.
+	pub fn from_tangent(normal: Vec3, tangent: Vec3) -> Self {
+		let along = tangent - normal * normal.dot(tangent);
+		if along.squared_magnitude() == 0.0 { return Self::new(normal); }
+		
+		let tangent = along.normalize();
+		Self { tangent, bitangent: Vec3::cross(normal, tangent), normal }
+	}
+	
+	/// Spin the tangent and bitangent around the normal.
+	pub fn rotated(self, degrees: Float) -> Self {
+		let (sin, cos) = degrees.to_radians().sin_cos();
+		Self {
+			tangent: cos * self.tangent + sin * self.bitangent,
+			bitangent: cos * self.bitangent - sin * self.tangent,
+			normal: self.normal,
+		}
+	}
+	
+	pub fn to_local(self, v: Vec3) -> Vec3 {
+		Vec3::new(v.dot(self.tangent), v.dot(self.bitangent), v.dot(self.normal))
+	}
+	
+	pub fn to_world(self, v: Vec3) -> Vec3 {
+		v.x * self.tangent + v.y * self.bitangent + v.z * self.normal
+	}
+}
+
+/// The GGX (or Trowbridge-Reitz) microfacet distribution, which models a rough
+/// surface as lots of tiny mirrors pointing in slightly different directions.
+/// 
+/// Everything here works in a [Frame]'s local space, where the surface normal is +Z.
+/// It can be rougher along X (`alpha_x`) than along Y (`alpha_y`), for brushed metal.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug)]
+pub struct Ggx {
+	pub alpha_x: Float,
+	pub alpha_y: Float,
+}
+impl Ggx {
+	/// Roughness is squared to get `alpha`, so it looks like it changes evenly.
+	pub fn from_roughness(roughness: Float) -> Self {
+		Self::anisotropic(roughness, roughness)
+	}
+	
+	pub fn anisotropic(roughness_x: Float, roughness_y: Float) -> Self {
+		// Perfectly smooth surfaces make the math fall apart, so keep it just above that.
+		let alpha = |roughness: Float| roughness.powi(2).max(1e-4);
+		Self { alpha_x: alpha(roughness_x), alpha_y: alpha(roughness_y) }
+	}
+	
+	/// Smith's Λ, which is used to figure out how many microfacets
+	/// are hidden behind others when looking from `w`.
+	fn lambda(&self, w: Vec3) -> Float {
+		let cos2 = w.z.powi(2);
+		if cos2 == 0.0 { return Float::INFINITY; }
+		let stretched = (self.alpha_x * w.x).powi(2) + (self.alpha_y * w.y).powi(2);
+		0.5 * (Float::sqrt(1.0 + stretched / cos2) - 1.0)
+	}
+	
+	/// How much of the surface faces in the direction `m`, per unit solid angle.
+	pub fn d(&self, m: Vec3) -> Float {
+		if m.z <= 0.0 { return 0.0; }
+		let e = (m.x / self.alpha_x).powi(2) + (m.y / self.alpha_y).powi(2) + m.z.powi(2);
+		(std::f64::consts::PI as Float * self.alpha_x * self.alpha_y * e.powi(2)).recip()
+	}
+	
+	/// How much of the surface is visible from `w`.
+	pub fn g1(&self, w: Vec3) -> Float {
+		(1.0 + self.lambda(w)).recip()
+	}
+	
+	/// How much of the surface is visible from both `wo` and `wi`
+	/// (the height-correlated Smith masking-shadowing function).
+	pub fn g2(&self, wo: Vec3, wi: Vec3) -> Float {
+		(1.0 + self.lambda(wo) + self.lambda(wi)).recip()
+	}
+	
+	/// Pick a microfacet normal, out of only the ones visible from `wo`, from
+	/// "Sampling the GGX Distribution of Visible Normals" (Heitz, 2018).
+	/// 
+	/// Picking like this means the weight of a sample is just `g2 / g1(wo)` times the Fresnel term.
+	pub fn sample_visible(&self, wo: Vec3) -> Vec3 {
+		// Stretch the view so the surface looks like a hemisphere.
+		let vh = Vec3::new(self.alpha_x * wo.x, self.alpha_y * wo.y, wo.z).normalize();
+		
+		let len2 = vh.x.powi(2) + vh.y.powi(2);
+		let t1 = if len2 > 0.0 { Vec3::new(-vh.y, vh.x, 0.0) / len2.sqrt() } else { Vec3::X };
+		let t2 = Vec3::cross(vh, t1);
+		
+		// Pick a point on a disk, squished so that the half of it that's
+		// hidden behind the hemisphere gets projected onto the visible half.
+		let r = util::random_float().sqrt();
+		let phi = 2.0 * std::f64::consts::PI as Float * util::random_float();
+		let p1 = r * phi.cos();
+		let s = 0.5 * (1.0 + vh.z);
+		let p2 = Float::mul_add(1.0 - s, Float::sqrt(1.0 - p1.powi(2)), s * r * phi.sin());
+		
+		let nh = p1 * t1 + p2 * t2 + Float::sqrt(Float::max(0.0, 1.0 - p1.powi(2) - p2.powi(2))) * vh;
+		
+		// Unstretch it.
+		Vec3::new(self.alpha_x * nh.x, self.alpha_y * nh.y, nh.z.max(0.0)).normalize()
+	}
+	
+	/// The pdf of [Ggx::sample_visible] picking the microfacet that reflects `wo` into `wi`,
+	/// per unit solid angle around `wi`. Also returns that microfacet.
+	pub fn reflection_pdf(&self, wo: Vec3, wi: Vec3) -> (Float, Vec3) {
+		let m = (wo + wi).normalize();
+		if wo.z <= 0.0 || wi.z <= 0.0 { return (0.0, m); }
+		
+		// The 4 (wo . m) from turning microfacet normals into reflected directions
+		// cancels with the (wo . m) in the distribution of visible normals.
+		(self.g1(wo) * self.d(m) / (4.0 * wo.z), m)
+	}
+	
+	/// The pdf of [Ggx::sample_visible] picking the microfacet that refracts `wo` into `wi`,
+	/// per unit solid angle around `wi`. Also returns that microfacet.
+	/// 
+	/// `eta` is the ratio of refractive indices, like for [fresnel_dielectric].
+	pub fn refraction_pdf(&self, wo: Vec3, wi: Vec3, eta: Float) -> (Float, Vec3) {
+		let m = eta * wo + wi;
+		let m = if m.z < 0.0 { -m.normalize() } else { m.normalize() };
+		let (cos_o, cos_i) = (wo.dot(m), wi.dot(m));
+		if wo.z <= 0.0 || wi.z >= 0.0 || cos_o <= 0.0 || cos_i >= 0.0 { return (0.0, m); }
+		
+		let visible = self.g1(wo) * cos_o * self.d(m) / wo.z;
+		(visible * -cos_i / (eta * cos_o + cos_i).powi(2), m)
+	}
+}
+
+/// The refractive index of a metal. Unlike glass, it has an imaginary part `k`
+/// (the extinction coefficient) for how quickly light dies off inside it.
+/// 
+/// Both parts change with wavelength, which is where the color of gold
+/// and copper comes from. These are sampled at red, green and blue.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug)]
+pub struct ComplexIor {
+	pub eta: Color,
+	pub k: Color,
+}
+impl ComplexIor {
+	pub const GOLD: Self = Self { eta: Color::new(0.143, 0.374, 1.442), k: Color::new(3.983, 2.385, 1.603) };
+	pub const COPPER: Self = Self { eta: Color::new(0.200, 0.924, 1.102), k: Color::new(3.912, 2.452, 2.142) };
+	pub const ALUMINIUM: Self = Self { eta: Color::new(1.657, 0.880, 0.521), k: Color::new(9.224, 6.270, 4.837) };
+	pub const SILVER: Self = Self { eta: Color::new(0.155, 0.117, 0.138), k: Color::new(4.828, 3.122, 2.147) };
+	
+	/// How much light gets reflected off of the metal, for light coming in at `cos_theta` to the normal.
+	pub fn fresnel(&self, cos_theta: Float) -> Color {
+		let (eta, k): ([Float; 3], [Float; 3]) = (self.eta.into(), self.k.into());
+		Color::from([0, 1, 2].map(|i| fresnel_conductor(cos_theta, eta[i], k[i])))
+	}
+}
+
+/// Schlick's approximation of the Fresnel equations, given how much
+/// gets reflected straight on (`f0`). Cheaper and easier to tweak than the real thing.
+pub fn schlick(f0: Color, cos_theta: Float) -> Color {
+	f0 + (Color::ONE - f0) * (1.0 - cos_theta.clamp(0.0, 1.0)).powi(5)
+}
+
+/// The exact Fresnel equations for a conductor, averaged over both polarizations.
+fn fresnel_conductor(cos_theta: Float, eta: Float, k: Float) -> Float {
+	let cos2 = cos_theta.clamp(0.0, 1.0).powi(2);
+	let sin2 = 1.0 - cos2;
+	let (eta2, k2) = (eta.powi(2), k.powi(2));
+	
+	let t0 = eta2 - k2 - sin2;
+	let a2_plus_b2 = Float::sqrt(t0.powi(2) + 4.0 * eta2 * k2);
+	let t1 = a2_plus_b2 + cos2;
+	let a = Float::sqrt(0.5 * (a2_plus_b2 + t0));
+	let t2 = 2.0 * cos_theta * a;
+	let rs = (t1 - t2) / (t1 + t2);
+	
+	let t3 = cos2 * a2_plus_b2 + sin2.powi(2);
+	let t4 = t2 * sin2;
+	let rp = rs * (t3 - t4) / (t3 + t4);
+	
+	0.5 * (rs + rp)
+}
+
+/// How much light reflects off of a thin film (like a soap bubble) of `thickness`
+/// nanometers, for light of a `wavelength` coming in at `cos_theta` to the normal.
+/// 
+/// The reflections off of the front and back of the film interfere with each other,
+/// so this changes a lot with the wavelength, which is where the rainbow colors come from.
+pub fn thin_film(cos_theta: Float, refractive_index: Float, thickness: Float, wavelength: Float) -> Float {
+	let eta = refractive_index.recip();
+	let cos_t = Float::sqrt(1.0 - eta.powi(2) * (1.0 - cos_theta.powi(2)));
+	
+	// How much further light bouncing off the back goes, as a phase difference.
+	let phase = 4.0 * std::f64::consts::PI as Float * refractive_index * thickness * cos_t / wavelength;
+	
+	// Going back out of the film flips the sign of each reflection, but not its size.
+	let reflectance = |r: Float| {
+		let r2 = r.powi(2);
+		2.0 * r2 * (1.0 - phase.cos()) / (1.0 + r2.powi(2) - 2.0 * r2 * phase.cos())
+	};
+	let rs = (cos_theta - refractive_index * cos_t) / (cos_theta + refractive_index * cos_t);
+	let rp = (refractive_index * cos_theta - cos_t) / (refractive_index * cos_theta + cos_t);
+	
+	0.5 * (reflectance(rs) + reflectance(rp))
+}
+
+/// The exact Fresnel equations for a dielectric, averaged over both polarizations.
+/// `eta` is the ratio of refractive indices, from the side the light's coming from
+/// over the side it's going into.
+pub fn fresnel_dielectric(cos_theta: Float, eta: Float) -> Float {
+	let sin2_t = eta.powi(2) * (1.0 - cos_theta.powi(2));
+	// Total internal reflection.
+	if sin2_t >= 1.0 { return 1.0; }
+	
+	let cos_t = Float::sqrt(1.0 - sin2_t);
+	let rs = (eta * cos_theta - cos_t) / (eta * cos_theta + cos_t);
+	let rp = (cos_theta - eta * cos_t) / (cos_theta + eta * cos_t);
+	
+	0.5 * (rs.powi(2) + rp.powi(2))
+}
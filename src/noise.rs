@@ -0,0 +1,78 @@
+use crate::vector::{Vec3, Point3, Float};
+use crate::util;
+
+use rand::seq::SliceRandom;
+
+const POINT_COUNT: usize = 256;
+
+/// Perlin noise: smooth, random-looking values that change gradually through space.
+#[derive(Clone, Debug)]
+pub struct Perlin {
+	gradients: Vec<Vec3>,
+	perm: [Vec<usize>; 3],
+}
+impl Perlin {
+	pub fn new() -> Self {
+		let gradients = (0..POINT_COUNT)
+			.map(|_| (2.0 * util::random_color() - Vec3::ONE).normalize())
+			.collect();
+		
+		let permutation = || {
+			let mut p: Vec<usize> = (0..POINT_COUNT).collect();
+			p.shuffle(&mut rand::thread_rng());
+			p
+		};
+		
+		Self { gradients, perm: [permutation(), permutation(), permutation()] }
+	}
+	
+	/// Get the noise at `p`. It's somewhere in [-1, 1], but mostly stays near 0.
+	pub fn noise(&self, p: Point3) -> Float {
+		let floor = Vec3::new(p.x.floor(), p.y.floor(), p.z.floor());
+		let frac = p - floor;
+		let (i, j, k) = (floor.x as i64, floor.y as i64, floor.z as i64);
+		
+		// Smooth out the interpolation, so there aren't any visible grid lines.
+		let smooth = |t: Float| t * t * (3.0 - 2.0 * t);
+		let (u, v, w) = (smooth(frac.x), smooth(frac.y), smooth(frac.z));
+		
+		let mut accum = 0.0;
+		for di in 0..2 {
+			for dj in 0..2 {
+				for dk in 0..2 {
+					let index = self.perm[0][((i + di) & 255) as usize]
+						^ self.perm[1][((j + dj) & 255) as usize]
+						^ self.perm[2][((k + dk) & 255) as usize];
+					
+					let (fi, fj, fk) = (di as Float, dj as Float, dk as Float);
+					let weight = frac - Vec3::new(fi, fj, fk);
+					
+					accum += (fi * u + (1.0 - fi) * (1.0 - u))
+						* (fj * v + (1.0 - fj) * (1.0 - v))
+						* (fk * w + (1.0 - fk) * (1.0 - w))
+						* self.gradients[index].dot(weight);
+				}
+			}
+		}
+		accum
+	}
+	
+	/// Several octaves of noise added together, each one finer and fainter than the last.
+	/// Also somewhere in [-1, 1].
+	pub fn fractal(&self, p: Point3, octaves: usize) -> Float {
+		let mut accum = 0.0;
+		let mut total_weight = 0.0;
+		let (mut p, mut weight) = (p, 1.0);
+		
+		for _ in 0..octaves {
+			accum += weight * self.noise(p);
+			total_weight += weight;
+			weight *= 0.5;
+			p *= 2.0;
+		}
+		accum / total_weight
+	}
+}
+impl Default for Perlin {
+	fn default() -> Self { Self::new() }
+}
@@ -1,16 +1,27 @@
-use crate::vector::{Vec3, Point3, Float};
+use crate::vector::{Vec3, Point2, Point3, Float};
+use crate::spectrum::Wavelengths;
 
 /// You know it from geometry class, it's a Ray!!!
 /// 
-/// Has a position and a direction.
+/// Has a position and a direction, along with the moment in time it
+/// was shot at, for things that move while the camera's shutter is open.
 #[derive(Copy, Clone, PartialOrd, PartialEq, Debug)]
 pub struct Ray {
 	pub position: Point3,
 	pub direction: Vec3,
+	pub time: Float,
+	/// The wavelengths of light it's carrying, when rendering spectrally.
+	/// Anything that doesn't care about them can leave this as `None`,
+	/// and the [Raytracer](crate::raytracer::Raytracer) will pass them along.
+	pub wavelengths: Option<Wavelengths>,
 }
 impl Ray {
-	pub const fn new(position: Point3, direction: Vec3) -> Self {
-		Self { position, direction }
+	pub const fn new(position: Point3, direction: Vec3, time: Float) -> Self {
+		Self { position, direction, time, wavelengths: None }
+	}
+	
+	pub const fn with_wavelengths(mut self, wavelengths: Option<Wavelengths>) -> Self {
+		self.wavelengths = wavelengths; self
 	}
 	
 	pub fn at(&self, t: Float) -> Point3 {
@@ -24,6 +35,12 @@ pub struct HitInfo {
 	pub normal: Vec3,
 	pub t: Float,
 	pub front_face: bool,
+	/// Where on the surface this is, for texturing. Both components are in [0, 1].
+	pub uv: Point2,
+	/// Which way the surface goes as `uv` changes, for lining up normal maps.
+	/// They aren't normalized, and they don't flip with the normal.
+	pub dpdu: Vec3,
+	pub dpdv: Vec3,
 }
 impl HitInfo {
 	pub fn get_face_normal_info(ray: Ray, outward_normal: Vec3) -> (bool, Vec3) {
@@ -32,4 +49,17 @@ impl HitInfo {
 		
 		(front_face, normal)
 	}
+	
+	/// Somewhere partway along a [Ray] through a volume (like fog), where there's no surface.
+	/// Anything about the surface is made up, so only use it with materials that don't care.
+	pub fn in_volume(ray: Ray, t: Float) -> Self {
+		Self {
+			position: ray.at(t), t,
+			normal: -ray.direction.normalize(),
+			front_face: true,
+			uv: Point2::ZERO,
+			dpdu: Vec3::X,
+			dpdv: Vec3::Y,
+		}
+	}
 }
@@ -0,0 +1,641 @@
+use crate::vector::{Vec2, Vec3, Point3, Color, Float};
+use crate::ray::{Ray, HitInfo};
+use crate::world::World;
+use crate::light::{Light, DirectionalLight, Emission};
+use crate::light::tree::Emitter;
+use crate::camera::Camera;
+use crate::spectrum::Wavelengths;
+use crate::bounding_box::BoundingBox;
+use crate::medium::{self, Nesting};
+use crate::util;
+
+use super::{Raytracer, ROULETTE_DEPTH, HERO_WEIGHT};
+
+/// One spot along a path, either one that started at the camera or one that started at a light.
+#[derive(Copy, Clone, Debug)]
+struct Vertex {
+	kind: Kind,
+	position: Point3,
+	/// Which way the surface faces, for spots that are on one.
+	normal: Option<Vec3>,
+	/// How much the path carries up to here: light for light paths,
+	/// and how much of it would make it to the camera for camera paths.
+	throughput: Color,
+	/// The pdf of getting here from the vertex before it on its own path, per unit area.
+	pdf_forward: Float,
+	/// The pdf of getting here the other way around, from the vertex after it.
+	pdf_reverse: Float,
+	/// Whether it only sends light off in exact directions (or ones that can't be looked up),
+	/// so that paths can't be connected through it.
+	delta: bool,
+	/// Whether only the hero wavelength made it here, when rendering spectrally.
+	/// The `throughput` hasn't made up for the others yet, since that only gets
+	/// done once for a whole path, after connecting it.
+	collapsed: bool,
+}
+impl Vertex {
+	fn new(kind: Kind, position: Point3, normal: Option<Vec3>, throughput: Color) -> Self {
+		Self { kind, position, normal, throughput, pdf_forward: 0.0, pdf_reverse: 0.0, delta: false, collapsed: false }
+	}
+}
+
+#[derive(Copy, Clone, Debug)]
+enum Kind {
+	Camera,
+	/// On a light, or on one of the world's glowing objects.
+	Light(Emitter),
+	/// Infinitely far away, in the environment, seen by looking in `direction`.
+	/// Its position is just somewhere that way, so the direction's what to go by.
+	Environment { direction: Vec3 },
+	/// On one of the world's objects, which the path got to along `ray`.
+	Surface { object: usize, ray: Ray, hit: HitInfo },
+	/// Somewhere in the fog, which scatters evenly in every direction.
+	Medium { albedo: Color },
+}
+
+/// The parts of a [Scene] that stay the same for the whole render, so they only get worked out once.
+#[derive(Copy, Clone, Debug)]
+pub struct Lighting {
+	/// A ball around everything (its center and radius), for sending light in from far away.
+	bounds: (Point3, Float),
+	/// The chance of sending light out from the environment, instead of one of the lights.
+	environment: Float,
+}
+impl Lighting {
+	pub fn new(world: &World) -> Self {
+		let bounds = world.bounds();
+		let bounds = if bounds == BoundingBox::EMPTY { (Point3::ZERO, 0.0) } else {
+			(bounds.centroid(), bounds.size().magnitude() / 2.0)
+		};
+		
+		// Environments that can't be sampled only get found by rays escaping to them.
+		let has_lights = match &world.light_tree {
+			Some(tree) => !tree.is_empty(),
+			None => !world.lights().is_empty(),
+		};
+		let environment = if !world.environment.is_sampleable() || bounds.1 <= 0.0 { 0.0 }
+			else if has_lights { 0.5 } else { 1.0 };
+		
+		Self { bounds, environment }
+	}
+}
+
+/// Everything about the world that connecting paths together needs to know.
+struct Scene<'a> {
+	world: &'a World,
+	camera: &'a Camera,
+	/// A ball around everything (its center and radius), for sending light in from far away.
+	bounds: (Point3, Float),
+	/// The chance of sending light out from the environment, instead of one of the lights.
+	environment: Float,
+	wavelengths: Option<Wavelengths>,
+	time: Float,
+}
+impl<'a> Scene<'a> {
+	fn new(world: &'a World, camera: &'a Camera, lighting: Lighting, ray: Ray) -> Self {
+		let Lighting { bounds, environment } = lighting;
+		Self { world, camera, bounds, environment, wavelengths: ray.wavelengths, time: ray.time }
+	}
+	
+	/// Turn an RGB color into one for each wavelength, when rendering spectrally.
+	fn spectrum(&self, rgb: Color) -> Color {
+		self.wavelengths.map_or(rgb, |w| w.sample_rgb(rgb))
+	}
+	
+	/// Pick something to send light out from, by how much light it gives off,
+	/// along with the chance of having picked it. `None` is the environment.
+	fn pick(&self) -> Option<(Option<Emitter>, Float)> {
+		if util::random_float() < self.environment {
+			return Some((None, self.environment));
+		}
+		
+		let (emitter, pmf) = match &self.world.light_tree {
+			Some(tree) => tree.sample_power()?,
+			None => {
+				if self.world.lights().is_empty() { return None; }
+				let count = self.world.lights().len();
+				let i = ((util::random_float() * count as Float) as usize).min(count - 1);
+				(Emitter::Light(i), (count as Float).recip())
+			},
+		};
+		Some((Some(emitter), pmf * (1.0 - self.environment)))
+	}
+	
+	/// The chance of [Scene::pick] picking `emitter`.
+	fn pmf(&self, emitter: Emitter) -> Float {
+		let pmf = match (&self.world.light_tree, emitter) {
+			(Some(tree), _) => tree.power_pmf(emitter),
+			(None, Emitter::Light(_)) => (self.world.lights().len() as Float).recip(),
+			(None, Emitter::Object(_)) => 0.0,
+		};
+		pmf * (1.0 - self.environment)
+	}
+	
+	/// Whether `vertex` is infinitely far away.
+	fn is_infinite(&self, vertex: &Vertex) -> bool {
+		match vertex.kind {
+			Kind::Environment { .. } => true,
+			Kind::Light(Emitter::Light(i)) => self.world.lights()[i].bounds().is_none(),
+			_ => false,
+		}
+	}
+	
+	/// Whether `vertex` is on one of the [Light]s, which are all either infinitely small
+	/// or only shine one way, so there's no way for a path to bump into them.
+	fn is_delta_light(vertex: &Vertex) -> bool {
+		matches!(vertex.kind, Kind::Light(Emitter::Light(_)))
+	}
+	
+	/// Whether a path can be connected to another one at `vertex`.
+	fn is_connectible(&self, vertex: &Vertex) -> bool {
+		match vertex.kind {
+			// Faraway lights only shine one way, so nothing else can be connected to them.
+			Kind::Light(Emitter::Light(_)) => !self.is_infinite(vertex),
+			Kind::Camera | Kind::Light(_) | Kind::Environment { .. } => true,
+			Kind::Surface { .. } | Kind::Medium { .. } => !vertex.delta,
+		}
+	}
+	
+	/// Turn a `pdf` per unit solid angle (of going from `from` to `to`) into one per unit area at `to`.
+	fn convert(&self, pdf: Float, from: &Vertex, to: &Vertex) -> Float {
+		if self.is_infinite(to) { return pdf; }
+		
+		let offset = to.position - from.position;
+		let distance2 = offset.squared_magnitude();
+		if distance2 == 0.0 { return 0.0; }
+		let cos = to.normal.map_or(1.0, |normal| normal.dot(offset).abs() / distance2.sqrt());
+		pdf * cos / distance2
+	}
+	
+	/// Put `vertex` at the end of the `path`, which got to it along `ray` with a `pdf` per unit solid angle.
+	fn add_vertex(&self, path: &mut Vec<Vertex>, mut vertex: Vertex, ray: Ray, pdf: Float) {
+		let prev = path.last().expect("paths start with a vertex");
+		vertex.pdf_forward = self.convert(pdf, prev, &vertex);
+		vertex.collapsed = ray.wavelengths.is_some_and(|w| w.collapsed);
+		path.push(vertex);
+	}
+	
+	/// How much of the light coming to `vertex` gets sent on towards `next`.
+	/// Like [Material::eval](crate::material::Material::eval), this has the cosine in it.
+	fn f(&self, vertex: &Vertex, next: &Vertex) -> Color {
+		let direction = next.position - vertex.position;
+		match vertex.kind {
+			Kind::Surface { object, ray, hit } => self.world.objects()[object].material.eval(ray, hit, direction)
+				.map_or(Color::ZERO, |(f, _)| self.spectrum(f)),
+			Kind::Medium { albedo } => albedo / (4.0 * std::f64::consts::PI as Float),
+			_ => Color::ZERO,
+		}
+	}
+	
+	/// Like [Scene::f], but for a vertex on a light path, where the light goes the other way.
+	/// 
+	/// Materials only say how light bounces towards the camera, which isn't always the same
+	/// both ways around (like for refraction), so this looks it up from `next`'s side instead.
+	fn f_light(&self, vertex: &Vertex, next: &Vertex) -> Color {
+		let Kind::Surface { object, ray, hit } = vertex.kind else { return self.f(vertex, next) };
+		let direction = next.position - vertex.position;
+		let arriving = Ray { position: next.position, direction: -direction, ..ray };
+		let Some((f, _)) = self.world.objects()[object].material.eval(arriving, hit, -ray.direction) else { return Color::ZERO };
+		
+		// Eval has the cosine towards where the light came from, but it needs to be the one towards `next`.
+		let cos = |w: Vec3| hit.normal.dot(w.normalize()).abs();
+		if cos(ray.direction) == 0.0 { return Color::ZERO; }
+		self.spectrum(f) * cos(direction) / cos(ray.direction)
+	}
+	
+	/// How much light `vertex` gives off on its own, back towards where the path came from.
+	fn emitted(&self, vertex: &Vertex) -> Color {
+		match vertex.kind {
+			Kind::Environment { direction } => self.spectrum(self.world.environment.radiance(direction)),
+			Kind::Surface { object, hit, .. } => self.spectrum(self.world.objects()[object].material.emitted(&hit)),
+			_ => Color::ZERO,
+		}
+	}
+	
+	/// The pdf of the path going on from `vertex` to `next`, having come from `prev`, per unit area at `next`.
+	fn pdf(&self, vertex: &Vertex, prev: Option<&Vertex>, next: &Vertex) -> Float {
+		let direction = next.position - vertex.position;
+		let pdf = match vertex.kind {
+			Kind::Camera => self.camera.direction_pdf(vertex.position, direction),
+			Kind::Light(_) | Kind::Environment { .. } => return self.light_pdf(vertex, next),
+			Kind::Surface { object, ray, hit } => {
+				let Some(prev) = prev else { return 0.0 };
+				let arriving = Ray { position: prev.position, direction: vertex.position - prev.position, ..ray };
+				self.world.objects()[object].material.eval(arriving, hit, direction).map_or(0.0, |(_, pdf)| pdf)
+			},
+			Kind::Medium { .. } => (4.0 * std::f64::consts::PI as Float).recip(),
+		};
+		self.convert(pdf, vertex, next)
+	}
+	
+	/// The pdf of the light at `vertex` sending its light towards `next`, per unit area at `next`.
+	/// The light's own position doesn't count here, see [Scene::light_origin_pdf] for that.
+	fn light_pdf(&self, vertex: &Vertex, next: &Vertex) -> Float {
+		let direction = match vertex.kind {
+			Kind::Environment { direction } => -direction,
+			_ => next.position - vertex.position,
+		};
+		let cos_next = next.normal.map_or(1.0, |normal| normal.dot(direction.normalize()).abs());
+		
+		// Light from far away comes in from anywhere on a disk as big as the whole scene.
+		if self.is_infinite(vertex) {
+			return cos_next / (std::f64::consts::PI as Float * self.bounds.1.powi(2));
+		}
+		
+		let direction_pdf = match vertex.kind {
+			Kind::Light(Emitter::Light(i)) => self.world.lights()[i].emit_pdf(direction, self.bounds).1,
+			// Glowing objects send their light out with a cosine distribution.
+			Kind::Light(Emitter::Object(_)) | Kind::Surface { .. } => vertex.normal
+				.map_or(0.0, |normal| normal.dot(direction.normalize()).max(0.0)) / std::f64::consts::PI as Float,
+			_ => 0.0,
+		};
+		direction_pdf * cos_next / direction.squared_magnitude()
+	}
+	
+	/// The pdf of picking `vertex` to start a light path at, and picking where on the light
+	/// it is, per unit area. Since the environment's infinitely far away, it's per unit
+	/// solid angle for that, seen from `next`.
+	fn light_origin_pdf(&self, vertex: &Vertex, next: &Vertex) -> Float {
+		match vertex.kind {
+			Kind::Environment { direction } => self.environment * self.world.environment.pdf(direction),
+			Kind::Light(emitter @ Emitter::Light(i)) => {
+				self.pmf(emitter) * self.world.lights()[i].emit_pdf(next.position - vertex.position, self.bounds).0
+			},
+			Kind::Light(Emitter::Object(object)) | Kind::Surface { object, .. } => {
+				self.pmf(Emitter::Object(object)) * self.world.objects()[object].solid.point_pdf(vertex.position)
+			},
+			_ => 0.0,
+		}
+	}
+	
+	/// Pick a light (or the environment) and a spot on it to connect `vertex` to, like the path tracer does.
+	/// The new vertex's throughput is how much light comes from there, over the pdf of having picked it.
+	fn sample_light(&self, vertex: &Vertex) -> Option<Vertex> {
+		let (emitter, pmf) = self.pick()?;
+		let point = vertex.position;
+		
+		let (kind, position, normal, light, pdf) = match emitter {
+			None => {
+				let (direction, radiance, pdf) = self.world.environment.sample()?;
+				(Kind::Environment { direction }, point + direction, None, radiance, pdf)
+			},
+			Some(emitter @ Emitter::Light(i)) => {
+				let sample = self.world.lights()[i].sample(point)?;
+				let distance = if sample.distance.is_finite() { sample.distance } else { 1.0 };
+				(Kind::Light(emitter), point + sample.direction * distance, None, sample.light, 1.0)
+			},
+			Some(emitter @ Emitter::Object(i)) => {
+				let object = &self.world.objects()[i];
+				let direction = object.solid.sample_direction(point)?;
+				let spot = object.solid.ray_hits(0.001..Float::INFINITY, Ray::new(point, direction, self.time))?;
+				let pdf = object.solid.direction_pdf(point, direction);
+				(Kind::Light(emitter), spot.position, Some(spot.normal), object.material.emitted(&spot), pdf)
+			},
+		};
+		if pdf <= 0.0 || light == Color::ZERO { return None; }
+		
+		let mut sampled = Vertex::new(kind, position, normal, self.spectrum(light) / (pdf * pmf));
+		sampled.pdf_forward = self.light_origin_pdf(&sampled, vertex);
+		Some(sampled)
+	}
+	
+	/// How much light makes it from `from` to `to`, from 0 to 1.
+	fn visibility(&self, from: &Vertex, to: &Vertex) -> Float {
+		let offset = to.position - from.position;
+		let distance = if self.is_infinite(to) { Float::INFINITY } else { offset.magnitude() - 0.001 };
+		let ray = Ray::new(from.position, offset.normalize(), self.time);
+		self.world.transmittance(ray, 0.001..distance)
+	}
+	
+	/// Connect the first `s` vertices of the light path to the first `t` of the camera path.
+	/// 
+	/// Returns how much light makes it to the camera this way, and where it lands
+	/// in the picture, if it's been moved off of the pixel being sampled.
+	fn connect(&self, light_path: &[Vertex], camera_path: &[Vertex], s: usize, t: usize) -> Option<(Color, Option<Vec2>)> {
+		let pt = &camera_path[t - 1];
+		
+		let (light, sampled, uv) = if s == 0 {
+			// The camera path bumped into something glowing all on its own.
+			(pt.throughput * self.emitted(pt), None, None)
+		} else if t == 1 {
+			// Find where the light path's end shows up in the picture.
+			let qs = &light_path[s - 1];
+			if !self.is_connectible(qs) { return None; }
+			let (uv, lens, importance) = self.camera.sample_towards(qs.position)?;
+			let sampled = Vertex::new(Kind::Camera, lens, None, Color::all(importance));
+			(qs.throughput * self.f_light(qs, &sampled) * sampled.throughput, Some(sampled), Some(uv))
+		} else if s == 1 {
+			// Look for light, like the path tracer.
+			if !self.is_connectible(pt) { return None; }
+			let sampled = self.sample_light(pt)?;
+			(pt.throughput * self.f(pt, &sampled) * sampled.throughput, Some(sampled), None)
+		} else {
+			let qs = &light_path[s - 1];
+			if !self.is_connectible(qs) || !self.is_connectible(pt) { return None; }
+			let distance2 = (qs.position - pt.position).squared_magnitude();
+			(qs.throughput * self.f_light(qs, pt) * self.f(pt, qs) * pt.throughput / distance2, None, None)
+		};
+		if light == Color::ZERO { return None; }
+		
+		let visible = match (s, t) {
+			(0, _) => 1.0,
+			(_, 1) => self.visibility(&light_path[s - 1], sampled.as_ref()?),
+			(1, _) => self.visibility(pt, sampled.as_ref()?),
+			_ => self.visibility(pt, &light_path[s - 1]),
+		};
+		if visible == 0.0 { return None; }
+		
+		// If only the hero made it along either half, it carries the light for the others, but just the once.
+		let collapsed = pt.collapsed || (s > 0 && light_path[s - 1].collapsed);
+		let hero = if collapsed { Color::new(HERO_WEIGHT, 0.0, 0.0) } else { Color::ONE };
+		
+		let weight = self.mis_weight(light_path, camera_path, sampled, s, t);
+		Some((light * hero * visible * weight, uv))
+	}
+	
+	/// How much connecting the paths with `s` and `t` vertices counts for, out of every way
+	/// the same path could've been found, by the balance heuristic.
+	/// `sampled` is the vertex that got picked instead of the end of one of the paths, if any.
+	fn mis_weight(&self, light_path: &[Vertex], camera_path: &[Vertex], sampled: Option<Vertex>, s: usize, t: usize) -> Float {
+		if s + t == 2 { return 1.0; }
+		
+		// Work on copies, to see what each vertex's pdfs would be if the path had been found the other way.
+		let mut light = light_path[..s].to_vec();
+		let mut camera = camera_path[..t].to_vec();
+		match sampled {
+			Some(sampled) if s == 1 => light[0] = sampled,
+			Some(sampled) if t == 1 => camera[0] = sampled,
+			_ => {},
+		}
+		
+		// The two ends being connected can't be delta, or they wouldn't have been connected.
+		camera[t - 1].delta = false;
+		if s > 0 { light[s - 1].delta = false; }
+		
+		let pt = camera[t - 1];
+		let pt_minus = (t > 1).then(|| camera[t - 2]);
+		let qs = (s > 0).then(|| light[s - 1]);
+		let qs_minus = (s > 1).then(|| light[s - 2]);
+		
+		// `pt_minus` is always there without a light path, since it takes at least two camera vertices.
+		camera[t - 1].pdf_reverse = match &qs {
+			Some(qs) => self.pdf(qs, qs_minus.as_ref(), &pt),
+			None => pt_minus.as_ref().map_or(0.0, |pt_minus| self.light_origin_pdf(&pt, pt_minus)),
+		};
+		// There's no other way to find light from something that never sends any out.
+		if s == 0 && camera[t - 1].pdf_reverse == 0.0 { return 1.0; }
+		
+		if let Some(pt_minus) = &pt_minus {
+			camera[t - 2].pdf_reverse = match &qs {
+				Some(qs) => self.pdf(&pt, Some(qs), pt_minus),
+				None => self.light_pdf(&pt, pt_minus),
+			};
+		}
+		if let Some(qs) = &qs {
+			light[s - 1].pdf_reverse = self.pdf(&pt, pt_minus.as_ref(), qs);
+			if let Some(qs_minus) = &qs_minus {
+				light[s - 2].pdf_reverse = self.pdf(qs, Some(&pt), qs_minus);
+			}
+		}
+		
+		// Delta pdfs are left as 0, and skipped over.
+		let remap = |pdf: Float| if pdf == 0.0 { 1.0 } else { pdf };
+		let mut sum = 0.0;
+		
+		let mut ratio = 1.0;
+		for i in (1..t).rev() {
+			ratio *= remap(camera[i].pdf_reverse) / remap(camera[i].pdf_forward);
+			if !camera[i].delta && !camera[i - 1].delta { sum += ratio; }
+		}
+		
+		let mut ratio = 1.0;
+		for i in (0..s).rev() {
+			ratio *= remap(light[i].pdf_reverse) / remap(light[i].pdf_forward);
+			let delta_before = if i > 0 { light[i - 1].delta } else { Self::is_delta_light(&light[0]) };
+			if !light[i].delta && !delta_before { sum += ratio; }
+		}
+		
+		(1.0 + sum).recip()
+	}
+}
+
+impl Raytracer {
+	/// Take a sample of the pixel at `uv` by following a path out of the camera and another one
+	/// out of a light, then connecting every part of one to every part of the other.
+	/// 
+	/// Light that lands somewhere else in the picture gets put in `splats`.
+	pub(super) fn get_bidirectional_sample(&self, world: &World, camera: &Camera, lighting: Lighting, uv: Vec2, splats: &mut Vec<(Vec2, Color)>) -> Color {
+		let wavelengths = self.spectral.then(Wavelengths::sample);
+		let ray = camera.get_ray(uv).with_wavelengths(wavelengths);
+		let scene = Scene::new(world, camera, lighting, ray);
+		let to_rgb = |color: Color| wavelengths.map_or(color, |w| w.to_rgb(color));
+		
+		let camera_path = self.camera_path(&scene, ray);
+		let light_path = self.light_path(&scene);
+		
+		let mut color = Color::ZERO;
+		for t in 1..=camera_path.len() {
+			// Camera paths that escape into the environment can't be connected to anything.
+			let escaped = matches!(camera_path[t - 1].kind, Kind::Environment { .. });
+			
+			for s in 0..=light_path.len() {
+				// Lights and the camera are too small to ever bump into each other.
+				if s + t < 2 || (s == 1 && t == 1) || s + t - 2 > self.max_depth { continue; }
+				if escaped && s > 0 { continue; }
+				
+				match scene.connect(&light_path, &camera_path, s, t) {
+					Some((light, Some(uv))) => splats.push((uv, to_rgb(light))),
+					Some((light, None)) => color += light,
+					None => {},
+				}
+			}
+		}
+		
+		to_rgb(color)
+	}
+	
+	/// Follow a [Ray] out of the camera.
+	fn camera_path(&self, scene: &Scene, ray: Ray) -> Vec<Vertex> {
+		let mut path = vec![Vertex::new(Kind::Camera, ray.position, None, Color::ONE)];
+		let pdf = scene.camera.direction_pdf(ray.position, ray.direction);
+		self.random_walk(scene, ray, Color::ONE, pdf, self.max_depth + 2, &mut path);
+		path
+	}
+	
+	/// Pick a light (or the environment), and follow some light out of it.
+	fn light_path(&self, scene: &Scene) -> Vec<Vertex> {
+		let mut path = Vec::new();
+		let Some((emitter, pmf)) = scene.pick() else { return path };
+		
+		let world = scene.world;
+		let emission = match emitter {
+			None => world.environment.sample().and_then(|(towards, radiance, pdf)| {
+				// Light from one direction of the environment works just like a DirectionalLight.
+				let light = DirectionalLight { direction: -towards, irradiance: radiance };
+				light.emit(scene.bounds).map(|emission| (Kind::Environment { direction: towards }, emission, None, pdf))
+			}),
+			Some(emitter @ Emitter::Light(i)) => world.lights()[i].emit(scene.bounds)
+				.map(|emission| (Kind::Light(emitter), emission, None, emission.direction_pdf)),
+			Some(emitter @ Emitter::Object(i)) => {
+				let object = &world.objects()[i];
+				object.solid.sample_point().and_then(|spot| {
+					// Send light out with a cosine distribution, in front of the surface.
+					let direction = (spot.normal + util::random_unit_vector()).normalize();
+					let cos = direction.dot(spot.normal);
+					(cos > 0.0).then(|| {
+						let emission = Emission {
+							position: spot.position,
+							direction,
+							light: object.material.emitted(&spot),
+							position_pdf: object.solid.point_pdf(spot.position),
+							direction_pdf: cos / std::f64::consts::PI as Float,
+						};
+						(Kind::Light(emitter), emission, Some(spot.normal), emission.direction_pdf)
+					})
+				})
+			},
+		};
+		
+		let Some((kind, emission, normal, direction_pdf)) = emission else { return path };
+		let origin_pdf = pmf * emission.position_pdf;
+		if origin_pdf * direction_pdf <= 0.0 || emission.light == Color::ZERO { return path; }
+		
+		let light = scene.spectrum(emission.light);
+		let mut start = Vertex::new(kind, emission.position, normal, light);
+		start.pdf_forward = origin_pdf;
+		path.push(start);
+		
+		let cos = normal.map_or(1.0, |normal| normal.dot(emission.direction).abs());
+		let throughput = light * cos / (origin_pdf * direction_pdf);
+		let ray = Ray::new(emission.position, emission.direction, scene.time).with_wavelengths(scene.wavelengths);
+		self.random_walk(scene, ray, throughput, direction_pdf, self.max_depth + 1, &mut path);
+		
+		// Faraway lights pick their direction first, and the spot on the disk second,
+		// so the pdfs go the other way around.
+		if scene.is_infinite(&path[0]) {
+			if let Some(next) = path.get_mut(1) {
+				next.pdf_forward = emission.position_pdf * next.normal.map_or(1.0, |normal| normal.dot(emission.direction).abs());
+			}
+			if let Kind::Environment { .. } = kind {
+				path[0].pdf_forward = scene.environment * direction_pdf;
+			}
+		}
+		
+		path
+	}
+	
+	/// Follow `ray` around the world, adding a vertex to `path` everywhere it lands,
+	/// until there are `max_vertices` of them.
+	/// 
+	/// `throughput` is how much the path's carrying so far, and `pdf` is the pdf of
+	/// the ray having gone the way it's going, per unit solid angle.
+	fn random_walk(&self, scene: &Scene, mut ray: Ray, mut throughput: Color, mut pdf: Float, max_vertices: usize, path: &mut Vec<Vertex>) {
+		let world = scene.world;
+		let from_camera = matches!(path[0].kind, Kind::Camera);
+		// How much the path's been dimmed since it started, for Russian roulette.
+		let mut weight = Color::ONE;
+		let mut nesting = Nesting::default();
+		
+		for depth in 0.. {
+			if path.len() >= max_vertices { break; }
+			
+			if depth >= ROULETTE_DEPTH {
+				let survival = weight.x.max(weight.y).max(weight.z).min(1.0);
+				if util::random_float() >= survival { break; }
+				throughput /= survival;
+				weight /= survival;
+			}
+			
+			let prev = path.len() - 1;
+			let closest = world.hit(ray, 0.001..Float::INFINITY);
+			
+			// The fog might get to the ray before anything else does.
+			if let Some(fog) = world.fog {
+				let t_max = closest.map_or(Float::INFINITY, |(_, _, hit)| hit.t);
+				if let Some((attenuation, scattered)) = fog.scatter(ray, t_max) {
+					let albedo = scene.spectrum(fog.albedo);
+					scene.add_vertex(path, Vertex::new(Kind::Medium { albedo }, scattered.position, None, throughput), ray, pdf);
+					
+					// It scatters evenly, so it's just as likely to go either way.
+					pdf = (4.0 * std::f64::consts::PI as Float).recip();
+					path[prev].pdf_reverse = scene.convert(pdf, &path[prev + 1], &path[prev]);
+					
+					let (attenuation, scattered) = Self::carry_wavelengths(ray, attenuation, scattered, 1.0);
+					throughput = throughput * attenuation;
+					weight = weight * attenuation;
+					ray = scattered;
+					continue;
+				}
+			}
+			
+			let Some((index, obj, hit)) = closest else {
+				// Only the camera can see the environment.
+				if from_camera {
+					let direction = ray.direction.normalize();
+					let kind = Kind::Environment { direction };
+					scene.add_vertex(path, Vertex::new(kind, ray.position + direction, None, throughput), ray, pdf);
+				}
+				break;
+			};
+			
+			// Same as for the path tracer, take out whatever got absorbed on the way here.
+			let absorbed = match nesting.interior() {
+				Some(interior) if interior.scatters() => {
+					let speed = ray.direction.magnitude();
+					let (medium_weight, scattered_at) = interior.sample_scatter(hit.t * speed);
+					if let Some(d) = scattered_at {
+						// Nothing can be connected to it through the walls of the object, so it's as good as delta.
+						let direction = medium::sample_henyey_greenstein(ray.direction, interior.g);
+						let scattered = Ray::new(ray.at(d / speed), direction, ray.time);
+						let mut vertex = Vertex::new(Kind::Medium { albedo: Color::ZERO }, scattered.position, None, throughput);
+						vertex.delta = true;
+						scene.add_vertex(path, vertex, ray, pdf);
+						
+						let (medium_weight, scattered) = Self::carry_wavelengths(ray, medium_weight, scattered, 1.0);
+						throughput = throughput * medium_weight;
+						weight = weight * medium_weight;
+						ray = scattered;
+						pdf = 0.0;
+						continue;
+					}
+					medium_weight
+				},
+				Some(interior) => interior.transmittance(hit.t * ray.direction.magnitude()),
+				None => Color::ONE,
+			};
+			let absorbed = scene.spectrum(absorbed);
+			throughput = throughput * absorbed;
+			weight = weight * absorbed;
+			
+			let mut vertex = Vertex::new(Kind::Surface { object: index, ray, hit }, hit.position, Some(hit.normal), throughput);
+			vertex.delta = obj.material.eval(ray, hit, hit.normal).is_none();
+			scene.add_vertex(path, vertex, ray, pdf);
+			
+			let Some((mut attenuation, scattered)) = obj.material.scatter(ray, hit) else { break };
+			let (forward, reverse) = if vertex.delta { (0.0, 0.0) } else {
+				let back = Ray { position: hit.position, direction: -scattered.direction, ..ray };
+				let (f_back, reverse) = obj.material.eval(back, hit, -ray.direction).unwrap_or((Color::ZERO, 0.0));
+				let forward = obj.material.eval(ray, hit, scattered.direction).map_or(0.0, |(_, pdf)| pdf);
+				
+				// Light goes the other way around on light paths, same as in f_light.
+				if !from_camera {
+					let cos = |w: Vec3| hit.normal.dot(w.normalize()).abs();
+					if forward == 0.0 || cos(ray.direction) == 0.0 { break; }
+					attenuation = f_back * cos(scattered.direction) / (cos(ray.direction) * forward);
+				}
+				(forward, reverse)
+			};
+			path[prev].pdf_reverse = scene.convert(reverse, &path[prev + 1], &path[prev]);
+			
+			if obj.solid.is_closed() && scattered.direction.dot(hit.normal) < 0.0 {
+				nesting.cross(index, obj.material.interior(), hit.front_face);
+			}
+			
+			let (attenuation, scattered) = Self::carry_wavelengths(ray, attenuation, scattered, 1.0);
+			throughput = throughput * attenuation;
+			weight = weight * attenuation;
+			ray = scattered;
+			pdf = forward;
+		}
+	}
+}
@@ -0,0 +1,549 @@
+use crate::vector::{Vec2, Vec3, Color, Float};
+use crate::ray::{Ray, HitInfo};
+use crate::material::{Material, Isotropic};
+use crate::world::World;
+use crate::light::tree::Emitter;
+use crate::camera::Camera;
+use crate::spectrum::Wavelengths;
+use crate::medium::{self, Nesting};
+use crate::util;
+
+use std::str::FromStr;
+
+use bidirectional::Lighting;
+
+/// How many bounces a path gets before it might be cut short by Russian roulette.
+const ROULETTE_DEPTH: usize = 3;
+
+/// How much more light the hero wavelength carries once it's the only one left on a path.
+const HERO_WEIGHT: Float = 3.0;
+
+#[derive(Copy, Clone, Debug)]
+pub struct Raytracer {
+	pub screen: Screen,
+	pub max_depth: usize,
+	pub samples: usize,
+	/// Follow individual wavelengths of light around instead of RGB colors.
+	/// It's noisier, but it's the only way to get dispersion.
+	pub spectral: bool,
+	pub integrator: Integrator,
+}
+
+/// How the [Raytracer] goes looking for light.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum Integrator {
+	/// Follow rays out of the camera as they bounce around, looking for light along the way.
+	#[default]
+	PathTracer,
+	/// Follow light out of the lights too, and connect the two paths together.
+	/// Each sample's slower, but it can find light that the path tracer would have
+	/// to get lucky to bump into, like caustics and rooms lit through a gap.
+	Bidirectional,
+}
+impl FromStr for Integrator {
+	type Err = String;
+	
+	fn from_str(name: &str) -> Result<Self, Self::Err> {
+		match name {
+			"path" => Ok(Self::PathTracer),
+			"bidirectional" => Ok(Self::Bidirectional),
+			_ => Err(format!("there's no integrator called {name:?}, try path or bidirectional")),
+		}
+	}
+}
+
+impl Raytracer {
+	/// Add up all the samples for the pixel at `uv`.
+	/// 
+	/// Some integrators find light that lands on other pixels too, which gets put in `splats`
+	/// (along with where in the picture it landed). That should be added in before averaging.
+	/// The [Lighting] only gets used by the bidirectional integrator.
+	pub fn get_pixel(&self, world: &World, camera: &Camera, lighting: Lighting, uv: Vec2, splats: &mut Vec<(Vec2, Color)>) -> Color {
+		let mut sample_results = Color::ZERO;
+		
+		// Collect all the samples.
+		for _ in 0..self.samples {
+			// Offset the uv coords slightly.
+			let uv_ofs = Vec2::new(
+				util::random_float() / self.screen.width as Float,
+				util::random_float() / self.screen.height as Float
+			);
+			// This assumes that the uv coordinates represent
+			// the bottom left of each pixel in the camera, as
+			// `random_float` outputs a number in [0, 1).
+			
+			// Shoot ray out of camera, and add it to the samples.
+			sample_results += match self.integrator {
+				Integrator::PathTracer => self.get_sample(world, camera, uv + uv_ofs),
+				Integrator::Bidirectional => self.get_bidirectional_sample(world, camera, lighting, uv + uv_ofs, splats),
+			};
+		}
+		
+		sample_results
+	}
+	
+	pub fn get_sample(&self, world: &World, camera: &Camera, uv: Vec2) -> Color {
+		if self.spectral {
+			let wavelengths = Wavelengths::sample();
+			let ray = camera.get_ray(uv).with_wavelengths(Some(wavelengths));
+			wavelengths.to_rgb(self.ray_color(world, ray))
+		} else {
+			self.ray_color(world, camera.get_ray(uv))
+		}
+	}
+	
+	/// Find how much light comes back along the [Ray].
+	/// 
+	/// This follows the ray around as it bounces, keeping track of how much of
+	/// the light found along the way would make it back to the start (`throughput`).
+	pub fn ray_color(&self, world: &World, mut ray: Ray) -> Color {
+		let mut color = Color::ZERO;
+		let mut throughput = Color::ONE;
+		// If the last bounce also went looking for light on its own, this is how likely it was
+		// to send the ray this way, so the light found both ways can be weighed.
+		let mut scatter_pdf: Option<Float> = None;
+		let mut nesting = Nesting::default();
+		
+		for depth in 0..self.max_depth {
+			// Once the path's gone on for a bit, randomly stop following the dimmer ones.
+			// The ones that keep going make up for the ones that didn't, so it all evens out.
+			if depth >= ROULETTE_DEPTH {
+				let survival = throughput.x.max(throughput.y).max(throughput.z).min(1.0);
+				if util::random_float() >= survival { break; }
+				throughput /= survival;
+			}
+			
+			let closest = world.hit(ray, 0.001..Float::INFINITY);
+			
+			// The fog might get to the ray before anything else does.
+			if let Some(fog) = world.fog {
+				let t_max = closest.map_or(Float::INFINITY, |(_, _, hit)| hit.t);
+				if let Some((attenuation, scattered)) = fog.scatter(ray, t_max) {
+					// Go looking for light from where it scattered, same as for a surface.
+					let t = (scattered.position - ray.position).magnitude() / ray.direction.magnitude();
+					let phase = Isotropic { albedo: attenuation };
+					let hit = HitInfo::in_volume(ray, t);
+					color += throughput * (self.sample_environment(world, ray, &phase, hit) + self.sample_lights(world, ray, &phase, hit));
+					scatter_pdf = phase.eval(ray, hit, scattered.direction).map(|(_, pdf)| pdf);
+					
+					let (attenuation, scattered) = Self::carry_wavelengths(ray, attenuation, scattered, HERO_WEIGHT);
+					throughput = throughput * attenuation;
+					ray = scattered;
+					continue;
+				}
+			}
+			
+			let Some((index, obj, hit)) = closest else {
+				let sky = world.environment.radiance(ray.direction);
+				let weight = scatter_pdf.map_or(1.0, |pdf| util::power_heuristic(pdf, world.environment.pdf(ray.direction)));
+				color += throughput * ray.wavelengths.map_or(sky, |w| w.sample_rgb(sky)) * weight;
+				break;
+			};
+			
+			// Being inside of something means the ray's been traveling through it,
+			// so take out whatever got absorbed along the way.
+			let absorbed = match nesting.interior() {
+				Some(interior) if interior.scatters() => {
+					// It might've bumped into something and gone off in another direction first.
+					let speed = ray.direction.magnitude();
+					let (weight, scattered_at) = interior.sample_scatter(hit.t * speed);
+					if let Some(d) = scattered_at {
+						// Shadow rays from in here would only run into the walls of the object, so don't
+						// bother looking for light, and count whatever the ray finds on its own in full.
+						let direction = medium::sample_henyey_greenstein(ray.direction, interior.g);
+						scatter_pdf = None;
+						let scattered = Ray::new(ray.at(d / speed), direction, ray.time);
+						let (weight, scattered) = Self::carry_wavelengths(ray, weight, scattered, HERO_WEIGHT);
+						throughput = throughput * weight;
+						ray = scattered;
+						continue;
+					}
+					weight
+				},
+				Some(interior) => interior.transmittance(hit.t * ray.direction.magnitude()),
+				None => Color::ONE,
+			};
+			
+			// It might be glowing, and the last bounce might've gone looking for that already.
+			let emitted = obj.material.emitted(&hit);
+			let emitted = if emitted == Color::ZERO { Color::ZERO } else {
+				let weight = scatter_pdf.map_or(1.0, |pdf| util::power_heuristic(pdf, world.light_pdf(ray.position, index, ray.direction)));
+				ray.wavelengths.map_or(emitted, |w| w.sample_rgb(emitted)) * weight
+			};
+			
+			let direct = emitted + self.sample_environment(world, ray, &*obj.material, hit) + self.sample_lights(world, ray, &*obj.material, hit);
+			color += throughput * direct * ray.wavelengths.map_or(absorbed, |w| w.sample_rgb(absorbed));
+			
+			let Some((attenuation, scattered)) = obj.material.scatter(ray, hit) else { break };
+			scatter_pdf = obj.material.eval(ray, hit, scattered.direction).map(|(_, pdf)| pdf);
+			
+			// Going through the surface of a closed object means going in or out of it.
+			if obj.solid.is_closed() && scattered.direction.dot(hit.normal) < 0.0 {
+				nesting.cross(index, obj.material.interior(), hit.front_face);
+			}
+			
+			let (attenuation, scattered) = Self::carry_wavelengths(ray, absorbed * attenuation, scattered, HERO_WEIGHT);
+			throughput = throughput * attenuation;
+			ray = scattered;
+		}
+		
+		color
+	}
+	
+	/// Pick a direction towards the environment, and see how much of its light
+	/// makes it to the hit and bounces back along the [Ray].
+	fn sample_environment(&self, world: &World, ray: Ray, material: &dyn Material, hit: HitInfo) -> Color {
+		let Some((direction, radiance, light_pdf)) = world.environment.sample() else { return Color::ZERO };
+		if light_pdf <= 0.0 { return Color::ZERO; }
+		
+		match Self::light_from(world, ray, material, hit, direction, Float::INFINITY, radiance) {
+			Some((light, scatter_pdf)) => light * util::power_heuristic(light_pdf, scatter_pdf) / light_pdf,
+			None => Color::ZERO,
+		}
+	}
+	
+	/// Pick one of the [World]'s lights (or glowing objects), and see how much
+	/// of its light makes it to the hit and bounces back along the [Ray].
+	fn sample_lights(&self, world: &World, ray: Ray, material: &dyn Material, hit: HitInfo) -> Color {
+		let Some((emitter, pmf)) = world.pick_light(hit.position) else { return Color::ZERO };
+		
+		match emitter {
+			Emitter::Light(i) => {
+				let Some(sample) = world.lights()[i].sample(hit.position) else { return Color::ZERO };
+				match Self::light_from(world, ray, material, hit, sample.direction, sample.distance, sample.light) {
+					Some((light, _)) => light / pmf,
+					None => Color::ZERO,
+				}
+			},
+			Emitter::Object(i) => {
+				let object = &world.objects()[i];
+				let Some(direction) = object.solid.sample_direction(hit.position) else { return Color::ZERO };
+				
+				// Find the spot on the object it's pointing at, to see how much light comes from there.
+				let towards = Ray::new(hit.position, direction, ray.time);
+				let Some(spot) = object.solid.ray_hits(0.001..Float::INFINITY, towards) else { return Color::ZERO };
+				let emitted = object.material.emitted(&spot);
+				if emitted == Color::ZERO { return Color::ZERO; }
+				
+				let light_pdf = pmf * object.solid.direction_pdf(hit.position, direction);
+				if light_pdf <= 0.0 { return Color::ZERO; }
+				
+				// Stop the shadow ray just short of the object, so it doesn't get in its own way.
+				let distance = spot.t - 0.001;
+				match Self::light_from(world, ray, material, hit, direction, distance, emitted) {
+					Some((light, scatter_pdf)) => light * util::power_heuristic(light_pdf, scatter_pdf) / light_pdf,
+					None => Color::ZERO,
+				}
+			},
+		}
+	}
+	
+	/// How much `light`, coming in from `direction` from `distance` away, bounces back along the [Ray]
+	/// (if nothing's in the way), along with the pdf of the material scattering that way.
+	fn light_from(world: &World, ray: Ray, material: &dyn Material, hit: HitInfo, direction: Vec3, distance: Float, light: Color) -> Option<(Color, Float)> {
+		let (reflected, scatter_pdf) = material.eval(ray, hit, direction)?;
+		if reflected == Color::ZERO { return None; }
+		
+		let shadow = Ray::new(hit.position, direction, ray.time);
+		let visible = world.transmittance(shadow, 0.001..distance);
+		if visible == 0.0 { return None; }
+		
+		let (reflected, light) = match ray.wavelengths {
+			Some(w) => (w.sample_rgb(reflected), w.sample_rgb(light)),
+			None => (reflected, light),
+		};
+		Some((reflected * light * visible, scatter_pdf))
+	}
+	
+	/// When rendering spectrally, turn an RGB `attenuation` into one for each of the
+	/// [Ray]'s wavelengths, and make sure the `scattered` ray keeps carrying them.
+	/// Materials that worked it out at each wavelength themselves mark it [spectral](Wavelengths::spectral).
+	/// 
+	/// If only the hero's left afterwards, the rest get dropped, and the hero's light
+	/// gets multiplied by `hero_weight` so that it carries the light for all three.
+	fn carry_wavelengths(ray: Ray, attenuation: Color, mut scattered: Ray, hero_weight: Float) -> (Color, Ray) {
+		let Some(wavelengths) = ray.wavelengths else { return (attenuation, scattered) };
+		let mut attenuation = match scattered.wavelengths {
+			Some(after) if after.spectral => attenuation,
+			_ => wavelengths.sample_rgb(attenuation),
+		};
+		
+		match scattered.wavelengths {
+			Some(after) if after.collapsed && !wavelengths.collapsed => attenuation = attenuation * Color::new(hero_weight, 0.0, 0.0),
+			Some(_) => {},
+			None => scattered.wavelengths = Some(wavelengths),
+		}
+		if let Some(after) = &mut scattered.wavelengths { after.spectral = false; }
+		
+		(attenuation, scattered)
+	}
+}
+
+// TODO: make a Vec3i and replace Screen with it.
+#[derive(Copy, Clone, Debug)]
+pub struct Screen {
+	pub width: usize, pub height: usize,
+}
+impl Screen {
+	pub fn aspect_ratio(&self) -> Float {
+		self.width as Float / self.height as Float
+	}
+	
+	/// Get the uv coordinates of the bottom left corner of a pixel.
+	/// Pixels go from the top left, and uv coordinates go from the bottom left.
+	pub fn uv(&self, x: usize, y: usize) -> Vec2 {
+		Vec2::new(
+			x as Float / self.width as Float,
+			1.0 - (y + 1) as Float / self.height as Float
+		)
+	}
+	
+	/// Find which pixel some uv coordinates are in, if any.
+	pub fn pixel(&self, uv: Vec2) -> Option<(usize, usize)> {
+		if !(0.0..1.0).contains(&uv.x) || !(0.0..1.0).contains(&uv.y) { return None; }
+		let x = (uv.x * self.width as Float) as usize;
+		let y = self.height - 1 - (uv.y * self.height as Float) as usize;
+		Some((x.min(self.width - 1), y.min(self.height - 1)))
+	}
+}
+impl From<(usize, usize)> for Screen {
+	fn from((width, height): (usize, usize)) -> Self {
+		Screen { width, height }
+	}
+}
+
+pub mod bidirectional;
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::vector::Point3;
+	use crate::world::Object;
+	use crate::solid::sphere::Sphere;
+	use crate::material::{Lambertian, DiffuseLight, Dielectric};
+	use crate::material::subsurface::Subsurface;
+	use crate::material::thin_dielectric::ThinDielectric;
+	use crate::microfacet;
+	use crate::environment::Gradient;
+	use crate::light::{Light, PointLight, LightSample, Emission};
+	use crate::light::tree::LightBounds;
+	
+	use std::sync::Arc;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	
+	/// Render a picture, counting light that landed on each pixel from other pixels' paths too.
+	fn render(raytracer: Raytracer, world: &World, camera: &Camera) -> Vec<Color> {
+		let lighting = Lighting::new(world);
+		let Screen { width, height } = raytracer.screen;
+		let mut splats = Vec::new();
+		let mut pixels = vec![Color::ZERO; width * height];
+		
+		for y in 0..height {
+			for x in 0..width {
+				pixels[y * width + x] += raytracer.get_pixel(world, camera, lighting, raytracer.screen.uv(x, y), &mut splats);
+				for (uv, light) in splats.drain(..) {
+					if let Some((x, y)) = raytracer.screen.pixel(uv) { pixels[y * width + x] += light; }
+				}
+			}
+		}
+		pixels.into_iter().map(|pixel| pixel / raytracer.samples as Float).collect()
+	}
+	
+	/// How bright each pixel is over `batches` separate renders, along with how far off
+	/// that might be (the standard error), so noisy pixels can be told apart from wrong ones.
+	fn brightness(raytracer: Raytracer, world: &World, camera: &Camera, batches: usize) -> Vec<(Float, Float)> {
+		let renders: Vec<_> = (0..batches).map(|_| render(raytracer, world, camera)).collect();
+		let n = batches as Float;
+		(0..renders[0].len()).map(|i| {
+			let mean = renders.iter().map(|pixels| pixels[i].luminance()).sum::<Float>() / n;
+			let variance = renders.iter().map(|pixels| (pixels[i].luminance() - mean).powi(2)).sum::<Float>() / (n - 1.0);
+			(mean, (variance / n).sqrt())
+		}).collect()
+	}
+	
+	/// How bright the whole picture is on average.
+	fn mean(raytracer: Raytracer, world: &World, camera: &Camera) -> Color {
+		let pixels = render(raytracer, world, camera);
+		let count = pixels.len() as Float;
+		pixels.into_iter().fold(Color::ZERO, |sum, pixel| sum + pixel) / count
+	}
+	
+	#[test]
+	fn glass_absorbs_all_the_way_through_things_inside_of_it() {
+		// Glass that doesn't bend light, so the ray goes straight through the middle of both.
+		let mut world = World::default();
+		world.environment = Box::new(Gradient { bottom: Color::ONE, top: Color::ONE });
+		world.push(Object::new(Box::new(Sphere::new(Point3::ZERO, 1.0)), Box::new(Dielectric::tinted(1.0, Color::new(0.5, 1.0, 0.25), 1.0))));
+		world.push(Object::new(Box::new(Sphere::new(Point3::ZERO, 0.5)), Box::new(Dielectric::new(1.0))));
+		
+		// It goes through half a unit of tinted glass on each side of the clear ball.
+		let raytracer = Raytracer { screen: Screen { width: 1, height: 1 }, max_depth: 8, samples: 1, spectral: false, integrator: Integrator::PathTracer };
+		let color = raytracer.ray_color(&world, Ray::new(Point3::new(0.0, 0.0, -5.0), Vec3::Z, 0.0));
+		assert!((color - Color::new(0.5, 1.0, 0.25)).magnitude() < 1e-9, "got {color:?}");
+		
+		// Bidirectional can only start from a camera, so look at the middle through a tiny one.
+		let camera = Camera::new(Point3::new(0.0, 0.0, -5.0), Point3::ZERO, Vec3::Y, 0.1, 1.0, None);
+		let color = mean(Raytracer { samples: 16, integrator: Integrator::Bidirectional, ..raytracer }, &world, &camera);
+		assert!((color - Color::new(0.5, 1.0, 0.25)).magnitude() < 1e-3, "bidirectional got {color:?}");
+	}
+	
+	#[test]
+	fn thin_films_reflect_each_wavelength_by_its_own_amount() {
+		// A film on a ball so big it's flat, with light coming only from straight above.
+		let mut world = World::default();
+		world.environment = Box::new(Gradient { bottom: Color::ZERO, top: Color::ONE });
+		world.push(Object::new(Box::new(Sphere::new(Point3::new(0.0, -1e4, 0.0), 1e4)), Box::new(ThinDielectric::new(1.33).film(300.0))));
+		
+		// Going in with only two steps means only the first reflection ever finds the light.
+		let raytracer = Raytracer { screen: Screen { width: 1, height: 1 }, max_depth: 2, samples: 1, spectral: true, integrator: Integrator::PathTracer };
+		let wavelengths = Wavelengths { nm: [430.0, 520.0, 640.0], collapsed: false, spectral: false };
+		let ray = Ray::new(Point3::new(0.0, 1.0, 0.0), -Vec3::Y, 0.0).with_wavelengths(Some(wavelengths));
+		let n = 400_000;
+		let mut color = Color::ZERO;
+		for _ in 0..n {
+			color += raytracer.ray_color(&world, ray);
+		}
+		
+		let got = <[Float; 3]>::from(color / n as Float);
+		let sky = <[Float; 3]>::from(wavelengths.sample_rgb(Color::ONE));
+		for ((nm, got), sky) in wavelengths.nm.into_iter().zip(got).zip(sky) {
+			let expected = microfacet::thin_film(1.0, 1.33, 300.0, nm) * sky;
+			assert!((got - expected).abs() < 0.05 * expected, "at {nm}nm it reflected {got}, but should've been {expected}");
+		}
+	}
+	
+	#[test]
+	fn subsurface_walks_lose_nothing_without_absorption() {
+		// Everything that goes in comes back out eventually, and the sky's the same everywhere.
+		let mut world = World::default();
+		world.environment = Box::new(Gradient { bottom: Color::ONE, top: Color::ONE });
+		world.push(Object::new(Box::new(Sphere::new(Point3::ZERO, 1.0)), Box::new(Subsurface::new(1.3, Color::ZERO, Color::all(3.0)))));
+		
+		let raytracer = Raytracer { screen: Screen { width: 1, height: 1 }, max_depth: 256, samples: 1, spectral: false, integrator: Integrator::PathTracer };
+		let n = 10_000;
+		let mut total = Color::ZERO;
+		for _ in 0..n {
+			let ray = Ray::new(Point3::new(0.0, 0.0, -5.0), Vec3::new(util::random_float() - 0.5, util::random_float() - 0.5, 5.0), 0.0);
+			total += raytracer.ray_color(&world, ray);
+		}
+		
+		// Only paths that ran out of bounces before getting back out lose anything.
+		let average = total / n as Float;
+		for channel in <[Float; 3]>::from(average) {
+			assert!((0.99..=1.0 + 1e-9).contains(&channel), "got {average:?}");
+		}
+	}
+	
+	/// A [PointLight] that keeps count of how many times it got looked for from inside of a unit ball.
+	struct Watched(PointLight, Arc<AtomicUsize>);
+	impl Light for Watched {
+		fn sample(&self, point: Point3) -> Option<LightSample> {
+			if point.magnitude() < 0.999 { self.1.fetch_add(1, Ordering::Relaxed); }
+			self.0.sample(point)
+		}
+		fn bounds(&self) -> Option<LightBounds> { self.0.bounds() }
+		fn emit(&self, scene: (Point3, Float)) -> Option<Emission> { self.0.emit(scene) }
+		fn emit_pdf(&self, direction: Vec3, scene: (Point3, Float)) -> (Float, Float) { self.0.emit_pdf(direction, scene) }
+	}
+	
+	#[test]
+	fn no_looking_for_light_from_inside_of_things() {
+		let looked = Arc::new(AtomicUsize::new(0));
+		let mut world = World::default();
+		world.push(Object::new(Box::new(Sphere::new(Point3::ZERO, 1.0)), Box::new(Subsurface::new(1.3, Color::ZERO, Color::all(10.0)))));
+		world.push_light(Box::new(Watched(PointLight { position: Point3::new(0.0, 5.0, 0.0), intensity: Color::all(10.0) }, looked.clone())));
+		let raytracer = Raytracer { screen: Screen { width: 1, height: 1 }, max_depth: 64, samples: 1, spectral: false, integrator: Integrator::PathTracer };
+		let mut color = Color::ZERO;
+		for _ in 0..100 {
+			color += raytracer.ray_color(&world, Ray::new(Point3::new(0.0, 0.0, -5.0), Vec3::Z, 0.0));
+		}
+		// The light still gets in, by way of the sky and the light found from the outside.
+		assert!(color != Color::ZERO);
+		assert_eq!(looked.load(Ordering::Relaxed), 0);
+	}
+	
+	#[test]
+	fn russian_roulette_keeps_the_average_the_same() {
+		// Inside a ball with a lamp in the middle, light bounces around forever, with less of it
+		// each time. The walls reflect `albedo` of what lands on them, which is the lamp's light
+		// plus pi times how bright the walls are themselves, so that's how bright they end up.
+		let albedo = 0.8;
+		let expected = albedo / (std::f64::consts::PI as Float * (1.0 - albedo));
+		
+		let mut world = World::default();
+		world.push(Object::new(Box::new(Sphere::new(Point3::ZERO, 1.0)), Box::new(Lambertian { albedo: Color::all(albedo) })));
+		world.push_light(Box::new(PointLight { position: Point3::ZERO, intensity: Color::ONE }));
+		world.build_light_tree();
+		
+		// Way more bounces than would ever happen, so only the roulette stops paths.
+		let screen = Screen { width: 4, height: 4 };
+		let camera = Camera::new(Point3::new(0.0, 0.0, 0.5), Point3::ZERO, Vec3::Y, 90.0, screen.aspect_ratio(), None);
+		let raytracer = Raytracer { screen, max_depth: 10_000, samples: 1024, spectral: false, integrator: Integrator::PathTracer };
+		for integrator in [Integrator::PathTracer, Integrator::Bidirectional] {
+			let color = mean(Raytracer { integrator, ..raytracer }, &world, &camera);
+			for channel in <[Float; 3]>::from(color) {
+				assert!((channel - expected).abs() < 0.02 * expected, "{integrator:?} got {color:?}, not {expected}");
+			}
+		}
+	}
+	
+	#[test]
+	fn integrators_agree_on_a_diffuse_scene() {
+		let mut world = World::default();
+		world.environment = Box::new(Gradient { bottom: Color::ZERO, top: Color::ZERO });
+		world.push(Object::new(
+			Box::new(Sphere::new(Point3::new(0.0, -1000.5, 0.0), 1000.0)),
+			Box::new(Lambertian { albedo: Color::all(0.5) })
+		));
+		world.push(Object::new(
+			Box::new(Sphere::new(Point3::ZERO, 0.5)),
+			Box::new(Lambertian { albedo: Color::new(0.8, 0.3, 0.3) })
+		));
+		world.push(Object::new(
+			Box::new(Sphere::new(Point3::new(0.5, 1.5, 0.5), 0.3)),
+			Box::new(DiffuseLight { emit: Color::all(4.0) })
+		));
+		world.build_bvh();
+		world.build_light_tree();
+		
+		let screen = Screen { width: 16, height: 12 };
+		let camera = Camera::new(Point3::new(0.0, 1.0, 4.0), Point3::ZERO, Vec3::Y, 40.0, screen.aspect_ratio(), None);
+		let raytracer = Raytracer { screen, max_depth: 8, samples: 1024, spectral: false, integrator: Integrator::PathTracer };
+		
+		let path = mean(raytracer, &world, &camera);
+		let bidirectional = mean(Raytracer { integrator: Integrator::Bidirectional, ..raytracer }, &world, &camera);
+		for (a, b) in <[Float; 3]>::from(path).into_iter().zip(<[Float; 3]>::from(bidirectional)) {
+			assert!((a - b).abs() < 0.05 * a, "the path tracer gets {path:?}, but bidirectional gets {bidirectional:?}");
+		}
+	}
+	
+	#[test]
+	fn integrators_agree_on_a_glass_caustic() {
+		// A glass ball on the floor, with a light off to the side, which it focuses into a bright spot.
+		let mut world = World::default();
+		world.environment = Box::new(Gradient { bottom: Color::ZERO, top: Color::ZERO });
+		world.push(Object::new(
+			Box::new(Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0)),
+			Box::new(Lambertian { albedo: Color::all(0.5) })
+		));
+		world.push(Object::new(Box::new(Sphere::new(Point3::new(0.0, 0.5, 0.0), 0.5)), Box::new(Dielectric::new(1.5))));
+		world.push(Object::new(
+			Box::new(Sphere::new(Point3::new(-3.0, 2.0, 0.0), 1.0)),
+			Box::new(DiffuseLight { emit: Color::all(2.0) })
+		));
+		world.build_bvh();
+		world.build_light_tree();
+		
+		// Each pixel covers a good chunk of the floor, so the spot's in a couple of them.
+		let screen = Screen { width: 4, height: 3 };
+		let camera = Camera::new(Point3::new(1.0, 3.0, 2.0), Point3::new(0.8, 0.0, 0.0), Vec3::Y, 40.0, screen.aspect_ratio(), None);
+		let raytracer = Raytracer { screen, max_depth: 8, samples: 1024, spectral: false, integrator: Integrator::PathTracer };
+		
+		let path = brightness(raytracer, &world, &camera, 16);
+		let bidirectional = brightness(Raytracer { integrator: Integrator::Bidirectional, ..raytracer }, &world, &camera, 16);
+		for (i, ((a, a_error), (b, b_error))) in path.into_iter().zip(bidirectional).enumerate() {
+			let (x, y) = (i % screen.width, i / screen.width);
+			assert!((a - b).abs() <= 4.0 * a_error.hypot(b_error), "at ({x}, {y}), the path tracer gets {a} ± {a_error}, but bidirectional gets {b} ± {b_error}");
+		}
+	}
+}
@@ -0,0 +1,72 @@
+use crate::vector::{Color, Transform};
+use crate::solid::Hittable;
+use crate::solid::transformed::Transformed;
+use crate::material::{Material, Lambertian};
+use crate::world::{World, Object};
+
+use std::sync::Arc;
+
+/// A node in a scene graph. Nodes can hold some geometry, some child nodes, or both.
+/// 
+/// Each node's `transform` is relative to its parent, and a node without a
+/// `material` uses whatever its parent has. Geometry and child nodes are behind
+/// [Arc]s, so the same table (or a whole room full of tables) can show up
+/// as many times as you want without being copied.
+#[derive(Clone, Default)]
+pub struct Node {
+	pub transform: Transform,
+	pub geometry: Option<Arc<dyn Hittable + Send + Sync>>,
+	pub material: Option<Arc<dyn Material + Send + Sync>>,
+	pub children: Vec<Arc<Node>>,
+}
+impl Node {
+	/// An empty node, for grouping other nodes under.
+	pub fn new(transform: Transform) -> Self {
+		Self { transform, ..Default::default() }
+	}
+	
+	/// A node holding some geometry.
+	pub fn with_geometry(geometry: Arc<dyn Hittable + Send + Sync>, transform: Transform) -> Self {
+		Self { transform, geometry: Some(geometry), ..Default::default() }
+	}
+	
+	/// Override the material for this node, and any children that don't override it themselves.
+	pub fn material(mut self, material: Arc<dyn Material + Send + Sync>) -> Self {
+		self.material = Some(material);
+		self
+	}
+	
+	pub fn child(mut self, child: impl Into<Arc<Node>>) -> Self {
+		self.children.push(child.into());
+		self
+	}
+	
+	/// Turn every piece of geometry under this node into an [Object] in the [World].
+	/// 
+	/// Don't forget to call [World::build_bvh] once everything's been added.
+	pub fn add_to_world(&self, world: &mut World) {
+		self.flatten(Transform::IDENTITY, None, world);
+	}
+	
+	fn flatten(&self, parent: Transform, inherited: Option<&Arc<dyn Material + Send + Sync>>, world: &mut World) {
+		let transform = self.transform.then(parent);
+		let material = self.material.as_ref().or(inherited);
+		
+		if let Some(geometry) = &self.geometry {
+			let material: Box<dyn Material + Send + Sync> = match material {
+				Some(material) => Box::new(material.clone()),
+				// Nobody up the tree picked a material, so go with something plain.
+				None => Box::new(Lambertian { albedo: Color::all(0.5) }),
+			};
+			
+			world.push(Object::new(
+				Box::new(Transformed::new(geometry.clone(), transform)),
+				material
+			));
+		}
+		
+		for child in &self.children {
+			child.flatten(transform, material, world);
+		}
+	}
+}
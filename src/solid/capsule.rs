@@ -0,0 +1,93 @@
+use crate::vector::{Vec3, Point2, Point3, Float};
+use crate::ray::{Ray, HitInfo};
+use crate::bounding_box::BoundingBox;
+use crate::util;
+
+use super::{Hittable, Crossing, Nearest, Span};
+use super::cone::{angle_around, frustum_crossings, same_point};
+
+use std::ops::Range;
+
+/// A cylinder with hemispheres on both ends, or every point within
+/// `radius` of the line segment between `a` and `b`.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug)]
+pub struct Capsule {
+	pub a: Point3,
+	pub b: Point3,
+	pub radius: Float,
+}
+impl Capsule {
+	/// Panics if `a` and `b` are the same point. Use a [Sphere](super::sphere::Sphere) for that.
+	pub const fn new(a: Point3, b: Point3, radius: Float) -> Self {
+		assert!(!same_point(a, b), "a capsule's ends can't be the same point");
+		Self { a, b, radius }
+	}
+	
+	pub fn crossings(&self, ray: Ray, mut f: impl FnMut(Crossing)) {
+		let height = Vec3::magnitude(self.b - self.a);
+		let axis = (self.b - self.a) / height;
+		let frame = axis.orthonormal_basis();
+		
+		// `v` runs along the whole capsule, ends included.
+		let length = Float::mul_add(2.0, self.radius, height);
+		let v_at = |y: Float| (y + self.radius) / length;
+		
+		frustum_crossings(self.a, self.b, self.radius, self.radius, false, ray, |c| {
+			f(Crossing { uv: Point2::new(c.uv.x, v_at(c.uv.y * height)), dpdv: c.dpdv * length / height, ..c })
+		});
+		
+		for (center, is_end_a) in [(self.a, true), (self.b, false)] {
+			let ofs = ray.position - center;
+			let roots = util::solve_quadratic(
+				ray.direction.squared_magnitude(),
+				ofs.dot(ray.direction),
+				ofs.squared_magnitude() - self.radius.powi(2)
+			);
+			
+			if let Some((t0, t1)) = roots {
+				for t in [t0, t1] {
+					let p = ray.at(t);
+					let y = (p - self.a).dot(axis);
+					
+					// Only the half of each sphere that sticks out past the side.
+					if (is_end_a && y < 0.0) || (!is_end_a && y > height) {
+						let outward_normal = (p - center) / self.radius;
+						let uv = Point2::new(angle_around(outward_normal, frame), v_at(y));
+						let crossing = Crossing::new(t, outward_normal, uv);
+						
+						// `v` goes straight along the axis, so it slides around the end to keep up.
+						// Right at the tip, `u` goes every which way.
+						let h = (p - center).dot(axis);
+						let radial = p - center - h * axis;
+						let r2 = radial.squared_magnitude();
+						if r2 == 0.0 { f(crossing); continue; }
+						let dpdu = std::f64::consts::TAU as Float * Vec3::cross(axis, radial);
+						let dpdv = length * (axis - h / r2 * radial);
+						
+						f(crossing.with_tangents(dpdu, dpdv));
+					}
+				}
+			}
+		}
+	}
+}
+impl Hittable for Capsule {
+	fn ray_hits(&self, t_range: Range<Float>, ray: Ray) -> Option<HitInfo> {
+		let mut nearest = Nearest::new(t_range);
+		self.crossings(ray, |c| nearest.offer(c));
+		nearest.into_hit(ray)
+	}
+	
+	fn bounding_box(&self) -> Option<BoundingBox> {
+		let r = Vec3::all(self.radius);
+		Some(BoundingBox::around(self.a, r).union(BoundingBox::around(self.b, r)))
+	}
+	
+	fn spans(&self, ray: Ray) -> Option<Vec<Span>> {
+		let mut crossings = Vec::new();
+		self.crossings(ray, |c| crossings.push(c));
+		Some(Span::from_crossings(ray, crossings))
+	}
+	
+	fn is_closed(&self) -> bool { true }
+}
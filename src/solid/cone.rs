@@ -0,0 +1,142 @@
+use crate::vector::{Vec3, Point2, Point3, Float};
+use crate::ray::{Ray, HitInfo};
+use crate::bounding_box::BoundingBox;
+use crate::util;
+
+use super::{Hittable, Crossing, Nearest, Span};
+
+use std::ops::Range;
+
+/// A cone standing on a circular base, narrowing to a point at `apex`.
+/// 
+/// Give it a nonzero `top_radius` (see [Cone::frustum]) and it'll be cut off
+/// before reaching the apex instead.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug)]
+pub struct Cone {
+	pub base: Point3,
+	pub apex: Point3,
+	pub radius: Float,
+	pub top_radius: Float,
+	pub capped: bool,
+}
+impl Cone {
+	/// Panics if `base` and `apex` are the same point, since then it doesn't go any way.
+	pub const fn new(base: Point3, apex: Point3, radius: Float) -> Self {
+		assert!(!same_point(base, apex), "a cone's base and apex can't be the same point");
+		Self { base, apex, radius, top_radius: 0.0, capped: true }
+	}
+	
+	/// A cone with its tip sliced off, going from `radius` at `base` to `top_radius` at `top`.
+	pub const fn frustum(base: Point3, top: Point3, radius: Float, top_radius: Float) -> Self {
+		assert!(!same_point(base, top), "a frustum's base and top can't be the same point");
+		Self { base, apex: top, radius, top_radius, capped: true }
+	}
+	
+	/// Leave the ends open, like a funnel.
+	pub const fn uncapped(mut self) -> Self {
+		self.capped = false; self
+	}
+	
+	pub fn crossings(&self, ray: Ray, f: impl FnMut(Crossing)) {
+		frustum_crossings(self.base, self.apex, self.radius, self.top_radius, self.capped, ray, f);
+	}
+}
+impl Hittable for Cone {
+	fn ray_hits(&self, t_range: Range<Float>, ray: Ray) -> Option<HitInfo> {
+		let mut nearest = Nearest::new(t_range);
+		self.crossings(ray, |c| nearest.offer(c));
+		nearest.into_hit(ray)
+	}
+	
+	fn bounding_box(&self) -> Option<BoundingBox> {
+		Some(frustum_bounds(self.base, self.apex, self.radius, self.top_radius))
+	}
+	
+	fn spans(&self, ray: Ray) -> Option<Vec<Span>> {
+		// Without caps it's open on both ends, so there's no inside.
+		if !self.capped { return None; }
+		
+		let mut crossings = Vec::new();
+		self.crossings(ray, |c| crossings.push(c));
+		Some(Span::from_crossings(ray, crossings))
+	}
+	
+	fn is_closed(&self) -> bool { self.capped }
+}
+
+/// Get the box around a truncated cone. Each end is a disk, which sticks
+/// out less along the axes that it's tilted towards.
+pub(super) fn frustum_bounds(a: Point3, b: Point3, ra: Float, rb: Float) -> BoundingBox {
+	let axis = Vec3::normalize(b - a);
+	let disk = Vec3::new(
+		Float::sqrt(1.0 - axis.x.powi(2)),
+		Float::sqrt(1.0 - axis.y.powi(2)),
+		Float::sqrt(1.0 - axis.z.powi(2))
+	);
+	
+	BoundingBox::around(a, disk * ra).union(BoundingBox::around(b, disk * rb))
+}
+
+/// Whether two points are exactly the same, for catching shapes whose axis has no length.
+pub(super) const fn same_point(a: Point3, b: Point3) -> bool {
+	a.x == b.x && a.y == b.y && a.z == b.z
+}
+
+/// How far around `axis_frame` the vector `radial` points, in [0, 1).
+pub(super) fn angle_around(radial: Vec3, (tangent, bitangent): (Vec3, Vec3)) -> Float {
+	let angle = Float::atan2(radial.dot(bitangent), radial.dot(tangent));
+	(angle / (std::f64::consts::TAU as Float)).rem_euclid(1.0)
+}
+
+/// Finds everywhere a [Ray] crosses a (possibly capped) truncated cone going
+/// from radius `ra` at `a` to radius `rb` at `b`. Cylinders are the `ra == rb` case.
+pub(super) fn frustum_crossings(a: Point3, b: Point3, ra: Float, rb: Float, capped: bool, ray: Ray, mut f: impl FnMut(Crossing)) {
+	let height = Vec3::magnitude(b - a);
+	let axis = (b - a) / height;
+	let frame = axis.orthonormal_basis();
+	
+	// How much the radius grows per unit of height.
+	let k = (rb - ra) / height;
+	
+	let ofs = ray.position - a;
+	let y0 = ofs.dot(axis);
+	let yd = ray.direction.dot(axis);
+	let r0 = k.mul_add(y0, ra);
+	
+	// Points on the side are where |p - y*axis|^2 = (ra + k*y)^2.
+	let qa = ray.direction.squared_magnitude() - yd.powi(2) * (1.0 + k.powi(2));
+	let half_b = ofs.dot(ray.direction) - y0 * yd - k * yd * r0;
+	let c = ofs.squared_magnitude() - y0.powi(2) - r0.powi(2);
+	
+	if let Some((t0, t1)) = util::solve_quadratic(qa, half_b, c) {
+		for t in [t0, t1] {
+			let y = t.mul_add(yd, y0);
+			if (0.0..=height).contains(&y) {
+				let radial = ofs + t * ray.direction - y * axis;
+				let outward_normal = Vec3::normalize(radial - k * k.mul_add(y, ra) * axis);
+				let uv = Point2::new(angle_around(radial, frame), y / height);
+				let dpdu = std::f64::consts::TAU as Float * Vec3::cross(axis, radial);
+				let dpdv = height * axis + k * height * radial.normalize();
+				
+				f(Crossing::new(t, outward_normal, uv).with_tangents(dpdu, dpdv));
+			}
+		}
+	}
+	
+	if capped && yd != 0.0 {
+		for (y, radius, outward_normal) in [(0.0, ra, -axis), (height, rb, axis)] {
+			if radius <= 0.0 { continue; }
+			
+			let t = (y - y0) / yd;
+			let p = ofs + t * ray.direction - y * axis;
+			if p.squared_magnitude() <= radius.powi(2) {
+				let uv = Point2::new(
+					0.5 + p.dot(frame.0) / (2.0 * radius),
+					0.5 + p.dot(frame.1) / (2.0 * radius)
+				);
+				
+				f(Crossing::new(t, outward_normal, uv).with_tangents(2.0 * radius * frame.0, 2.0 * radius * frame.1));
+			}
+		}
+	}
+}
@@ -0,0 +1,67 @@
+use crate::vector::{Vec3, Float};
+use crate::ray::{Ray, HitInfo};
+use crate::bounding_box::BoundingBox;
+use crate::util;
+
+use super::Hittable;
+
+use std::ops::Range;
+
+/// A cloud of stuff with the same density all the way through, filling up some `boundary`.
+/// 
+/// Rays don't bounce off the outside, they go in and get scattered somewhere inside,
+/// so pair it up with a material like [Isotropic](crate::material::Isotropic).
+/// The boundary needs to be convex, or rays will think they left it early.
+pub struct ConstantMedium {
+	pub boundary: Box<dyn Hittable + Send + Sync>,
+	pub density: Float,
+}
+impl ConstantMedium {
+	pub fn new(boundary: Box<dyn Hittable + Send + Sync>, density: Float) -> Self {
+		Self { boundary, density }
+	}
+}
+impl Hittable for ConstantMedium {
+	fn ray_hits(&self, t_range: Range<Float>, ray: Ray) -> Option<HitInfo> {
+		let (enter, t_enter, t_exit) = boundary_span(self.boundary.as_ref(), t_range, ray)?;
+		
+		// How far the ray gets before hitting something is exponentially distributed.
+		let speed = ray.direction.magnitude();
+		let distance_inside = (t_exit - t_enter) * speed;
+		let hit_distance = -util::random_float().ln() / self.density;
+		if hit_distance > distance_inside { return None; }
+		
+		let t = t_enter + hit_distance / speed;
+		Some(HitInfo {
+			position: ray.at(t),
+			t,
+			// These don't mean anything inside of a volume.
+			normal: Vec3::X,
+			front_face: true,
+			uv: enter.uv,
+			dpdu: Vec3::Z,
+			dpdv: Vec3::Y,
+		})
+	}
+	
+	fn bounding_box(&self) -> Option<BoundingBox> {
+		self.boundary.bounding_box()
+	}
+	
+	fn transmittance(&self, t_range: Range<Float>, ray: Ray) -> Float {
+		boundary_span(self.boundary.as_ref(), t_range, ray).map_or(1.0, |(_, t_enter, t_exit)| {
+			Float::exp(-self.density * (t_exit - t_enter) * ray.direction.magnitude())
+		})
+	}
+}
+
+/// Find the stretch of `t_range` that's inside a volume's (convex) boundary, even if
+/// the ray started inside. Also gives back where the ray entered, for its uv.
+pub(super) fn boundary_span(boundary: &(dyn Hittable + Send + Sync), t_range: Range<Float>, ray: Ray) -> Option<(HitInfo, Float, Float)> {
+	let enter = boundary.ray_hits(Float::NEG_INFINITY..Float::INFINITY, ray)?;
+	let exit = boundary.ray_hits(enter.t + 0.0001..Float::INFINITY, ray)?;
+	
+	let t_enter = enter.t.max(t_range.start);
+	let t_exit = exit.t.min(t_range.end);
+	(t_enter < t_exit).then_some((enter, t_enter, t_exit))
+}
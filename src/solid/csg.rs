@@ -0,0 +1,173 @@
+use crate::vector::Float;
+use crate::ray::{Ray, HitInfo};
+use crate::bounding_box::BoundingBox;
+
+use super::{Hittable, Crossing, Span};
+
+use std::ops::Range;
+use std::fmt;
+
+/// How a [Csg] combines its two solids.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Operation {
+	/// Everything inside either solid.
+	Union,
+	/// Only what's inside both solids.
+	Intersection,
+	/// Everything inside `a`, with `b` carved out of it.
+	Difference,
+}
+impl Operation {
+	fn inside(self, in_a: bool, in_b: bool) -> bool {
+		match self {
+			Operation::Union => in_a || in_b,
+			Operation::Intersection => in_a && in_b,
+			Operation::Difference => in_a && !in_b,
+		}
+	}
+}
+
+/// Constructive solid geometry: two solids combined into one with some [Operation].
+/// 
+/// Both of them need to be closed (see [Hittable::is_closed]), since this works by
+/// figuring out which parts of the [Ray] are inside which solid. Making one out of
+/// anything else gives a [NotClosed] error.
+/// A `Csg` is closed too, so they can be nested as much as you like.
+pub struct Csg {
+	pub a: Box<dyn Hittable + Send + Sync>,
+	pub b: Box<dyn Hittable + Send + Sync>,
+	pub operation: Operation,
+}
+impl Csg {
+	pub fn new(a: Box<dyn Hittable + Send + Sync>, b: Box<dyn Hittable + Send + Sync>, operation: Operation) -> Result<Self, NotClosed> {
+		if !a.is_closed() || !b.is_closed() { return Err(NotClosed); }
+		Ok(Self { a, b, operation })
+	}
+	
+	pub fn union(a: Box<dyn Hittable + Send + Sync>, b: Box<dyn Hittable + Send + Sync>) -> Result<Self, NotClosed> {
+		Self::new(a, b, Operation::Union)
+	}
+	
+	pub fn intersection(a: Box<dyn Hittable + Send + Sync>, b: Box<dyn Hittable + Send + Sync>) -> Result<Self, NotClosed> {
+		Self::new(a, b, Operation::Intersection)
+	}
+	
+	pub fn difference(a: Box<dyn Hittable + Send + Sync>, b: Box<dyn Hittable + Send + Sync>) -> Result<Self, NotClosed> {
+		Self::new(a, b, Operation::Difference)
+	}
+}
+
+/// What [Csg] gives back when one of its solids isn't closed, so it has no inside to combine.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct NotClosed;
+impl fmt::Display for NotClosed {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "CSG only works on closed solids")
+	}
+}
+impl std::error::Error for NotClosed {}
+
+impl Hittable for Csg {
+	fn ray_hits(&self, t_range: Range<Float>, ray: Ray) -> Option<HitInfo> {
+		self.spans(ray)?
+			.into_iter()
+			.flat_map(|span| [span.enter, span.exit])
+			.find(|c| t_range.contains(&c.t))
+			.map(|c| c.into_hit(ray))
+	}
+	
+	fn bounding_box(&self) -> Option<BoundingBox> {
+		let (a, b) = (self.a.bounding_box(), self.b.bounding_box());
+		match self.operation {
+			Operation::Union => Some(a?.union(b?)),
+			Operation::Intersection => match (a, b) {
+				(Some(a), Some(b)) => Some(a.intersection(b)),
+				(a, b) => a.or(b),
+			},
+			Operation::Difference => a,
+		}
+	}
+	
+	fn spans(&self, ray: Ray) -> Option<Vec<Span>> {
+		let a = self.a.spans(ray)?;
+		let b = self.b.spans(ray)?;
+		
+		// Every place the ray goes in or out of either solid, in order.
+		let mut events: Vec<(Crossing, bool, bool)> = Vec::with_capacity(2 * (a.len() + b.len()));
+		for (spans, is_a) in [(a, true), (b, false)] {
+			for span in spans {
+				events.push((span.enter, is_a, true));
+				events.push((span.exit, is_a, false));
+			}
+		}
+		events.sort_by(|x, y| x.0.t.total_cmp(&y.0.t));
+		
+		// Walk along the ray, and keep the crossings where being inside the result changes.
+		let (mut in_a, mut in_b) = (false, false);
+		let mut spans = Vec::new();
+		let mut enter = None;
+		
+		for (crossing, is_a, entering) in events {
+			let was_inside = self.operation.inside(in_a, in_b);
+			if is_a { in_a = entering; } else { in_b = entering; }
+			let inside = self.operation.inside(in_a, in_b);
+			
+			if was_inside == inside { continue; }
+			
+			// The walls of a hole that's been carved out face inwards.
+			let crossing = if !is_a && self.operation == Operation::Difference {
+				Crossing { outward_normal: -crossing.outward_normal, ..crossing }
+			} else {
+				crossing
+			};
+			
+			if inside {
+				enter = Some(crossing);
+			} else if let Some(enter) = enter.take() {
+				spans.push(Span { enter, exit: crossing });
+			}
+		}
+		
+		Some(spans)
+	}
+	
+	fn is_closed(&self) -> bool { true }
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::vector::{Vec3, Point3};
+	use crate::solid::sphere::Sphere;
+	use crate::solid::cylinder::Cylinder;
+	use crate::solid::group::Group;
+	
+	use std::sync::Arc;
+	
+	fn ball(x: Float) -> Box<Sphere> {
+		Box::new(Sphere::new(Point3::new(x, 0.0, 0.0), 1.0))
+	}
+	
+	#[test]
+	fn open_solids_get_rejected() {
+		let pipe = Cylinder::new(Point3::ZERO, Point3::new(0.0, 1.0, 0.0), 0.5).uncapped();
+		assert_eq!(Csg::union(Box::new(pipe), ball(0.0)).err(), Some(NotClosed));
+		assert_eq!(Csg::difference(ball(0.0), Box::new(pipe)).err(), Some(NotClosed));
+	}
+	
+	#[test]
+	fn groups_of_closed_solids_work() {
+		// Two overlapping balls from -1 to 2, with a ball from 1 to 3 taken out.
+		let group = Group::new(vec![Arc::new(Sphere::new(Point3::ZERO, 1.0)), Arc::new(Sphere::new(Point3::new(1.0, 0.0, 0.0), 1.0))]);
+		let csg = Csg::difference(Box::new(group), ball(2.0)).unwrap();
+		
+		let ray = Ray::new(Point3::new(-5.0, 0.0, 0.0), Vec3::X, 0.0);
+		let spans = csg.spans(ray).unwrap();
+		assert_eq!(spans.len(), 1);
+		assert!((spans[0].enter.t - 4.0).abs() < 1e-9);
+		assert!((spans[0].exit.t - 6.0).abs() < 1e-9);
+		
+		let hit = csg.ray_hits(0.001..Float::INFINITY, ray).unwrap();
+		assert!((hit.t - 4.0).abs() < 1e-9);
+	}
+}
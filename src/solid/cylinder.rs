@@ -0,0 +1,57 @@
+use crate::vector::{Point3, Float};
+use crate::ray::{Ray, HitInfo};
+use crate::bounding_box::BoundingBox;
+
+use super::{Hittable, Crossing, Nearest, Span};
+use super::cone::{frustum_crossings, frustum_bounds, same_point};
+
+use std::ops::Range;
+
+/// A cylinder going from the center of its `base` to the center of its `top`.
+/// 
+/// Uncapped cylinders are hollow tubes, which is what you want for pipes.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug)]
+pub struct Cylinder {
+	pub base: Point3,
+	pub top: Point3,
+	pub radius: Float,
+	pub capped: bool,
+}
+impl Cylinder {
+	/// Panics if `base` and `top` are the same point, since then it doesn't go any way.
+	pub const fn new(base: Point3, top: Point3, radius: Float) -> Self {
+		assert!(!same_point(base, top), "a cylinder's base and top can't be the same point");
+		Self { base, top, radius, capped: true }
+	}
+	
+	/// Leave the ends open, like a pipe.
+	pub const fn uncapped(mut self) -> Self {
+		self.capped = false; self
+	}
+	
+	pub fn crossings(&self, ray: Ray, f: impl FnMut(Crossing)) {
+		frustum_crossings(self.base, self.top, self.radius, self.radius, self.capped, ray, f);
+	}
+}
+impl Hittable for Cylinder {
+	fn ray_hits(&self, t_range: Range<Float>, ray: Ray) -> Option<HitInfo> {
+		let mut nearest = Nearest::new(t_range);
+		self.crossings(ray, |c| nearest.offer(c));
+		nearest.into_hit(ray)
+	}
+	
+	fn bounding_box(&self) -> Option<BoundingBox> {
+		Some(frustum_bounds(self.base, self.top, self.radius, self.radius))
+	}
+	
+	fn spans(&self, ray: Ray) -> Option<Vec<Span>> {
+		// Without caps it's open on both ends, so there's no inside.
+		if !self.capped { return None; }
+		
+		let mut crossings = Vec::new();
+		self.crossings(ray, |c| crossings.push(c));
+		Some(Span::from_crossings(ray, crossings))
+	}
+	
+	fn is_closed(&self) -> bool { self.capped }
+}
@@ -0,0 +1,60 @@
+use crate::vector::Float;
+use crate::ray::{Ray, HitInfo};
+use crate::bounding_box::BoundingBox;
+use crate::bvh::Bvh;
+
+use super::{Hittable, Span};
+
+use std::ops::Range;
+use std::sync::Arc;
+
+/// A bunch of solids that act as one, sorted into their own [Bvh].
+/// 
+/// Put one of these behind an [Arc] and [Transformed](super::transformed::Transformed)
+/// instances of it will share the whole thing, tree and all.
+pub struct Group {
+	pub solids: Vec<Arc<dyn Hittable + Send + Sync>>,
+	bvh: Bvh,
+}
+impl Group {
+	pub fn new(solids: Vec<Arc<dyn Hittable + Send + Sync>>) -> Self {
+		let boxes: Vec<_> = solids.iter().map(|s| s.bounding_box()).collect();
+		Self { bvh: Bvh::build(&boxes), solids }
+	}
+}
+impl Hittable for Group {
+	fn ray_hits(&self, t_range: Range<Float>, ray: Ray) -> Option<HitInfo> {
+		self.bvh.ray_hits(t_range, ray, |i, t_range| {
+			self.solids[i].ray_hits(t_range, ray).map(|hit| (hit, hit.t))
+		})
+	}
+	
+	fn bounding_box(&self) -> Option<BoundingBox> {
+		self.bvh.bounds()
+	}
+	
+	/// Everything that's inside any of the solids, if they're all closed.
+	fn spans(&self, ray: Ray) -> Option<Vec<Span>> {
+		let mut all = Vec::new();
+		for solid in &self.solids {
+			all.extend(solid.spans(ray)?);
+		}
+		all.sort_by(|a, b| a.enter.t.total_cmp(&b.enter.t));
+		
+		// Overlapping stretches join up into one.
+		let mut spans: Vec<Span> = Vec::with_capacity(all.len());
+		for span in all {
+			match spans.last_mut() {
+				Some(last) if span.enter.t <= last.exit.t => {
+					if span.exit.t > last.exit.t { last.exit = span.exit; }
+				},
+				_ => spans.push(span),
+			}
+		}
+		Some(spans)
+	}
+	
+	fn is_closed(&self) -> bool {
+		self.solids.iter().all(|solid| solid.is_closed())
+	}
+}
@@ -0,0 +1,294 @@
+use crate::vector::{Vec3, Point2, Point3, Float};
+use crate::ray::{Ray, HitInfo};
+use crate::bounding_box::BoundingBox;
+
+use super::Hittable;
+
+use image::ImageError;
+use image::error::{ParameterError, ParameterErrorKind};
+
+use std::ops::Range;
+use std::path::Path;
+
+/// How many cells wide each block is. Blocks remember the highest point
+/// inside of them, so rays passing over them can skip the whole thing.
+const BLOCK_SIZE: usize = 16;
+
+/// Terrain made from a regular grid of heights, like a grayscale image.
+/// 
+/// The heights are never turned into triangles up front. Instead, rays walk
+/// across the grid, only looking at the cells they pass over. The grid sits on
+/// the XZ plane, from `origin` to `origin + size`, with heights going up to `size.y`.
+#[derive(Clone, Debug)]
+pub struct Heightfield {
+	pub origin: Point3,
+	pub size: Vec3,
+	/// How many samples there are along X and Z.
+	pub resolution: (usize, usize),
+	/// Stored as `f32`s to save space, since there can be millions of them.
+	heights: Vec<f32>,
+	block_max: Vec<f32>,
+	lowest: Float,
+	highest: Float,
+}
+impl Heightfield {
+	/// Make a heightfield from a grid of heights in [0, 1], listed row by row along X.
+	/// Panics if there are fewer than 2x2 heights, or the count doesn't match the resolution.
+	pub fn new(heights: Vec<f32>, resolution: (usize, usize), origin: Point3, size: Vec3) -> Self {
+		let (nx, nz) = resolution;
+		assert!(nx >= 2 && nz >= 2, "heightfields need at least 2x2 samples");
+		assert_eq!(heights.len(), nx * nz, "wrong number of heights for the resolution");
+		
+		let mut field = Self {
+			origin, size, resolution, heights,
+			block_max: Vec::new(),
+			lowest: 0.0, highest: 0.0,
+		};
+		
+		let (lowest, highest) = field.heights.iter()
+			.fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), &h| (lo.min(h), hi.max(h)));
+		field.lowest = field.world_height(lowest);
+		field.highest = field.world_height(highest);
+		
+		let (bx, bz) = field.blocks();
+		field.block_max = vec![f32::NEG_INFINITY; bx * bz];
+		for z in 0..nz {
+			for x in 0..nx {
+				let h = field.heights[z * nx + x];
+				// Samples on a block's edge belong to the blocks on both sides.
+				for block_z in (z.saturating_sub(1) / BLOCK_SIZE)..=(z.min(nz - 2) / BLOCK_SIZE) {
+					for block_x in (x.saturating_sub(1) / BLOCK_SIZE)..=(x.min(nx - 2) / BLOCK_SIZE) {
+						let max = &mut field.block_max[block_z * bx + block_x];
+						*max = max.max(h);
+					}
+				}
+			}
+		}
+		
+		field
+	}
+	
+	/// Load a grayscale image as a heightfield. Black is the bottom, white is `size.y` up.
+	/// Images smaller than 2x2 pixels don't have any cells, so they give an error.
+	pub fn load(path: impl AsRef<Path>, origin: Point3, size: Vec3) -> image::ImageResult<Self> {
+		let image = image::open(path)?.into_luma16();
+		let resolution = (image.width() as usize, image.height() as usize);
+		if resolution.0 < 2 || resolution.1 < 2 {
+			return Err(ImageError::Parameter(ParameterError::from_kind(ParameterErrorKind::Generic(
+				format!("heightfields need at least 2x2 pixels, but the image is {}x{}", resolution.0, resolution.1),
+			))));
+		}
+		let heights = image.pixels().map(|p| p.0[0] as f32 / u16::MAX as f32).collect();
+		
+		Ok(Self::new(heights, resolution, origin, size))
+	}
+	
+	fn cells(&self) -> (usize, usize) {
+		(self.resolution.0 - 1, self.resolution.1 - 1)
+	}
+	
+	fn blocks(&self) -> (usize, usize) {
+		let (cx, cz) = self.cells();
+		(cx.div_ceil(BLOCK_SIZE), cz.div_ceil(BLOCK_SIZE))
+	}
+	
+	fn cell_size(&self) -> (Float, Float) {
+		let (cx, cz) = self.cells();
+		(self.size.x / cx as Float, self.size.z / cz as Float)
+	}
+	
+	fn world_height(&self, h: f32) -> Float {
+		Float::mul_add(h as Float, self.size.y, self.origin.y)
+	}
+	
+	/// Get the sample at (x, z), clamped to the edges of the grid.
+	fn sample(&self, x: isize, z: isize) -> Float {
+		let x = x.clamp(0, self.resolution.0 as isize - 1) as usize;
+		let z = z.clamp(0, self.resolution.1 as isize - 1) as usize;
+		self.world_height(self.heights[z * self.resolution.0 + x])
+	}
+	
+	fn vertex(&self, x: usize, z: usize) -> Point3 {
+		let (sx, sz) = self.cell_size();
+		Point3::new(
+			Float::mul_add(x as Float, sx, self.origin.x),
+			self.sample(x as isize, z as isize),
+			Float::mul_add(z as Float, sz, self.origin.z)
+		)
+	}
+	
+	/// The smooth normal at a sample, from the slope between its neighbors.
+	fn vertex_normal(&self, x: usize, z: usize) -> Vec3 {
+		let (sx, sz) = self.cell_size();
+		let (x, z) = (x as isize, z as isize);
+		Vec3::new(
+			(self.sample(x - 1, z) - self.sample(x + 1, z)) / (2.0 * sx),
+			1.0,
+			(self.sample(x, z - 1) - self.sample(x, z + 1)) / (2.0 * sz)
+		).normalize()
+	}
+	
+	/// Check the two triangles that make up a cell.
+	fn cell_hits(&self, x: usize, z: usize, t_range: Range<Float>, ray: Ray) -> Option<HitInfo> {
+		let corners = [(x, z), (x + 1, z), (x + 1, z + 1), (x, z + 1)];
+		let triangles = [[corners[0], corners[1], corners[2]], [corners[0], corners[2], corners[3]]];
+		let mut closest = None;
+		let mut t_end = t_range.end;
+		
+		for (i, tri) in triangles.iter().enumerate() {
+			if let Some((t, u, v)) = triangle_hits(tri.map(|(x, z)| self.vertex(x, z)), t_range.start..t_end, ray) {
+				t_end = t;
+				closest = Some((i, u, v));
+			}
+		}
+		
+		let (i, u, v) = closest?;
+		let t = t_end;
+		let [n0, n1, n2] = triangles[i].map(|(x, z)| self.vertex_normal(x, z));
+		let outward_normal = Vec3::normalize((1.0 - u - v) * n0 + u * n1 + v * n2);
+		
+		let position = ray.at(t);
+		let (front_face, normal) = HitInfo::get_face_normal_info(ray, outward_normal);
+		let uv = Point2::new(
+			(position.x - self.origin.x) / self.size.x,
+			(position.z - self.origin.z) / self.size.z
+		);
+		// Following the slope of the smooth normal.
+		let dpdu = self.size.x * Vec3::new(outward_normal.y, -outward_normal.x, 0.0).normalize();
+		let dpdv = self.size.z * Vec3::new(0.0, -outward_normal.z, outward_normal.y).normalize();
+		
+		Some(HitInfo { position, normal, t, front_face, uv, dpdu, dpdv })
+	}
+}
+impl Hittable for Heightfield {
+	fn ray_hits(&self, t_range: Range<Float>, ray: Ray) -> Option<HitInfo> {
+		let bounds = self.bounding_box()?;
+		let (t_start, t_end) = bounds.ray_span(t_range.clone(), ray)?;
+		
+		// Squish the ray so each cell is 1 unit across. That doesn't change `t`.
+		let (sx, sz) = self.cell_size();
+		let grid_ray = Ray::new(
+			Point3::new((ray.position.x - self.origin.x) / sx, ray.position.y, (ray.position.z - self.origin.z) / sz),
+			Vec3::new(ray.direction.x / sx, ray.direction.y, ray.direction.z / sz),
+			ray.time
+		);
+		let block_ray = Ray::new(
+			Point3::new(grid_ray.position.x / BLOCK_SIZE as Float, ray.position.y, grid_ray.position.z / BLOCK_SIZE as Float),
+			Vec3::new(grid_ray.direction.x / BLOCK_SIZE as Float, ray.direction.y, grid_ray.direction.z / BLOCK_SIZE as Float),
+			ray.time
+		);
+		
+		// The lowest the ray gets over some stretch, for comparing against the terrain's highest.
+		let lowest_between = |t0: Float, t1: Float| Float::min(ray.at(t0).y, ray.at(t1).y);
+		
+		let (bx, bz) = self.blocks();
+		let (cx, cz) = self.cells();
+		let mut result = None;
+		
+		walk_grid((bx, bz), block_ray, t_start, t_end, |block_x, block_z, t0, t1| {
+			if lowest_between(t0, t1) > self.world_height(self.block_max[block_z * bx + block_x]) {
+				return false;
+			}
+			
+			walk_grid((cx, cz), grid_ray, t0, t1, |x, z, t0, t1| {
+				if x / BLOCK_SIZE != block_x || z / BLOCK_SIZE != block_z { return false; }
+				
+				let highest = [(x, z), (x + 1, z), (x, z + 1), (x + 1, z + 1)]
+					.map(|(x, z)| self.sample(x as isize, z as isize))
+					.into_iter()
+					.fold(Float::NEG_INFINITY, Float::max);
+				if lowest_between(t0, t1) > highest { return false; }
+				
+				result = self.cell_hits(x, z, t_range.clone(), ray);
+				result.is_some()
+			});
+			
+			result.is_some()
+		});
+		
+		result
+	}
+	
+	fn bounding_box(&self) -> Option<BoundingBox> {
+		Some(BoundingBox::new(
+			self.origin.set_y(self.lowest),
+			(self.origin + self.size).set_y(self.highest)
+		))
+	}
+}
+
+/// Walk a ray across a grid of `cells`, each of which is 1 unit across in X and Z,
+/// visiting each cell it passes over in order along with the stretch of `t` spent over it.
+/// 
+/// Stops as soon as `visit` returns `true`.
+fn walk_grid((cols, rows): (usize, usize), ray: Ray, t_start: Float, t_end: Float, mut visit: impl FnMut(usize, usize, Float, Float) -> bool) {
+	let start = ray.at(t_start);
+	let mut cell = [
+		(start.x.floor() as isize).clamp(0, cols as isize - 1),
+		(start.z.floor() as isize).clamp(0, rows as isize - 1),
+	];
+	let limits = [cols as isize, rows as isize];
+	let position = [start.x, start.z];
+	let direction = [ray.direction.x, ray.direction.z];
+	
+	let mut step = [0isize; 2];
+	let mut t_next = [Float::INFINITY; 2];
+	let mut t_delta = [Float::INFINITY; 2];
+	for axis in 0..2 {
+		if direction[axis] != 0.0 {
+			step[axis] = direction[axis].signum() as isize;
+			let boundary = (cell[axis] + (step[axis] > 0) as isize) as Float;
+			t_next[axis] = t_start + (boundary - position[axis]) / direction[axis];
+			t_delta[axis] = direction[axis].recip().abs();
+		}
+	}
+	
+	let mut t = t_start;
+	loop {
+		let t_exit = t_next[0].min(t_next[1]).min(t_end);
+		if visit(cell[0] as usize, cell[1] as usize, t, t_exit) || t_exit >= t_end { return; }
+		
+		let axis = if t_next[0] < t_next[1] { 0 } else { 1 };
+		cell[axis] += step[axis];
+		if !(0..limits[axis]).contains(&cell[axis]) { return; }
+		t_next[axis] += t_delta[axis];
+		t = t_exit;
+	}
+}
+
+/// Möller-Trumbore ray/triangle intersection. Gives back `t` and the barycentric
+/// coordinates of the second and third vertices.
+fn triangle_hits([v0, v1, v2]: [Point3; 3], t_range: Range<Float>, ray: Ray) -> Option<(Float, Float, Float)> {
+	let edge1 = v1 - v0;
+	let edge2 = v2 - v0;
+	let p = Vec3::cross(ray.direction, edge2);
+	let det = edge1.dot(p);
+	if det.abs() < 1e-12 { return None; }
+	
+	let inv_det = det.recip();
+	let s = ray.position - v0;
+	let u = s.dot(p) * inv_det;
+	if !(0.0..=1.0).contains(&u) { return None; }
+	
+	let q = Vec3::cross(s, edge1);
+	let v = ray.direction.dot(q) * inv_det;
+	if v < 0.0 || u + v > 1.0 { return None; }
+	
+	let t = edge2.dot(q) * inv_det;
+	t_range.contains(&t).then_some((t, u, v))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	#[test]
+	fn tiny_images_are_an_error() {
+		let path = std::env::temp_dir().join("heightfield_one_pixel.png");
+		image::GrayImage::new(1, 1).save(&path).unwrap();
+		
+		let terrain = Heightfield::load(&path, Point3::ZERO, Vec3::ONE);
+		std::fs::remove_file(&path).unwrap();
+		assert!(matches!(terrain, Err(ImageError::Parameter(_))));
+	}
+}
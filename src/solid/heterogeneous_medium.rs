@@ -0,0 +1,85 @@
+use crate::vector::{Vec3, Float};
+use crate::ray::{Ray, HitInfo};
+use crate::bounding_box::BoundingBox;
+use crate::medium::DensityField;
+use crate::util;
+
+use super::Hittable;
+use super::constant_medium::boundary_span;
+
+use std::ops::Range;
+
+/// A volume filling some `boundary`, whose density changes from place to place
+/// according to a [DensityField], like a [VoxelGrid](crate::medium::VoxelGrid).
+/// 
+/// It's rendered with delta tracking: pretend the whole volume is as thick as its
+/// thickest point, and then randomly reject the collisions that land in thinner
+/// parts. That gives the exact same result as the real thing, just with some noise.
+/// As with [ConstantMedium](super::constant_medium::ConstantMedium), the boundary
+/// needs to be convex.
+pub struct HeterogeneousMedium {
+	pub boundary: Box<dyn Hittable + Send + Sync>,
+	pub field: Box<dyn DensityField + Send + Sync>,
+}
+impl HeterogeneousMedium {
+	pub fn new(boundary: Box<dyn Hittable + Send + Sync>, field: Box<dyn DensityField + Send + Sync>) -> Self {
+		Self { boundary, field }
+	}
+	
+	/// Take a random step forward, as if the volume were at its thickest everywhere.
+	fn step(&self, t: Float, speed: Float) -> Float {
+		t - (1.0 - util::random_float()).ln() / (self.field.max_density() * speed)
+	}
+}
+impl Hittable for HeterogeneousMedium {
+	fn ray_hits(&self, t_range: Range<Float>, ray: Ray) -> Option<HitInfo> {
+		let (enter, t_enter, t_exit) = boundary_span(self.boundary.as_ref(), t_range, ray)?;
+		let max_density = self.field.max_density();
+		if max_density <= 0.0 { return None; }
+		
+		let speed = ray.direction.magnitude();
+		let mut t = t_enter;
+		loop {
+			t = self.step(t, speed);
+			if t >= t_exit { return None; }
+			
+			// A real collision, rather than a made-up one.
+			let position = ray.at(t);
+			if util::random_float() * max_density < self.field.density(position) {
+				return Some(HitInfo {
+					position,
+					t,
+					// These don't mean anything inside of a volume.
+					normal: Vec3::X,
+					front_face: true,
+					uv: enter.uv,
+					dpdu: Vec3::Z,
+					dpdv: Vec3::Y,
+				});
+			}
+		}
+	}
+	
+	fn bounding_box(&self) -> Option<BoundingBox> {
+		self.boundary.bounding_box()
+	}
+	
+	/// Uses ratio tracking: instead of stopping at the first real collision, keep
+	/// going and multiply by how likely each step was to not be a collision.
+	/// It averages out to the same thing, with a lot less noise.
+	fn transmittance(&self, t_range: Range<Float>, ray: Ray) -> Float {
+		let Some((_, t_enter, t_exit)) = boundary_span(self.boundary.as_ref(), t_range, ray) else { return 1.0; };
+		let max_density = self.field.max_density();
+		if max_density <= 0.0 { return 1.0; }
+		
+		let speed = ray.direction.magnitude();
+		let mut transmittance = 1.0;
+		let mut t = t_enter;
+		loop {
+			t = self.step(t, speed);
+			if t >= t_exit { return transmittance; }
+			
+			transmittance *= 1.0 - self.field.density(ray.at(t)) / max_density;
+		}
+	}
+}
@@ -1,14 +1,255 @@
-use crate::vector::{Float};
+use crate::vector::{Vec3, Point2, Point3, Float};
 use crate::ray::{Ray, HitInfo};
+use crate::bounding_box::BoundingBox;
 
 use std::ops::Range;
+use std::sync::Arc;
 
 /// The `Hittable` trait encompasses all things that can be hit by a [Ray].
 /// If some object implements the `Hittable` trait, then it can easily be
 /// drawn in the world with an associated [Material].
 pub trait Hittable {
 	fn ray_hits(&self, t_range: Range<Float>, ray: Ray) -> Option<HitInfo>;
-	// fn bounding_box(&self) -> Option<BoundingBox>;
+	
+	/// Get a box that the whole solid fits inside, or `None` if it's infinitely big.
+	fn bounding_box(&self) -> Option<BoundingBox>;
+	
+	/// Get every stretch of the [Ray]'s line that's inside the solid, in order,
+	/// including the parts behind the ray's position.
+	/// 
+	/// Only closed solids have an inside, so everything else returns `None`.
+	fn spans(&self, _ray: Ray) -> Option<Vec<Span>> { None }
+	
+	/// Whether it's a closed solid, so that [Hittable::spans] always gives `Some`.
+	fn is_closed(&self) -> bool { false }
+	
+	/// How much light makes it through the solid along the [Ray] within `t_range`,
+	/// from 0 (none of it) to 1 (all of it).
+	/// 
+	/// Surfaces block everything they get hit by. Volumes let some light through.
+	fn transmittance(&self, t_range: Range<Float>, ray: Ray) -> Float {
+		if self.ray_hits(t_range, ray).is_some() { 0.0 } else { 1.0 }
+	}
+	
+	/// Whether the solid knows how to [sample a direction](Hittable::sample_direction) and
+	/// [a point](Hittable::sample_point). Glowing solids that don't are left out of the
+	/// [LightTree](crate::light::tree::LightTree), and only get found by rays bumping into them.
+	fn is_sampleable(&self) -> bool { false }
+	
+	/// Pick a direction from `origin` that hits the solid, for when it's giving off light.
+	/// 
+	/// Solids that don't know how to do that return `None`, and only get found by rays bumping into them.
+	fn sample_direction(&self, _origin: Point3) -> Option<Vec3> { None }
+	
+	/// The pdf of [Hittable::sample_direction] picking `direction` (which hits the solid),
+	/// per unit solid angle.
+	fn direction_pdf(&self, _origin: Point3, _direction: Vec3) -> Float { 0.0 }
+	
+	/// Pick a spot on the surface, for sending light out from it. The [HitInfo]
+	/// is as if a ray hit the front of the surface there.
+	fn sample_point(&self) -> Option<HitInfo> { None }
+	
+	/// The pdf of [Hittable::sample_point] picking `point`, per unit area.
+	fn point_pdf(&self, _point: Point3) -> Float { 0.0 }
+}
+
+/// Lets one solid get shared between a bunch of [Object](crate::world::Object)s,
+/// usually through [Transformed](transformed::Transformed).
+impl<T: Hittable + ?Sized> Hittable for Arc<T> {
+	fn ray_hits(&self, t_range: Range<Float>, ray: Ray) -> Option<HitInfo> {
+		T::ray_hits(self, t_range, ray)
+	}
+	
+	fn bounding_box(&self) -> Option<BoundingBox> {
+		T::bounding_box(self)
+	}
+	
+	fn spans(&self, ray: Ray) -> Option<Vec<Span>> {
+		T::spans(self, ray)
+	}
+	
+	fn is_closed(&self) -> bool {
+		T::is_closed(self)
+	}
+	
+	fn transmittance(&self, t_range: Range<Float>, ray: Ray) -> Float {
+		T::transmittance(self, t_range, ray)
+	}
+	
+	fn is_sampleable(&self) -> bool {
+		T::is_sampleable(self)
+	}
+	
+	fn sample_direction(&self, origin: Point3) -> Option<Vec3> {
+		T::sample_direction(self, origin)
+	}
+	
+	fn direction_pdf(&self, origin: Point3, direction: Vec3) -> Float {
+		T::direction_pdf(self, origin, direction)
+	}
+	
+	fn sample_point(&self) -> Option<HitInfo> {
+		T::sample_point(self)
+	}
+	
+	fn point_pdf(&self, point: Point3) -> Float {
+		T::point_pdf(self, point)
+	}
+}
+
+/// A spot where a [Ray] crosses the surface of a solid, before
+/// we know if it's the one we care about.
+#[derive(Copy, Clone, Debug)]
+pub struct Crossing {
+	pub t: Float,
+	pub outward_normal: Vec3,
+	pub uv: Point2,
+	pub dpdu: Vec3,
+	pub dpdv: Vec3,
+}
+impl Crossing {
+	/// Make a crossing with some made-up tangents. Use [Crossing::with_tangents]
+	/// if the solid knows which way its `uv`s go.
+	pub fn new(t: Float, outward_normal: Vec3, uv: Point2) -> Self {
+		let (dpdu, dpdv) = outward_normal.orthonormal_basis();
+		Self { t, outward_normal, uv, dpdu, dpdv }
+	}
+	
+	pub const fn with_tangents(mut self, dpdu: Vec3, dpdv: Vec3) -> Self {
+		self.dpdu = dpdu;
+		self.dpdv = dpdv;
+		self
+	}
+	
+	pub fn into_hit(self, ray: Ray) -> HitInfo {
+		let (front_face, normal) = HitInfo::get_face_normal_info(ray, self.outward_normal);
+		HitInfo {
+			position: ray.at(self.t), normal, t: self.t, front_face,
+			uv: self.uv, dpdu: self.dpdu, dpdv: self.dpdv,
+		}
+	}
+}
+
+/// A stretch of a [Ray] that's inside of a solid, from where
+/// it goes in to where it comes back out.
+#[derive(Copy, Clone, Debug)]
+pub struct Span {
+	pub enter: Crossing,
+	pub exit: Crossing,
+}
+impl Span {
+	/// Pair up all the [Crossing]s of a closed surface into [Span]s.
+	pub fn from_crossings(ray: Ray, mut crossings: Vec<Crossing>) -> Vec<Span> {
+		crossings.sort_by(|a, b| a.t.total_cmp(&b.t));
+		
+		let mut spans = Vec::with_capacity(crossings.len() / 2);
+		let mut enter = None;
+		
+		for crossing in crossings {
+			let entering = crossing.outward_normal.dot(ray.direction) < 0.0;
+			match (enter, entering) {
+				(None, true) => enter = Some(crossing),
+				(Some(e), false) => {
+					spans.push(Span { enter: e, exit: crossing });
+					enter = None;
+				},
+				// Grazing hits can come out of order. Just skip them.
+				_ => {},
+			}
+		}
+		
+		spans
+	}
+}
+
+/// Keeps track of the closest [Crossing] inside some range, for solids
+/// that have more than a couple of surfaces to check.
+pub struct Nearest {
+	t_range: Range<Float>,
+	closest: Option<Crossing>,
+}
+impl Nearest {
+	pub const fn new(t_range: Range<Float>) -> Self {
+		Self { t_range, closest: None }
+	}
+	
+	pub fn offer(&mut self, crossing: Crossing) {
+		if self.t_range.contains(&crossing.t) {
+			self.t_range.end = crossing.t;
+			self.closest = Some(crossing);
+		}
+	}
+	
+	pub fn into_hit(self, ray: Ray) -> Option<HitInfo> {
+		self.closest.map(|c| c.into_hit(ray))
+	}
 }
 
 pub mod sphere;
+pub mod moving_sphere;
+pub mod cylinder;
+pub mod cone;
+pub mod capsule;
+pub mod torus;
+pub mod transformed;
+pub mod group;
+pub mod csg;
+pub mod sdf;
+pub mod heightfield;
+pub mod constant_medium;
+pub mod heterogeneous_medium;
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::vector::Point3;
+	use torus::Torus;
+	use capsule::Capsule;
+	use sdf::RayMarched;
+	
+	/// Check that moving across the surface a little changes the position the way
+	/// the hit's tangents say it should, for how much the uvs changed.
+	fn check_tangents(solid: &dyn Hittable, ray: Ray) {
+		let hit = solid.ray_hits(0.001..Float::INFINITY, ray).unwrap();
+		let (side, up) = ray.direction.normalize().orthonormal_basis();
+		
+		for nudge in [side, up] {
+			let nudged = Ray { position: ray.position + nudge * 1e-4, ..ray };
+			let other = solid.ray_hits(0.001..Float::INFINITY, nudged).unwrap();
+			let moved = other.position - hit.position;
+			let (du, dv) = (other.uv.x - hit.uv.x, other.uv.y - hit.uv.y);
+			let guess = hit.dpdu * du + hit.dpdv * dv;
+			assert!((moved - guess).magnitude() < 0.05 * moved.magnitude(), "moved {moved:?}, but the tangents say {guess:?}");
+		}
+	}
+	
+	#[test]
+	fn tori_have_tangents_along_their_uvs() {
+		let torus = Torus::new(Point3::new(0.1, 0.2, 0.3), Vec3::new(0.2, 1.0, 0.4), 1.0, 0.3);
+		let (tangent, bitangent) = torus.axis.orthonormal_basis();
+		
+		// Aim straight at a couple of spots, some way around the ring and the tube.
+		for (ring, tube) in [(0.7, 0.9), (2.5, -2.0)] {
+			let around = Float::cos(ring) * tangent + Float::sin(ring) * bitangent;
+			let normal = Float::cos(tube) * around + Float::sin(tube) * torus.axis;
+			let spot = torus.center + torus.major_radius * around + torus.minor_radius * normal;
+			let from = spot + 2.0 * normal + 0.3 * torus.axis;
+			check_tangents(&torus, Ray::new(from, spot - from, 0.0));
+		}
+	}
+	
+	#[test]
+	fn capsules_have_tangents_along_their_uvs() {
+		let capsule = Capsule::new(Point3::ZERO, Point3::new(0.3, 2.0, 0.1), 0.5);
+		// One on the side, and one on each end.
+		check_tangents(&capsule, Ray::new(Point3::new(3.0, 1.0, 0.5), Vec3::new(-1.0, 0.1, -0.1), 0.0));
+		check_tangents(&capsule, Ray::new(Point3::new(0.4, -3.0, 0.3), Vec3::new(-0.05, 1.0, -0.1), 0.0));
+		check_tangents(&capsule, Ray::new(Point3::new(0.6, 5.0, -0.2), Vec3::new(-0.1, -1.0, 0.1), 0.0));
+	}
+	
+	#[test]
+	fn distance_fields_have_tangents_along_their_uvs() {
+		let sphere = RayMarched::new(sdf::Sphere { center: Point3::new(0.2, 0.0, 0.1), radius: 1.0 }).epsilon(1e-7);
+		check_tangents(&sphere, Ray::new(Point3::new(3.0, 1.0, 2.0), Vec3::new(-3.0, -0.7, -1.8), 0.0));
+	}
+}
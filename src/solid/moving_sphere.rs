@@ -0,0 +1,83 @@
+use crate::vector::{Point3, Float};
+use crate::ray::{Ray, HitInfo};
+use crate::bounding_box::BoundingBox;
+
+use super::{Hittable, Span};
+use super::sphere::Sphere;
+
+use std::ops::Range;
+
+/// A sphere that moves in a straight line, from `start` at `start_time`
+/// to `end` at `end_time`. Before and after that, it stays put.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug, Default)]
+pub struct MovingSphere {
+	pub start: Point3,
+	pub end: Point3,
+	pub start_time: Float,
+	pub end_time: Float,
+	pub radius: Float,
+}
+impl MovingSphere {
+	pub const fn new(start: Point3, end: Point3, start_time: Float, end_time: Float, radius: Float) -> Self {
+		Self { start, end, start_time, end_time, radius }
+	}
+	
+	/// Where the sphere is at some point in time.
+	pub fn center(&self, time: Float) -> Point3 {
+		if self.end_time <= self.start_time { return self.start; }
+		
+		let t = (time - self.start_time) / (self.end_time - self.start_time);
+		Point3::lerp(self.start, self.end, t.clamp(0.0, 1.0))
+	}
+	
+	/// The sphere frozen at the moment the [Ray] was shot.
+	fn at_time_of(&self, ray: Ray) -> Sphere {
+		Sphere::new(self.center(ray.time), self.radius)
+	}
+}
+impl Hittable for MovingSphere {
+	fn ray_hits(&self, t_range: Range<Float>, ray: Ray) -> Option<HitInfo> {
+		self.at_time_of(ray).ray_hits(t_range, ray)
+	}
+	
+	fn bounding_box(&self) -> Option<BoundingBox> {
+		// It never leaves the line between `start` and `end`,
+		// so this covers any shutter interval.
+		let start = Sphere::new(self.start, self.radius).bounding_box()?;
+		let end = Sphere::new(self.end, self.radius).bounding_box()?;
+		Some(start.union(end))
+	}
+	
+	fn spans(&self, ray: Ray) -> Option<Vec<Span>> {
+		self.at_time_of(ray).spans(ray)
+	}
+	
+	fn is_closed(&self) -> bool { true }
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::vector::Vec3;
+	
+	#[test]
+	fn rays_see_it_where_it_was_at_their_time() {
+		let sphere = MovingSphere::new(Point3::ZERO, Point3::new(0.0, 2.0, 0.0), 0.0, 1.0, 0.5);
+		let hit_at = |time| {
+			let ray = Ray::new(Point3::new(0.0, 0.0, -5.0), Vec3::Z, time);
+			sphere.ray_hits(0.001..Float::INFINITY, ray).map(|hit| hit.position)
+		};
+		
+		let start = hit_at(0.0).unwrap();
+		assert!((start - Point3::new(0.0, 0.0, -0.5)).magnitude() < 1e-9);
+		// By the end it's moved up out of the way.
+		assert!(hit_at(1.0).is_none());
+		// Halfway there, it's halfway up.
+		let middle = Ray::new(Point3::new(0.0, 1.0, -5.0), Vec3::Z, 0.5);
+		let hit = sphere.ray_hits(0.001..Float::INFINITY, middle).unwrap();
+		assert!((hit.position - Point3::new(0.0, 1.0, -0.5)).magnitude() < 1e-9);
+		
+		// It stays put after the shutter closes.
+		assert_eq!(sphere.center(2.0), Point3::new(0.0, 2.0, 0.0));
+	}
+}
@@ -0,0 +1,372 @@
+use crate::vector::{Vec3, Point3, Float};
+use crate::ray::{Ray, HitInfo};
+use crate::bounding_box::BoundingBox;
+
+use super::{Hittable, Crossing, Span};
+use super::sphere::Sphere as UvSphere;
+
+use std::ops::Range;
+
+/// A signed distance field: something that can tell you how far any point
+/// is from its surface. Points inside the surface have negative distances.
+/// 
+/// The distance doesn't have to be exact, but it should never be an
+/// overestimate, or [RayMarched] will step right through the surface.
+pub trait Sdf {
+	fn distance(&self, p: Point3) -> Float;
+	
+	/// A box that the whole surface fits inside.
+	fn bounds(&self) -> BoundingBox;
+}
+
+/// Draws any [Sdf] by sphere tracing it: stepping along the [Ray] by the
+/// distance to the surface, which is always safe, until it's close enough.
+#[derive(Copy, Clone, Debug)]
+pub struct RayMarched<S: Sdf> {
+	pub sdf: S,
+	/// How close counts as touching the surface.
+	pub epsilon: Float,
+	/// Give up after this many steps, and count it as a miss.
+	pub max_steps: usize,
+	/// Scales each step down, for distance fields that overestimate a little.
+	pub step_scale: Float,
+}
+impl<S: Sdf> RayMarched<S> {
+	pub const fn new(sdf: S) -> Self {
+		Self { sdf, epsilon: 1e-4, max_steps: 256, step_scale: 1.0 }
+	}
+	
+	pub const fn epsilon(mut self, epsilon: Float) -> Self {
+		self.epsilon = epsilon; self
+	}
+	
+	pub const fn max_steps(mut self, max_steps: usize) -> Self {
+		self.max_steps = max_steps; self
+	}
+	
+	pub const fn step_scale(mut self, step_scale: Float) -> Self {
+		self.step_scale = step_scale; self
+	}
+	
+	/// Estimate the surface normal from how the distance changes around `p`.
+	fn normal_at(&self, p: Point3) -> Vec3 {
+		let h = self.epsilon;
+		let d = |axis: Vec3| self.sdf.distance(p + axis * h) - self.sdf.distance(p - axis * h);
+		Vec3::new(d(Vec3::X), d(Vec3::Y), d(Vec3::Z)).normalize()
+	}
+	
+	/// The bounds, with a little bit of room so we never start marching right on the surface.
+	fn padded_bounds(&self) -> BoundingBox {
+		let bounds = self.sdf.bounds();
+		BoundingBox::new(bounds.min - Vec3::all(2.0 * self.epsilon), bounds.max + Vec3::all(2.0 * self.epsilon))
+	}
+	
+	/// Move `t` along the [Ray] until it's off of the surface, if it's on it.
+	fn step_off(&self, ray: Ray, mut t: Float) -> Float {
+		let speed = ray.direction.magnitude();
+		let mut nudges = 0;
+		while self.sdf.distance(ray.at(t)).abs() < self.epsilon && nudges < 8 {
+			t += self.epsilon / speed;
+			nudges += 1;
+		}
+		t
+	}
+	
+	/// Sphere trace from `t` up to `t_exit`, returning where the [Ray] touches the surface, if it does.
+	/// Rays starting inside (like refracted ones) find the way out.
+	fn march(&self, ray: Ray, mut t: Float, t_exit: Float) -> Option<Float> {
+		let speed = ray.direction.magnitude();
+		for _ in 0..self.max_steps {
+			if t > t_exit { return None; }
+			
+			let distance = self.sdf.distance(ray.at(t)).abs();
+			if distance < self.epsilon { return Some(t); }
+			t += distance * self.step_scale / speed;
+		}
+		None
+	}
+	
+	fn crossing(&self, ray: Ray, t: Float) -> Crossing {
+		let position = ray.at(t);
+		let outward_normal = self.normal_at(position);
+		
+		// There's no natural way to unwrap an arbitrary surface, so use the one spheres do,
+		// from the middle of the bounds. Its tangents get flattened onto the surface,
+		// so they're exact for spheres, and go the right way around for everything else.
+		let ofs = position - self.sdf.bounds().centroid();
+		let uv = UvSphere::get_uv(ofs.normalize());
+		let (dpdu, dpdv) = UvSphere::get_tangents(ofs.normalize(), ofs.magnitude());
+		let flatten = |v: Vec3| v - outward_normal * outward_normal.dot(v);
+		
+		Crossing::new(t, outward_normal, uv).with_tangents(flatten(dpdu), flatten(dpdv))
+	}
+}
+impl<S: Sdf> Hittable for RayMarched<S> {
+	fn ray_hits(&self, t_range: Range<Float>, ray: Ray) -> Option<HitInfo> {
+		let (t, t_exit) = self.padded_bounds().ray_span(t_range, ray)?;
+		
+		// Rays bouncing off the surface start out right on it, so nudge them off first.
+		let t = self.march(ray, self.step_off(ray, t), t_exit)?;
+		Some(self.crossing(ray, t).into_hit(ray))
+	}
+	
+	fn bounding_box(&self) -> Option<BoundingBox> {
+		Some(self.padded_bounds())
+	}
+	
+	/// Marches along the whole line, stepping off of the surface each time it gets to it.
+	fn spans(&self, ray: Ray) -> Option<Vec<Span>> {
+		let mut crossings = Vec::new();
+		if let Some((mut t, t_exit)) = self.padded_bounds().ray_span(Float::NEG_INFINITY..Float::INFINITY, ray) {
+			while let Some(hit_t) = self.march(ray, t, t_exit) {
+				crossings.push(self.crossing(ray, hit_t));
+				t = self.step_off(ray, hit_t);
+			}
+		}
+		Some(Span::from_crossings(ray, crossings))
+	}
+	
+	/// Every point has a distance, and the negative ones are inside, so there's always an inside.
+	fn is_closed(&self) -> bool { true }
+}
+
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug)]
+pub struct Sphere {
+	pub center: Point3,
+	pub radius: Float,
+}
+impl Sdf for Sphere {
+	fn distance(&self, p: Point3) -> Float {
+		Vec3::magnitude(p - self.center) - self.radius
+	}
+	
+	fn bounds(&self) -> BoundingBox {
+		BoundingBox::around(self.center, Vec3::all(self.radius))
+	}
+}
+
+/// A box, stretching out `half_size` from its `center` in each direction.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug)]
+pub struct Cuboid {
+	pub center: Point3,
+	pub half_size: Vec3,
+}
+impl Sdf for Cuboid {
+	fn distance(&self, p: Point3) -> Float {
+		RoundCuboid { center: self.center, half_size: self.half_size, radius: 0.0 }.distance(p)
+	}
+	
+	fn bounds(&self) -> BoundingBox {
+		BoundingBox::around(self.center, self.half_size)
+	}
+}
+
+/// A box with its edges and corners rounded off by `radius`.
+/// It still fits inside `half_size`.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug)]
+pub struct RoundCuboid {
+	pub center: Point3,
+	pub half_size: Vec3,
+	pub radius: Float,
+}
+impl Sdf for RoundCuboid {
+	fn distance(&self, p: Point3) -> Float {
+		let ofs = p - self.center;
+		let q = Vec3::new(ofs.x.abs(), ofs.y.abs(), ofs.z.abs()) - self.half_size + Vec3::all(self.radius);
+		let outside = q.max(Vec3::ZERO).magnitude();
+		let inside = Float::min(q.x.max(q.y).max(q.z), 0.0);
+		outside + inside - self.radius
+	}
+	
+	fn bounds(&self) -> BoundingBox {
+		BoundingBox::around(self.center, self.half_size)
+	}
+}
+
+/// A torus lying flat, with its ring going around the Y axis.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug)]
+pub struct Torus {
+	pub center: Point3,
+	pub major_radius: Float,
+	pub minor_radius: Float,
+}
+impl Sdf for Torus {
+	fn distance(&self, p: Point3) -> Float {
+		let ofs = p - self.center;
+		let ring = Float::hypot(ofs.x, ofs.z) - self.major_radius;
+		Float::hypot(ring, ofs.y) - self.minor_radius
+	}
+	
+	fn bounds(&self) -> BoundingBox {
+		let r = self.major_radius + self.minor_radius;
+		BoundingBox::around(self.center, Vec3::new(r, self.minor_radius, r))
+	}
+}
+
+/// Every point within `radius` of the line segment between `a` and `b`.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug)]
+pub struct Capsule {
+	pub a: Point3,
+	pub b: Point3,
+	pub radius: Float,
+}
+impl Sdf for Capsule {
+	fn distance(&self, p: Point3) -> Float {
+		let pa = p - self.a;
+		let ba = self.b - self.a;
+		let h = Float::clamp(pa.dot(ba) / ba.squared_magnitude(), 0.0, 1.0);
+		Vec3::magnitude(pa - ba * h) - self.radius
+	}
+	
+	fn bounds(&self) -> BoundingBox {
+		let r = Vec3::all(self.radius);
+		BoundingBox::around(self.a, r).union(BoundingBox::around(self.b, r))
+	}
+}
+
+/// The classic power-8 Mandelbulb fractal, sized to fit in a sphere of `scale` around `center`.
+/// 
+/// Its distance estimate isn't exact, so give [RayMarched] a `step_scale` under 1.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug)]
+pub struct Mandelbulb {
+	pub center: Point3,
+	pub scale: Float,
+	pub power: Float,
+	pub iterations: usize,
+}
+impl Mandelbulb {
+	pub const fn new(center: Point3, scale: Float) -> Self {
+		Self { center, scale, power: 8.0, iterations: 12 }
+	}
+}
+impl Sdf for Mandelbulb {
+	fn distance(&self, p: Point3) -> Float {
+		let c = (p - self.center) / self.scale;
+		let mut z = c;
+		let mut dr = 1.0;
+		let mut r = z.magnitude();
+		
+		for _ in 0..self.iterations {
+			if r > 2.0 { break; }
+			
+			// Raise z to the power in spherical coordinates.
+			let theta = Float::acos(Float::clamp(z.z / r, -1.0, 1.0)) * self.power;
+			let phi = Float::atan2(z.y, z.x) * self.power;
+			dr = r.powf(self.power - 1.0) * self.power * dr + 1.0;
+			
+			let zr = r.powf(self.power);
+			z = zr * Vec3::new(theta.sin() * phi.cos(), theta.sin() * phi.sin(), theta.cos()) + c;
+			r = z.magnitude();
+		}
+		
+		// Points right at the center never escape, so they're as deep inside as it gets.
+		if r == 0.0 { return -self.scale; }
+		0.5 * r.ln() * r / dr * self.scale
+	}
+	
+	fn bounds(&self) -> BoundingBox {
+		BoundingBox::around(self.center, Vec3::all(1.2 * self.scale))
+	}
+}
+
+/// Two distance fields merged together, with a smooth fillet of size `k` where they meet.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug)]
+pub struct SmoothUnion<A: Sdf, B: Sdf> {
+	pub a: A,
+	pub b: B,
+	pub k: Float,
+}
+impl<A: Sdf, B: Sdf> Sdf for SmoothUnion<A, B> {
+	fn distance(&self, p: Point3) -> Float {
+		let (da, db) = (self.a.distance(p), self.b.distance(p));
+		if self.k <= 0.0 { return da.min(db); }
+		
+		// Polynomial smooth minimum.
+		let h = Float::max(self.k - (da - db).abs(), 0.0) / self.k;
+		da.min(db) - h.powi(2) * self.k / 4.0
+	}
+	
+	fn bounds(&self) -> BoundingBox {
+		// The fillet can bulge out by up to a quarter of `k`.
+		let bounds = self.a.bounds().union(self.b.bounds());
+		BoundingBox::new(bounds.min - Vec3::all(self.k / 4.0), bounds.max + Vec3::all(self.k / 4.0))
+	}
+}
+
+/// Morph between two distance fields. A `t` of 0 is all `a`, and 1 is all `b`.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug)]
+pub struct Blend<A: Sdf, B: Sdf> {
+	pub a: A,
+	pub b: B,
+	pub t: Float,
+}
+impl<A: Sdf, B: Sdf> Sdf for Blend<A, B> {
+	fn distance(&self, p: Point3) -> Float {
+		Float::mul_add(self.b.distance(p) - self.a.distance(p), self.t, self.a.distance(p))
+	}
+	
+	fn bounds(&self) -> BoundingBox {
+		self.a.bounds().union(self.b.bounds())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::solid::csg::Csg;
+	
+	#[test]
+	fn spheres_get_hit_where_they_should() {
+		let sphere = RayMarched::new(Sphere { center: Point3::ZERO, radius: 1.0 });
+		let ray = Ray::new(Point3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 2.0), 0.0);
+		let hit = sphere.ray_hits(0.001..Float::INFINITY, ray).unwrap();
+		assert!((hit.t - 2.0).abs() < 1e-3, "hit at {}", hit.t);
+		assert!(hit.front_face);
+		assert!((hit.normal - Vec3::new(0.0, 0.0, -1.0)).magnitude() < 1e-3);
+		
+		// Refracted rays start inside, and find the way out.
+		let inside = Ray::new(Point3::ZERO, Vec3::X, 0.0);
+		let hit = sphere.ray_hits(0.001..Float::INFINITY, inside).unwrap();
+		assert!((hit.t - 1.0).abs() < 1e-3);
+		assert!(!hit.front_face);
+	}
+	
+	#[test]
+	fn boxes_have_flat_faces() {
+		let cuboid = RayMarched::new(Cuboid { center: Point3::ZERO, half_size: Vec3::new(1.0, 2.0, 3.0) });
+		let ray = Ray::new(Point3::new(5.0, 0.5, 1.0), Vec3::new(-1.0, 0.1, -0.2), 0.0);
+		let hit = cuboid.ray_hits(0.001..Float::INFINITY, ray).unwrap();
+		assert!((hit.position.x - 1.0).abs() < 1e-3);
+		assert!((hit.normal - Vec3::X).magnitude() < 1e-3, "normal is {:?}", hit.normal);
+	}
+	
+	#[test]
+	fn spans_match_an_exact_sphere() {
+		let marched = RayMarched::new(Sphere { center: Point3::ZERO, radius: 1.0 });
+		let exact = UvSphere::new(Point3::ZERO, 1.0);
+		let ray = Ray::new(Point3::new(-3.0, 0.3, 0.2), Vec3::X, 0.0);
+		
+		let (marched, exact) = (marched.spans(ray).unwrap(), exact.spans(ray).unwrap());
+		assert_eq!(marched.len(), 1);
+		assert!((marched[0].enter.t - exact[0].enter.t).abs() < 1e-3);
+		assert!((marched[0].exit.t - exact[0].exit.t).abs() < 1e-3);
+	}
+	
+	#[test]
+	fn works_in_csg() {
+		// A ring around the middle of a torus, cut out of a box.
+		let slab = RayMarched::new(Cuboid { center: Point3::ZERO, half_size: Vec3::new(2.0, 0.25, 2.0) });
+		let ring = RayMarched::new(Torus { center: Point3::ZERO, major_radius: 1.0, minor_radius: 0.5 });
+		let csg = Csg::difference(Box::new(slab), Box::new(ring)).unwrap();
+		
+		// Going through the middle, it's slab from -2 to -1.5, hole, slab from -0.5 to 0.5, and so on.
+		let ray = Ray::new(Point3::new(-5.0, 0.0, 0.0), Vec3::X, 0.0);
+		let spans = csg.spans(ray).unwrap();
+		let ts: Vec<_> = spans.iter().flat_map(|span| [span.enter.t, span.exit.t]).collect();
+		let expected = [3.0, 3.5, 4.5, 5.5, 6.5, 7.0];
+		assert_eq!(ts.len(), expected.len(), "spans at {ts:?}");
+		for (t, expected) in ts.into_iter().zip(expected) {
+			assert!((t - expected).abs() < 1e-3, "expected {expected}, but got {t}");
+		}
+	}
+}
@@ -1,7 +1,9 @@
-use crate::vector::{Vec3, Point3, Float};
+use crate::vector::{Vec3, Point2, Point3, Float};
+use crate::util;
 use crate::ray::{Ray, HitInfo};
+use crate::bounding_box::BoundingBox;
 
-use super::Hittable;
+use super::{Hittable, Crossing, Span};
 
 use std::ops::Range;
 
@@ -14,6 +16,41 @@ impl Sphere {
 	pub const fn new(center: Point3, radius: Float) -> Self {
 		Self { center, radius }
 	}
+	
+	/// Get the uv coordinates of a point on a unit sphere centered at the origin.
+	/// 
+	/// `u` goes around the Y axis starting from -X, `v` goes from the bottom to the top.
+	pub fn get_uv(p: Point3) -> Point2 {
+		let theta = Float::acos(Float::clamp(-p.y, -1.0, 1.0));
+		let phi = Float::atan2(-p.z, p.x) + std::f64::consts::PI as Float;
+		
+		Point2::new(
+			phi / (2.0 * std::f64::consts::PI as Float),
+			theta / std::f64::consts::PI as Float
+		)
+	}
+	
+	/// The cosine of the angle from the center of the sphere to its edge, seen from `origin`.
+	/// From the inside, it's all around, so there's no edge.
+	fn cos_subtended(&self, origin: Point3) -> Option<Float> {
+		let sin2_max = self.radius.powi(2) / (self.center - origin).squared_magnitude();
+		if sin2_max >= 1.0 { return None; }
+		Some(Float::sqrt(1.0 - sin2_max))
+	}
+	
+	/// Which way a point on the sphere moves as its [Sphere::get_uv] coordinates change.
+	/// `p` is the unit vector from the center, like for `get_uv`.
+	pub fn get_tangents(p: Point3, radius: Float) -> (Vec3, Vec3) {
+		let pi = std::f64::consts::PI as Float;
+		let sin_theta = Float::hypot(p.x, p.z);
+		// Right at the poles, u goes every which way.
+		if sin_theta == 0.0 { return p.orthonormal_basis(); }
+		
+		(
+			2.0 * pi * radius * Vec3::new(p.z, 0.0, -p.x),
+			pi * radius * Vec3::new(-p.y * p.x / sin_theta, sin_theta, -p.y * p.z / sin_theta)
+		)
+	}
 }
 impl Hittable for Sphere {
 	fn ray_hits(&self, t_range: Range<Float>, ray: Ray) -> Option<HitInfo> {
@@ -32,14 +69,74 @@ impl Hittable for Sphere {
 				
 				if t_range.contains(&t) {
 					let position = ray.at(t);
+					let outward_normal = (position - self.center) / self.radius;
 					let (front_face, normal) =
-						HitInfo::get_face_normal_info(ray, (position - self.center) / self.radius);
+						HitInfo::get_face_normal_info(ray, outward_normal);
+					let uv = Sphere::get_uv(outward_normal);
+					let (dpdu, dpdv) = Sphere::get_tangents(outward_normal, self.radius.abs());
 					
-					return Some(HitInfo { position, normal, t, front_face });
+					return Some(HitInfo { position, normal, t, front_face, uv, dpdu, dpdv });
 				}
 			}
 		}
 		
 		None
 	}
+	
+	fn bounding_box(&self) -> Option<BoundingBox> {
+		Some(BoundingBox::around(self.center, Vec3::all(self.radius.abs())))
+	}
+	
+	/// Picks evenly out of the cone of directions the sphere takes up.
+	fn is_sampleable(&self) -> bool { true }
+	
+	fn sample_direction(&self, origin: Point3) -> Option<Vec3> {
+		let cos_max = self.cos_subtended(origin)?;
+		Some(util::random_in_cone((self.center - origin).normalize(), cos_max))
+	}
+	
+	fn direction_pdf(&self, origin: Point3, _direction: Vec3) -> Float {
+		let Some(cos_max) = self.cos_subtended(origin) else { return 0.0 };
+		(2.0 * std::f64::consts::PI as Float * (1.0 - cos_max)).recip()
+	}
+	
+	/// Picks evenly over the whole sphere.
+	fn sample_point(&self) -> Option<HitInfo> {
+		let outward_normal = util::random_unit_vector() * self.radius.signum();
+		let (dpdu, dpdv) = Sphere::get_tangents(outward_normal, self.radius.abs());
+		
+		Some(HitInfo {
+			position: self.center + outward_normal * self.radius,
+			normal: outward_normal,
+			t: 0.0,
+			front_face: true,
+			uv: Sphere::get_uv(outward_normal),
+			dpdu, dpdv,
+		})
+	}
+	
+	fn point_pdf(&self, _point: Point3) -> Float {
+		(4.0 * std::f64::consts::PI as Float * self.radius.powi(2)).recip()
+	}
+	
+	fn spans(&self, ray: Ray) -> Option<Vec<Span>> {
+		let ofs = ray.position - self.center;
+		let roots = util::solve_quadratic(
+			ray.direction.squared_magnitude(),
+			ofs.dot(ray.direction),
+			ofs.squared_magnitude() - self.radius.powi(2)
+		);
+		
+		let crossing = |t| {
+			let outward_normal = (ray.at(t) - self.center) / self.radius;
+			let (dpdu, dpdv) = Sphere::get_tangents(outward_normal, self.radius.abs());
+			Crossing::new(t, outward_normal, Sphere::get_uv(outward_normal)).with_tangents(dpdu, dpdv)
+		};
+		
+		Some(roots.map_or_else(Vec::new, |(t0, t1)| {
+			vec![Span { enter: crossing(t0), exit: crossing(t1) }]
+		}))
+	}
+	
+	fn is_closed(&self) -> bool { true }
 }
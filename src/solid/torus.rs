@@ -0,0 +1,98 @@
+use crate::vector::{Vec3, Point2, Point3, Float};
+use crate::ray::{Ray, HitInfo};
+use crate::bounding_box::BoundingBox;
+use crate::util;
+
+use super::{Hittable, Crossing, Nearest, Span};
+use super::cone::angle_around;
+
+use std::ops::Range;
+
+/// A donut! The ring goes around `axis`, `major_radius` away from the `center`,
+/// and the tube itself is `minor_radius` thick.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug)]
+pub struct Torus {
+	pub center: Point3,
+	pub axis: Vec3,
+	pub major_radius: Float,
+	pub minor_radius: Float,
+}
+impl Torus {
+	pub fn new(center: Point3, axis: Vec3, major_radius: Float, minor_radius: Float) -> Self {
+		Self { center, axis: axis.normalize(), major_radius, minor_radius }
+	}
+	
+	pub fn crossings(&self, ray: Ray, mut f: impl FnMut(Crossing)) {
+		// Work in a space where the torus lies flat on the XZ plane.
+		let frame = self.axis.orthonormal_basis();
+		let to_local = |v: Vec3| Vec3::new(v.dot(frame.0), v.dot(self.axis), v.dot(frame.1));
+		let o = to_local(ray.position - self.center);
+		let d = to_local(ray.direction);
+		
+		// Skip ahead to the bounding sphere first. The quartic is much
+		// better behaved when the ray starts close to the torus.
+		let bound = self.major_radius + self.minor_radius;
+		let Some((t_skip, _)) = util::solve_quadratic(
+			d.squared_magnitude(), o.dot(d), o.squared_magnitude() - bound.powi(2)
+		) else { return; };
+		let o = o + t_skip * d;
+		
+		let big_r2 = self.major_radius.powi(2);
+		let dd = d.squared_magnitude();
+		let e = o.squared_magnitude() + big_r2 - self.minor_radius.powi(2);
+		let od = o.dot(d);
+		
+		let (roots, count) = util::solve_quartic([
+			dd.powi(2),
+			4.0 * dd * od,
+			2.0 * dd * e + 4.0 * od.powi(2) - 4.0 * big_r2 * (d.x.powi(2) + d.z.powi(2)),
+			4.0 * od * e - 8.0 * big_r2 * (o.x * d.x + o.z * d.z),
+			e.powi(2) - 4.0 * big_r2 * (o.x.powi(2) + o.z.powi(2)),
+		]);
+		
+		for &t in &roots[..count] {
+			let p = o + t * d;
+			let ring = Vec3::new(p.x, 0.0, p.z);
+			let ring_dist = ring.magnitude();
+			
+			let to_world = |v: Vec3| v.x * frame.0 + v.y * self.axis + v.z * frame.1;
+			let outward_normal = to_world((p - self.major_radius / ring_dist * ring).normalize());
+			let uv = Point2::new(
+				angle_around(ring, (Vec3::X, Vec3::Z)),
+				angle_around(Vec3::new(ring_dist - self.major_radius, p.y, 0.0), (Vec3::X, Vec3::Y))
+			);
+			
+			// `u` goes around the ring, and `v` goes around the tube.
+			let tau = std::f64::consts::TAU as Float;
+			let dpdu = tau * Vec3::new(-p.z, 0.0, p.x);
+			let dpdv = tau * Vec3::new(-p.y * p.x / ring_dist, ring_dist - self.major_radius, -p.y * p.z / ring_dist);
+			
+			f(Crossing::new(t + t_skip, outward_normal, uv).with_tangents(to_world(dpdu), to_world(dpdv)));
+		}
+	}
+}
+impl Hittable for Torus {
+	fn ray_hits(&self, t_range: Range<Float>, ray: Ray) -> Option<HitInfo> {
+		let mut nearest = Nearest::new(t_range);
+		self.crossings(ray, |c| nearest.offer(c));
+		nearest.into_hit(ray)
+	}
+	
+	fn bounding_box(&self) -> Option<BoundingBox> {
+		// The ring is a circle, which sticks out less along the axes it's tilted towards.
+		let ring = Vec3::new(
+			Float::sqrt(1.0 - self.axis.x.powi(2)),
+			Float::sqrt(1.0 - self.axis.y.powi(2)),
+			Float::sqrt(1.0 - self.axis.z.powi(2))
+		);
+		Some(BoundingBox::around(self.center, ring * self.major_radius + Vec3::all(self.minor_radius)))
+	}
+	
+	fn spans(&self, ray: Ray) -> Option<Vec<Span>> {
+		let mut crossings = Vec::new();
+		self.crossings(ray, |c| crossings.push(c));
+		Some(Span::from_crossings(ray, crossings))
+	}
+	
+	fn is_closed(&self) -> bool { true }
+}
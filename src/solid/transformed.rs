@@ -0,0 +1,66 @@
+use crate::vector::{Float, Transform};
+use crate::ray::{Ray, HitInfo};
+use crate::bounding_box::BoundingBox;
+
+use super::{Hittable, Crossing, Span};
+
+use std::ops::Range;
+use std::sync::Arc;
+
+/// A solid that's been moved, rotated, or scaled.
+/// 
+/// The solid itself is behind an [Arc], so you can make as many
+/// `Transformed` copies of it as you want without duplicating it.
+#[derive(Clone)]
+pub struct Transformed {
+	pub solid: Arc<dyn Hittable + Send + Sync>,
+	pub transform: Transform,
+}
+impl Transformed {
+	pub fn new(solid: Arc<dyn Hittable + Send + Sync>, transform: Transform) -> Self {
+		Self { solid, transform }
+	}
+	
+	/// Move the ray into the solid's space instead of moving the solid.
+	/// The direction isn't normalized afterwards, so `t` means the same thing in both spaces.
+	fn to_local(&self, ray: Ray) -> Ray {
+		let inverse = self.transform.inverted();
+		Ray::new(inverse.point(ray.position), inverse.vector(ray.direction), ray.time)
+	}
+}
+impl Hittable for Transformed {
+	fn ray_hits(&self, t_range: Range<Float>, ray: Ray) -> Option<HitInfo> {
+		let hit = self.solid.ray_hits(t_range, self.to_local(ray))?;
+		
+		Some(HitInfo {
+			position: self.transform.point(hit.position),
+			normal: self.transform.normal(hit.normal),
+			dpdu: self.transform.vector(hit.dpdu),
+			dpdv: self.transform.vector(hit.dpdv),
+			..hit
+		})
+	}
+	
+	fn bounding_box(&self) -> Option<BoundingBox> {
+		Some(self.solid.bounding_box()?.transform(&self.transform))
+	}
+	
+	fn spans(&self, ray: Ray) -> Option<Vec<Span>> {
+		let to_world = |c: Crossing| Crossing {
+			outward_normal: self.transform.normal(c.outward_normal),
+			dpdu: self.transform.vector(c.dpdu),
+			dpdv: self.transform.vector(c.dpdv),
+			..c
+		};
+		
+		let mut spans = self.solid.spans(self.to_local(ray))?;
+		for span in &mut spans {
+			*span = Span { enter: to_world(span.enter), exit: to_world(span.exit) };
+		}
+		Some(spans)
+	}
+	
+	fn is_closed(&self) -> bool {
+		self.solid.is_closed()
+	}
+}
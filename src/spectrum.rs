@@ -0,0 +1,183 @@
+use crate::vector::{Vec3, Color, Float};
+use crate::util;
+
+use std::sync::LazyLock;
+
+/// The shortest and longest wavelengths (in nanometers) that get sampled.
+/// Anything outside of this is too hard to see to matter.
+pub const VISIBLE: (Float, Float) = (380.0, 720.0);
+
+/// The wavelengths of light one path is carrying, in nanometers.
+/// 
+/// This is hero wavelength sampling: the first one (the "hero") is picked at random,
+/// and the other two are spread evenly across the rest of the visible range. Each
+/// channel of a [Color] then holds how much light there is at one of the wavelengths.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug)]
+pub struct Wavelengths {
+	pub nm: [Float; 3],
+	/// Set once something (like dispersive glass) bends each wavelength a different way,
+	/// and only the hero is still following the path.
+	pub collapsed: bool,
+	/// Set on a scattered ray by a material that worked out its attenuation at each
+	/// wavelength itself, so it doesn't get turned from RGB a second time.
+	pub spectral: bool,
+}
+impl Wavelengths {
+	pub fn sample() -> Self {
+		let (lo, hi) = VISIBLE;
+		let range = hi - lo;
+		let hero = util::random_float() * range;
+		let nm = [0.0, 1.0, 2.0].map(|i| lo + (hero + i * range / 3.0) % range);
+		
+		Self { nm, collapsed: false, spectral: false }
+	}
+	
+	pub const fn hero(&self) -> Float {
+		self.nm[0]
+	}
+	
+	/// Drop everything but the hero wavelength.
+	pub const fn collapse(mut self) -> Self {
+		self.collapsed = true; self
+	}
+	
+	/// Say the attenuation that goes with these is already one value per wavelength.
+	pub const fn already_spectral(mut self) -> Self {
+		self.spectral = true; self
+	}
+	
+	/// Turn an RGB color (like an albedo) into its values at each wavelength.
+	pub fn sample_rgb(&self, rgb: Color) -> Color {
+		Color::from(self.nm.map(|nm| upsample(rgb, nm)))
+	}
+	
+	/// Turn how much light there is at each wavelength back into an RGB color.
+	/// This is the estimate for a single path, so it's only right on average.
+	pub fn to_rgb(self, spectrum: Color) -> Color {
+		let (lo, hi) = VISIBLE;
+		let samples: [Float; 3] = spectrum.into();
+		
+		let xyz = self.nm.iter().zip(samples)
+			.map(|(&nm, value)| value * color_matching(nm))
+			.fold(Vec3::ZERO, |sum, xyz| sum + xyz)
+			* (hi - lo) / 3.0;
+		
+		xyz_to_rgb(xyz * *WHITE_SCALE)
+	}
+}
+
+/// Turn a CIE XYZ color into linear sRGB.
+pub fn xyz_to_rgb(xyz: Vec3) -> Color {
+	Color::from(XYZ_TO_RGB.map(|row| Vec3::from(row).dot(xyz)))
+}
+
+/// How much to scale each XYZ channel so a flat spectrum (that's 1 at every wavelength)
+/// comes out as exactly white.
+/// 
+/// That's what white upsamples to, but its XYZ color over [VISIBLE] isn't sRGB's white,
+/// which is [D65_WHITE]. So this is the ratio between the two.
+static WHITE_SCALE: LazyLock<Vec3> = LazyLock::new(|| {
+	let (lo, hi) = VISIBLE;
+	let steps = 10_000;
+	let step = (hi - lo) / steps as Float;
+	let flat = (0..steps)
+		.map(|i| color_matching(lo + (i as Float + 0.5) * step))
+		.fold(Vec3::ZERO, |sum, xyz| sum + xyz)
+		* step;
+	
+	Vec3::new(D65_WHITE.x / flat.x, D65_WHITE.y / flat.y, D65_WHITE.z / flat.z)
+});
+
+/// The XYZ color of sRGB's white point, with a brightness of 1.
+const D65_WHITE: Vec3 = Vec3::new(0.95047, 1.0, 1.08883);
+
+/// From CIE XYZ to linear sRGB.
+const XYZ_TO_RGB: [[Float; 3]; 3] = [
+	[ 3.2406, -1.5372, -0.4986],
+	[-0.9689,  1.8758,  0.0415],
+	[ 0.0557, -0.2040,  1.0570],
+];
+
+/// The CIE 1931 color matching functions at a wavelength, using the piecewise
+/// Gaussian fit from "Simple Analytic Approximations to the CIE XYZ Color Matching Functions"
+/// (Wyman, Sloan & Shirley, 2013).
+pub fn color_matching(nm: Float) -> Vec3 {
+	let g = |mu: Float, sigma_lo: Float, sigma_hi: Float| {
+		let sigma = if nm < mu { sigma_lo } else { sigma_hi };
+		Float::exp(-0.5 * ((nm - mu) / sigma).powi(2))
+	};
+	
+	Vec3::new(
+		1.056 * g(599.8, 37.9, 31.0) + 0.362 * g(442.0, 16.0, 26.7) - 0.065 * g(501.1, 20.4, 26.2),
+		0.821 * g(568.8, 46.9, 40.5) + 0.286 * g(530.9, 16.3, 31.1),
+		1.217 * g(437.0, 11.8, 36.0) + 0.681 * g(459.0, 26.0, 13.8)
+	)
+}
+
+/// Spectra for each of the primary and secondary colors, in 10 even bins across
+/// [VISIBLE], from "An RGB-to-Spectrum Conversion for Reflectances" (Smits, 1999).
+mod smits {
+	use crate::vector::Float;
+	
+	pub const WHITE:   [Float; 10] = [1.0000, 1.0000, 0.9999, 0.9993, 0.9992, 0.9998, 1.0000, 1.0000, 1.0000, 1.0000];
+	pub const CYAN:    [Float; 10] = [0.9710, 0.9426, 1.0007, 1.0007, 1.0007, 1.0007, 0.1564, 0.0000, 0.0000, 0.0000];
+	pub const MAGENTA: [Float; 10] = [1.0000, 1.0000, 0.9685, 0.2229, 0.0000, 0.0458, 0.8369, 1.0000, 1.0000, 0.9959];
+	pub const YELLOW:  [Float; 10] = [0.0001, 0.0000, 0.1088, 0.6651, 1.0000, 1.0000, 0.9996, 0.9586, 0.9685, 0.9840];
+	pub const RED:     [Float; 10] = [0.1012, 0.0515, 0.0000, 0.0000, 0.0000, 0.0000, 0.8325, 1.0149, 1.0149, 1.0149];
+	pub const GREEN:   [Float; 10] = [0.0000, 0.0000, 0.0273, 0.7937, 1.0000, 0.9418, 0.1719, 0.0000, 0.0000, 0.0025];
+	pub const BLUE:    [Float; 10] = [1.0000, 1.0000, 0.8916, 0.3323, 0.0000, 0.0000, 0.0003, 0.0369, 0.0483, 0.0496];
+}
+
+/// How much of an RGB color there is at one wavelength, using Smits' method: build
+/// the color out of as much white as it can, then a secondary color, then a primary.
+pub fn upsample(rgb: Color, nm: Float) -> Float {
+	let (lo, hi) = VISIBLE;
+	let bin = (((nm - lo) / (hi - lo) * 10.0) as usize).min(9);
+	let Vec3 { x: r, y: g, z: b } = rgb;
+	
+	// Which channel is smallest decides the secondary color,
+	// and which of the other two is smaller decides the primary.
+	let (white, secondary, primary) = if r <= g && r <= b {
+		if g <= b {
+			(r, (&smits::CYAN, g - r), (&smits::BLUE, b - g))
+		} else {
+			(r, (&smits::CYAN, b - r), (&smits::GREEN, g - b))
+		}
+	} else if g <= r && g <= b {
+		if r <= b {
+			(g, (&smits::MAGENTA, r - g), (&smits::BLUE, b - r))
+		} else {
+			(g, (&smits::MAGENTA, b - g), (&smits::RED, r - b))
+		}
+	} else if r <= g {
+		(b, (&smits::YELLOW, r - b), (&smits::GREEN, g - r))
+	} else {
+		(b, (&smits::YELLOW, g - b), (&smits::RED, r - g))
+	};
+	
+	white * smits::WHITE[bin] + secondary.1 * secondary.0[bin] + primary.1 * primary.0[bin]
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	#[test]
+	fn white_round_trips() {
+		// Step the hero across the whole range, so the average is close to the real integral.
+		let (lo, hi) = VISIBLE;
+		let steps = 3000;
+		let mut sum = Color::ZERO;
+		for i in 0..steps {
+			let hero = (i as Float + 0.5) / steps as Float * (hi - lo);
+			let nm = [0.0, 1.0, 2.0].map(|j| lo + (hero + j * (hi - lo) / 3.0) % (hi - lo));
+			let wavelengths = Wavelengths { nm, collapsed: false, spectral: false };
+			sum += wavelengths.to_rgb(wavelengths.sample_rgb(Color::ONE));
+		}
+		
+		let white = sum / steps as Float;
+		for channel in <[Float; 3]>::from(white) {
+			assert!((channel - 1.0).abs() < 0.005, "{white:?} isn't white");
+		}
+	}
+}
@@ -0,0 +1,114 @@
+use crate::vector::{Point2, Point3, Color, Float};
+use crate::noise::Perlin;
+
+use std::path::Path;
+use std::sync::Arc;
+
+/// Something that changes across a surface, like a color or a roughness.
+/// It gets looked up by the `uv` coordinates of a hit, along with where it is in space.
+/// 
+/// Plain [Color]s and [Float]s are textures too, that are the same everywhere.
+pub trait Texture<T> {
+	fn value(&self, uv: Point2, position: Point3) -> T;
+}
+
+/// A texture that can go in a material and get sent between threads.
+pub type BoxedTexture<T> = Box<dyn Texture<T> + Send + Sync>;
+
+impl Texture<Color> for Color {
+	fn value(&self, _uv: Point2, _position: Point3) -> Color { *self }
+}
+
+impl Texture<Float> for Float {
+	fn value(&self, _uv: Point2, _position: Point3) -> Float { *self }
+}
+
+/// Lets one texture get shared between a bunch of materials.
+impl<T, X: Texture<T> + ?Sized> Texture<T> for Arc<X> {
+	fn value(&self, uv: Point2, position: Point3) -> T {
+		X::value(self, uv, position)
+	}
+}
+
+/// A 3D checkerboard of `even` and `odd`, with each square `size` across.
+/// It doesn't care about `uv`s, so it looks the same on anything.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug)]
+pub struct Checker<T> {
+	pub even: T,
+	pub odd: T,
+	pub size: Float,
+}
+impl<T: Copy> Texture<T> for Checker<T> {
+	fn value(&self, _uv: Point2, position: Point3) -> T {
+		let p = position / self.size;
+		let sum = p.x.floor() as i64 + p.y.floor() as i64 + p.z.floor() as i64;
+		if sum.rem_euclid(2) == 0 { self.even } else { self.odd }
+	}
+}
+
+/// Stretches another texture's values out from [0, 1] to [`min`, `max`].
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug)]
+pub struct Remapped<X> {
+	pub texture: X,
+	pub min: Float,
+	pub max: Float,
+}
+impl<X: Texture<Float>> Texture<Float> for Remapped<X> {
+	fn value(&self, uv: Point2, position: Point3) -> Float {
+		Float::mul_add(self.texture.value(uv, position), self.max - self.min, self.min)
+	}
+}
+
+/// Marble-ish stripes, made by bending a sine wave with [Perlin] turbulence.
+/// Goes from 0 to 1, so it's meant for things like roughness.
+#[derive(Clone, Debug)]
+pub struct Marble {
+	pub noise: Perlin,
+	pub frequency: Float,
+}
+impl Marble {
+	pub fn new(frequency: Float) -> Self {
+		Self { noise: Perlin::new(), frequency }
+	}
+}
+impl Texture<Float> for Marble {
+	fn value(&self, _uv: Point2, position: Point3) -> Float {
+		let p = position * self.frequency;
+		let turbulence = 10.0 * self.noise.fractal(p, 7).abs();
+		0.5 * (1.0 + Float::sin(p.z + turbulence))
+	}
+}
+
+/// An image wrapped onto a surface by its `uv`s, with (0, 0) at the bottom left.
+#[derive(Clone, Debug)]
+pub struct ImageTexture {
+	pub image: image::RgbImage,
+}
+impl ImageTexture {
+	pub fn load(path: impl AsRef<Path>) -> image::ImageResult<Self> {
+		Ok(Self { image: image::open(path)?.into_rgb8() })
+	}
+	
+	/// The color at `uv` exactly as it's stored, with each channel in [0, 1].
+	/// Use this for anything that isn't really a color, like normal maps.
+	pub fn raw(&self, uv: Point2) -> Color {
+		let (w, h) = self.image.dimensions();
+		let x = ((uv.x.clamp(0.0, 1.0) * w as Float) as u32).min(w - 1);
+		let y = (((1.0 - uv.y.clamp(0.0, 1.0)) * h as Float) as u32).min(h - 1);
+		let [r, g, b] = self.image.get_pixel(x, y).0.map(|c| c as Float / 255.0);
+		Color::new(r, g, b)
+	}
+}
+/// Images are stored with gamma, so this squares it back out to match the `sqrt` on output.
+impl Texture<Color> for ImageTexture {
+	fn value(&self, uv: Point2, _position: Point3) -> Color {
+		let raw = self.raw(uv);
+		raw * raw
+	}
+}
+/// For grayscale maps (like roughness) that aren't colors, so they don't have any gamma.
+impl Texture<Float> for ImageTexture {
+	fn value(&self, uv: Point2, _position: Point3) -> Float {
+		self.raw(uv).x
+	}
+}
@@ -31,6 +31,17 @@ pub fn random_unit_vector() -> Vec3 {
 	)
 }
 
+/// Pick a direction evenly out of the cone around `axis` (which should be normalized),
+/// out to where the cosine of the angle from it is `cos_max`.
+pub fn random_in_cone(axis: Vec3, cos_max: Float) -> Vec3 {
+	let cos_theta = 1.0 - random::<Float>() * (1.0 - cos_max);
+	let sin_theta = Float::sqrt(1.0 - cos_theta.powi(2));
+	let phi = random::<Float>() * 2.0 * (std::f64::consts::PI as Float);
+	
+	let (tangent, bitangent) = axis.orthonormal_basis();
+	sin_theta * (phi.cos() * tangent + phi.sin() * bitangent) + cos_theta * axis
+}
+
 pub fn random_in_unit_disk() -> Vec3 {
 	let a = random::<Float>() * 2.0 * (std::f64::consts::PI as Float);
 	let r = random::<Float>() + random::<Float>();
@@ -52,3 +63,185 @@ pub fn schlick(cosine: Float, refractive_index: Float) -> Float {
 	let r0 = ((1.0 - refractive_index) / (1.0 + refractive_index)).powi(2);
 	Float::mul_add(1.0 - r0, (1.0 - cosine).powi(5), r0)
 }
+
+/// Weighs one of two ways of sampling the same thing, by how likely each was to pick it.
+/// This is Veach's power heuristic, which favors whichever one is better at it.
+pub fn power_heuristic(pdf: Float, other_pdf: Float) -> Float {
+	let (a, b) = (pdf.powi(2), other_pdf.powi(2));
+	if a + b == 0.0 { 0.0 } else { a / (a + b) }
+}
+
+/// Solve `a*x^2 + 2*half_b*x + c = 0`, returning the real roots in ascending order.
+/// There aren't any if `a` is 0, since it's not a quadratic then.
+pub fn solve_quadratic(a: Float, half_b: Float, c: Float) -> Option<(Float, Float)> {
+	let discriminant = half_b.powi(2) - a * c;
+	if discriminant < 0.0 || a == 0.0 { return None; }
+	
+	// Avoids catastrophic cancellation when `half_b` and the root are close.
+	let q = -(half_b + Float::copysign(discriminant.sqrt(), half_b));
+	let (t0, t1) = if q == 0.0 { (0.0, 0.0) } else { (q / a, c / q) };
+	
+	Some((t0.min(t1), t0.max(t1)))
+}
+
+/// Find the largest real root of the monic cubic `x^3 + a*x^2 + b*x + c = 0`.
+pub fn largest_cubic_root(a: Float, b: Float, c: Float) -> Float {
+	let q = (a.powi(2) - 3.0 * b) / 9.0;
+	let r = (2.0 * a.powi(3) - 9.0 * a * b + 27.0 * c) / 54.0;
+	
+	if q > 0.0 && r.powi(2) <= q.powi(3) {
+		// Three real roots (maybe with some repeated), the largest of which is at k = 1.
+		let theta = Float::acos(Float::clamp(r / q.powi(3).sqrt(), -1.0, 1.0));
+		let tau = 2.0 * std::f64::consts::PI as Float;
+		-2.0 * q.sqrt() * Float::cos((theta + tau) / 3.0) - a / 3.0
+	} else {
+		let big_a = -Float::copysign(Float::cbrt(r.abs() + (r.powi(2) - q.powi(3)).sqrt()), r);
+		let big_b = if big_a != 0.0 { q / big_a } else { 0.0 };
+		big_a + big_b - a / 3.0
+	}
+}
+
+/// Solve `c[0]*x^4 + c[1]*x^3 + c[2]*x^2 + c[3]*x + c[4] = 0` with Ferrari's method.
+/// 
+/// Returns the real roots in ascending order, along with how many of them there are.
+/// Repeated roots show up once for each time they're repeated, and there aren't any if `c[0]` is 0.
+/// Each root gets polished with a few Newton steps, since Ferrari on its own loses a
+/// lot of precision for the kinds of quartics a torus produces.
+pub fn solve_quartic(c: [Float; 5]) -> ([Float; 4], usize) {
+	let mut roots = [0.0; 4];
+	let mut count = 0;
+	
+	if c[0] == 0.0 { return (roots, count); }
+	let (a, b, cc, d) = (c[1] / c[0], c[2] / c[0], c[3] / c[0], c[4] / c[0]);
+	
+	// Substitute x = y - a/4 to get the depressed quartic y^4 + p*y^2 + q*y + r.
+	let a2 = a.powi(2);
+	let p = b - 3.0 * a2 / 8.0;
+	let q = cc - a * b / 2.0 + a2 * a / 8.0;
+	let r = d - a * cc / 4.0 + a2 * b / 16.0 - 3.0 * a2.powi(2) / 256.0;
+	
+	let mut push = |y: Float| {
+		roots[count] = y - a / 4.0;
+		count += 1;
+	};
+	
+	if q.abs() < 1e-12 {
+		// Biquadratic, so solve for y^2 instead.
+		if let Some((z0, z1)) = solve_quadratic(1.0, p / 2.0, r) {
+			for z in [z0, z1] {
+				if z >= 0.0 {
+					push(-z.sqrt());
+					push(z.sqrt());
+				}
+			}
+		}
+	} else {
+		// Resolvent cubic m^3 + p*m^2 + (p^2/4 - r)*m - q^2/8 = 0 always has a positive root here.
+		let m = largest_cubic_root(p, p.powi(2) / 4.0 - r, -q.powi(2) / 8.0);
+		if m <= 0.0 { return (roots, count); }
+		
+		let s = Float::sqrt(2.0 * m);
+		for (sign, offset) in [(-1.0, q / (2.0 * s)), (1.0, -q / (2.0 * s))] {
+			if let Some((y0, y1)) = solve_quadratic(1.0, sign * s / 2.0, p / 2.0 + m + offset) {
+				push(y0);
+				push(y1);
+			}
+		}
+	}
+	
+	// Polish the roots against the original polynomial. Near repeated roots the slope is
+	// about 0, so a step can shoot way off, and only ones that get closer are kept.
+	let f = |x: Float| (((c[0] * x + c[1]) * x + c[2]) * x + c[3]) * x + c[4];
+	let df = |x: Float| ((4.0 * c[0] * x + 3.0 * c[1]) * x + 2.0 * c[2]) * x + c[3];
+	for x in &mut roots[..count] {
+		for _ in 0..2 {
+			if df(*x) == 0.0 { break; }
+			let polished = *x - f(*x) / df(*x);
+			if f(polished).abs() >= f(*x).abs() { break; }
+			*x = polished;
+		}
+	}
+	
+	roots[..count].sort_by(Float::total_cmp);
+	(roots, count)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	fn assert_roots(found: &[Float], expected: &[Float]) {
+		assert_eq!(found.len(), expected.len(), "found {found:?}, expected {expected:?}");
+		for (found, expected) in found.iter().zip(expected) {
+			assert!((found - expected).abs() < 1e-6, "found {found:?}, expected {expected:?}");
+		}
+	}
+	
+	#[test]
+	fn quadratic_roots() {
+		// (x - 1)(x - 3) = x^2 - 4x + 3
+		let (t0, t1) = solve_quadratic(1.0, -2.0, 3.0).unwrap();
+		assert_roots(&[t0, t1], &[1.0, 3.0]);
+		// Scaled, and upside down: -2(x + 2)(x - 1)
+		let (t0, t1) = solve_quadratic(-2.0, -1.0, 4.0).unwrap();
+		assert_roots(&[t0, t1], &[-2.0, 1.0]);
+	}
+	
+	#[test]
+	fn quadratic_repeated_and_missing_roots() {
+		// (x - 1)^2
+		let (t0, t1) = solve_quadratic(1.0, -1.0, 1.0).unwrap();
+		assert_roots(&[t0, t1], &[1.0, 1.0]);
+		
+		assert_eq!(solve_quadratic(1.0, 0.0, 1.0), None);
+		assert_eq!(solve_quadratic(0.0, 1.0, 1.0), None);
+	}
+	
+	#[test]
+	fn cubic_largest_root() {
+		// (x - 1)(x - 2)(x - 3)
+		assert_roots(&[largest_cubic_root(-6.0, 11.0, -6.0)], &[3.0]);
+		// (x - 2)^2 (x + 1)
+		assert_roots(&[largest_cubic_root(-3.0, 0.0, 4.0)], &[2.0]);
+		// (x + 1)(x^2 + 1), with only one real root.
+		assert_roots(&[largest_cubic_root(1.0, 1.0, 1.0)], &[-1.0]);
+		// x^3
+		assert_roots(&[largest_cubic_root(0.0, 0.0, 0.0)], &[0.0]);
+	}
+	
+	#[test]
+	fn quartic_roots() {
+		// (x + 2)(x - 1)(x - 3)(x - 4) = x^4 - 6x^3 + 3x^2 + 26x - 24
+		let (roots, count) = solve_quartic([1.0, -6.0, 3.0, 26.0, -24.0]);
+		assert_roots(&roots[..count], &[-2.0, 1.0, 3.0, 4.0]);
+		
+		// Scaling it shouldn't matter.
+		let (roots, count) = solve_quartic([-3.0, 18.0, -9.0, -78.0, 72.0]);
+		assert_roots(&roots[..count], &[-2.0, 1.0, 3.0, 4.0]);
+		
+		// (x^2 - 1)(x^2 - 4), which is biquadratic.
+		let (roots, count) = solve_quartic([1.0, 0.0, -5.0, 0.0, 4.0]);
+		assert_roots(&roots[..count], &[-2.0, -1.0, 1.0, 2.0]);
+		
+		// (x - 1)(x - 2)(x^2 + 1), with only two real roots.
+		let (roots, count) = solve_quartic([1.0, -3.0, 3.0, -3.0, 2.0]);
+		assert_roots(&roots[..count], &[1.0, 2.0]);
+		
+		// x^4 + 1, with none.
+		assert_eq!(solve_quartic([1.0, 0.0, 0.0, 0.0, 1.0]).1, 0);
+	}
+	
+	#[test]
+	fn quartic_repeated_and_missing_roots() {
+		// (x - 1)^2 (x - 3)(x + 2) = x^4 - 3x^3 - 3x^2 + 11x - 6
+		let (roots, count) = solve_quartic([1.0, -3.0, -3.0, 11.0, -6.0]);
+		assert_roots(&roots[..count], &[-2.0, 1.0, 1.0, 3.0]);
+		
+		// (x - 1)^2 (x + 2)^2 = x^4 + 2x^3 - 3x^2 - 4x + 4
+		let (roots, count) = solve_quartic([1.0, 2.0, -3.0, -4.0, 4.0]);
+		assert_roots(&roots[..count], &[-2.0, -2.0, 1.0, 1.0]);
+		
+		// A cubic isn't a quartic.
+		assert_eq!(solve_quartic([0.0, 1.0, -6.0, 11.0, -6.0]).1, 0);
+	}
+}
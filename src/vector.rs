@@ -187,6 +187,21 @@ macro_rules! vector_def {
 			pub fn lerp(self, other: Self, t: Float) -> Self {
 				self * (1.0 - t) + other * t
 			}
+			
+			/// Get the smaller of each pair of components.
+			pub fn min(self, other: Self) -> Self {
+				Self { $($component: self.$component.min(other.$component),)+ }
+			}
+			
+			/// Raise e to the power of each component.
+			pub fn exp(self) -> Self {
+				Self { $($component: self.$component.exp(),)+ }
+			}
+			
+			/// Get the larger of each pair of components.
+			pub fn max(self, other: Self) -> Self {
+				Self { $($component: self.$component.max(other.$component),)+ }
+			}
 		}
 		
 		// Conversion to/from Tuples
@@ -233,6 +248,19 @@ impl Vec3 {
 			z: Float::mul_add(self.x, other.y, -self.y * other.x),
 		}
 	}
+	
+	/// Get two unit vectors that, along with the unit vector `self`, make up
+	/// an orthonormal basis. Handy for building a local frame around an axis.
+	// From "Building an Orthonormal Basis, Revisited" (Duff et al. 2017)
+	pub fn orthonormal_basis(self) -> (Self, Self) {
+		let sign = Float::copysign(1.0, self.z);
+		let a = -1.0 / (sign + self.z);
+		let b = self.x * self.y * a;
+		(
+			Self::new(1.0 + sign * self.x.powi(2) * a, sign * b, -sign * self.x),
+			Self::new(b, sign + self.y.powi(2) * a, -self.y),
+		)
+	}
 }
 
 impl From<Color> for [u8; 3] {
@@ -249,6 +277,11 @@ impl From<Color> for [u8; 3] {
 
 pub type Color = Vec3;
 impl Color {
+	/// How bright the color looks, in linear sRGB.
+	pub fn luminance(self) -> Float {
+		0.2126 * self.x + 0.7152 * self.y + 0.0722 * self.z
+	}
+	
 	pub fn gamma_accurate_average(self, samples: usize) -> Self {
 		let incorrect_avg = self / (samples as Float);
 		Self { x: incorrect_avg.x.sqrt(), y: incorrect_avg.y.sqrt(), z: incorrect_avg.z.sqrt() }
@@ -257,3 +290,259 @@ impl Color {
 
 pub type Point2 = Vec2;
 pub type Point3 = Vec3;
+
+/// A 4x4 matrix, stored as rows.
+/// 
+/// Used for affine transforms, so the bottom row should usually be `[0, 0, 0, 1]`.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug)]
+pub struct Mat4(pub [[Float; 4]; 4]);
+
+impl ops::Mul for Mat4 {
+	type Output = Self;
+	fn mul(self, other: Self) -> Self::Output {
+		let mut m = [[0.0; 4]; 4];
+		for (i, row) in m.iter_mut().enumerate() {
+			for (j, cell) in row.iter_mut().enumerate() {
+				*cell = (0..4).fold(0.0, |r, k| Float::mul_add(self.0[i][k], other.0[k][j], r));
+			}
+		}
+		Self(m)
+	}
+}
+
+impl Mat4 {
+	pub const IDENTITY: Self = Self([
+		[1.0, 0.0, 0.0, 0.0],
+		[0.0, 1.0, 0.0, 0.0],
+		[0.0, 0.0, 1.0, 0.0],
+		[0.0, 0.0, 0.0, 1.0],
+	]);
+	
+	pub fn transpose(self) -> Self {
+		let mut m = [[0.0; 4]; 4];
+		for (i, row) in m.iter_mut().enumerate() {
+			for (j, cell) in row.iter_mut().enumerate() {
+				*cell = self.0[j][i];
+			}
+		}
+		Self(m)
+	}
+	
+	/// Invert the matrix with Gauss-Jordan elimination.
+	/// 
+	/// Returns `None` if the matrix is singular (like if you scaled something by 0).
+	pub fn inverse(self) -> Option<Self> {
+		let mut a = self.0;
+		let mut inv = Self::IDENTITY.0;
+		
+		for col in 0..4 {
+			// Partial pivoting, to keep things stable.
+			let pivot = (col..4).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+			if a[pivot][col].abs() < 1e-12 { return None; }
+			a.swap(col, pivot);
+			inv.swap(col, pivot);
+			
+			let scale = a[col][col].recip();
+			for k in 0..4 {
+				a[col][k] *= scale;
+				inv[col][k] *= scale;
+			}
+			
+			for row in 0..4 {
+				if row == col { continue; }
+				let factor = a[row][col];
+				for k in 0..4 {
+					a[row][k] -= factor * a[col][k];
+					inv[row][k] -= factor * inv[col][k];
+				}
+			}
+		}
+		
+		Some(Self(inv))
+	}
+	
+	/// Transform a point, which gets affected by translation.
+	pub fn transform_point(self, p: Point3) -> Point3 {
+		let m = self.0;
+		Point3::new(
+			m[0][0] * p.x + m[0][1] * p.y + m[0][2] * p.z + m[0][3],
+			m[1][0] * p.x + m[1][1] * p.y + m[1][2] * p.z + m[1][3],
+			m[2][0] * p.x + m[2][1] * p.y + m[2][2] * p.z + m[2][3],
+		)
+	}
+	
+	/// Transform a direction, which doesn't get affected by translation.
+	pub fn transform_vector(self, v: Vec3) -> Vec3 {
+		let m = self.0;
+		Vec3::new(
+			m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+			m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+			m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+		)
+	}
+}
+
+/// An affine transform, which keeps its inverse around since you
+/// almost always need both.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug)]
+pub struct Transform {
+	pub matrix: Mat4,
+	pub inverse: Mat4,
+}
+impl Default for Transform {
+	fn default() -> Self { Self::IDENTITY }
+}
+impl Transform {
+	pub const IDENTITY: Self = Self { matrix: Mat4::IDENTITY, inverse: Mat4::IDENTITY };
+	
+	/// Make a transform out of any invertible matrix.
+	pub fn new(matrix: Mat4) -> Option<Self> {
+		Some(Self { matrix, inverse: matrix.inverse()? })
+	}
+	
+	pub fn translate(v: Vec3) -> Self {
+		let mut matrix = Mat4::IDENTITY;
+		let mut inverse = Mat4::IDENTITY;
+		for (i, c) in <[Float; 3]>::from(v).into_iter().enumerate() {
+			matrix.0[i][3] = c;
+			inverse.0[i][3] = -c;
+		}
+		Self { matrix, inverse }
+	}
+	
+	/// Scale along each axis. None of the components should be 0.
+	pub fn scale(v: Vec3) -> Self {
+		let mut matrix = Mat4::IDENTITY;
+		let mut inverse = Mat4::IDENTITY;
+		for (i, c) in <[Float; 3]>::from(v).into_iter().enumerate() {
+			matrix.0[i][i] = c;
+			inverse.0[i][i] = c.recip();
+		}
+		Self { matrix, inverse }
+	}
+	
+	/// Rotate around `axis` by `degrees`, counterclockwise when looking down the axis.
+	pub fn rotate(axis: Vec3, degrees: Float) -> Self {
+		let Vec3 { x, y, z } = axis.normalize();
+		let (s, c) = degrees.to_radians().sin_cos();
+		let t = 1.0 - c;
+		
+		let matrix = Mat4([
+			[t * x * x + c,     t * x * y - s * z, t * x * z + s * y, 0.0],
+			[t * x * y + s * z, t * y * y + c,     t * y * z - s * x, 0.0],
+			[t * x * z - s * y, t * y * z + s * x, t * z * z + c,     0.0],
+			[0.0, 0.0, 0.0, 1.0],
+		]);
+		// Rotation matrices are orthogonal, so this one's free.
+		Self { matrix, inverse: matrix.transpose() }
+	}
+	
+	/// Do `self`, and then `other` after it.
+	pub fn then(self, other: Self) -> Self {
+		Self {
+			matrix: other.matrix * self.matrix,
+			inverse: self.inverse * other.inverse,
+		}
+	}
+	
+	pub fn inverted(self) -> Self {
+		Self { matrix: self.inverse, inverse: self.matrix }
+	}
+	
+	pub fn point(&self, p: Point3) -> Point3 { self.matrix.transform_point(p) }
+	pub fn vector(&self, v: Vec3) -> Vec3 { self.matrix.transform_vector(v) }
+	
+	/// Transform a surface normal. These need the inverse transpose, or else
+	/// they stop being perpendicular to the surface under non-uniform scaling.
+	pub fn normal(&self, n: Vec3) -> Vec3 {
+		self.inverse.transpose().transform_vector(n).normalize()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	fn assert_near_matrix(a: Mat4, b: Mat4) {
+		for (row_a, row_b) in a.0.iter().zip(b.0) {
+			for (x, y) in row_a.iter().zip(row_b) {
+				assert!((x - y).abs() < 1e-9, "{a:?} isn't {b:?}");
+			}
+		}
+	}
+	
+	fn assert_near(a: Vec3, b: Vec3) {
+		assert!((a - b).magnitude() < 1e-9, "{a:?} isn't {b:?}");
+	}
+	
+	/// Some rotating, shearing, non-uniform scaling, and moving.
+	fn messy_transform() -> Transform {
+		let shear = Transform::new(Mat4([
+			[1.0, 0.5, 0.0, 0.0],
+			[0.0, 1.0, 0.0, 0.0],
+			[0.3, 0.0, 1.0, 0.0],
+			[0.0, 0.0, 0.0, 1.0],
+		])).unwrap();
+		
+		Transform::rotate(Vec3::new(1.0, 2.0, -0.5), 37.0)
+			.then(Transform::scale(Vec3::new(2.0, 0.5, -3.0)))
+			.then(shear)
+			.then(Transform::translate(Vec3::new(-4.0, 1.5, 7.0)))
+	}
+	
+	#[test]
+	fn inverse_undoes_matrix() {
+		let m = messy_transform().matrix;
+		let inverse = m.inverse().unwrap();
+		assert_near_matrix(m * inverse, Mat4::IDENTITY);
+		assert_near_matrix(inverse * m, Mat4::IDENTITY);
+	}
+	
+	#[test]
+	fn inverse_needs_pivoting() {
+		// A 0 where the first pivot would be, which only works by swapping rows.
+		let m = Mat4([
+			[0.0, 2.0, 0.0, 1.0],
+			[3.0, 0.0, 0.0, 0.0],
+			[0.0, 0.0, 0.0, 4.0],
+			[0.0, 0.0, 5.0, 0.0],
+		]);
+		assert_near_matrix(m * m.inverse().unwrap(), Mat4::IDENTITY);
+	}
+	
+	#[test]
+	fn singular_has_no_inverse() {
+		// The second row is just twice the first.
+		let mut m = Mat4::IDENTITY;
+		m.0[1] = [2.0, 0.0, 0.0, 0.0];
+		assert_eq!(m.inverse(), None);
+		assert_eq!(Transform::new(Mat4([[0.0; 4]; 4])), None);
+	}
+	
+	#[test]
+	fn transform_inverse_matches_matrix_inverse() {
+		let transform = messy_transform();
+		assert_near_matrix(transform.inverse, transform.matrix.inverse().unwrap());
+	}
+	
+	#[test]
+	fn transform_round_trip() {
+		let transform = messy_transform();
+		let p = Point3::new(0.3, -2.0, 5.0);
+		let v = Vec3::new(-1.0, 0.25, 2.0);
+		
+		assert_near(transform.inverted().point(transform.point(p)), p);
+		assert_near(transform.inverted().vector(transform.vector(v)), v);
+		assert_near(transform.then(transform.inverted()).point(p), p);
+	}
+	
+	#[test]
+	fn normals_stay_perpendicular() {
+		let transform = messy_transform();
+		let normal = Vec3::new(1.0, 1.0, 0.0).normalize();
+		// Both of these lie in the surface.
+		for tangent in [Vec3::new(1.0, -1.0, 0.0), Vec3::Z] {
+			assert!(transform.normal(normal).dot(transform.vector(tangent)).abs() < 1e-9);
+		}
+	}
+}
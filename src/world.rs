@@ -1,7 +1,15 @@
-use crate::vector::{Color, Float};
+use crate::vector::{Vec3, Point3, Color, Float};
 use crate::ray::{Ray, HitInfo};
 use crate::solid::Hittable;
 use crate::material::Material;
+use crate::bvh::Bvh;
+use crate::bounding_box::BoundingBox;
+use crate::medium::Fog;
+use crate::environment::{Environment, Gradient};
+use crate::light::Light;
+use crate::light::tree::{LightTree, LightBounds, Emitter};
+use crate::texture::{Texture, BoxedTexture};
+use crate::util;
 
 use std::ops::Range;
 
@@ -9,41 +17,322 @@ use std::ops::Range;
 pub struct Object {
 	pub solid: Box<dyn Hittable + Send + Sync>,
 	pub material: Box<dyn Material + Send + Sync>,
+	/// Cuts holes in the surface, for things like leaves and fences.
+	pub mask: Option<AlphaMask>,
 }
 impl Object {
 	pub fn new(solid: Box<dyn Hittable + Send + Sync>, material: Box<dyn Material + Send + Sync>) -> Self {
-		Self { solid, material }
+		Self { solid, material, mask: None }
+	}
+	
+	pub fn with_mask(mut self, mask: AlphaMask) -> Self {
+		self.mask = Some(mask); self
+	}
+	
+	/// Find the closest spot the [Ray] hits, going right through any holes in the mask.
+	pub fn hit(&self, mut t_range: Range<Float>, ray: Ray) -> Option<HitInfo> {
+		loop {
+			let hit = self.solid.ray_hits(t_range.clone(), ray)?;
+			match &self.mask {
+				// Look again, starting just past this hit.
+				Some(mask) if !mask.keeps(&hit) => t_range.start = hit.t.next_up(),
+				_ => return Some(hit),
+			}
+		}
+	}
+	
+	/// How much light makes it through the object along the [Ray] within `t_range`.
+	/// See [Hittable::transmittance].
+	pub fn transmittance(&self, mut t_range: Range<Float>, ray: Ray) -> Float {
+		let Some(mask) = &self.mask else { return self.solid.transmittance(t_range, ray) };
+		
+		// Rather than rolling the dice for each hit, take the average of how much gets through.
+		let mut transmittance = 1.0;
+		while let Some(hit) = self.solid.ray_hits(t_range.clone(), ray) {
+			transmittance *= 1.0 - mask.coverage(&hit);
+			if transmittance == 0.0 { break; }
+			t_range.start = hit.t.next_up();
+		}
+		transmittance
+	}
+}
+
+/// Makes parts of an [Object] see-through, by an `alpha` texture that goes
+/// from 0 (not there at all) to 1 (completely solid).
+pub struct AlphaMask {
+	pub alpha: BoxedTexture<Float>,
+	/// Anything with less alpha than this is cut out, and everything else is solid.
+	/// Without one, each hit is solid with a chance of its alpha, which looks softer but noisier.
+	pub cutoff: Option<Float>,
+}
+impl AlphaMask {
+	pub fn cutoff(alpha: impl Texture<Float> + Send + Sync + 'static, cutoff: Float) -> Self {
+		Self { alpha: Box::new(alpha), cutoff: Some(cutoff) }
+	}
+	
+	pub fn stochastic(alpha: impl Texture<Float> + Send + Sync + 'static) -> Self {
+		Self { alpha: Box::new(alpha), cutoff: None }
+	}
+	
+	/// How much of the surface is there at a hit, from 0 to 1.
+	fn coverage(&self, hit: &HitInfo) -> Float {
+		let alpha = self.alpha.value(hit.uv, hit.position);
+		match self.cutoff {
+			Some(cutoff) => if alpha < cutoff { 0.0 } else { 1.0 },
+			None => alpha.clamp(0.0, 1.0),
+		}
+	}
+	
+	/// Whether a hit should count, or if the ray should go through.
+	fn keeps(&self, hit: &HitInfo) -> bool {
+		util::random_float() < self.coverage(hit)
 	}
 }
 
-/// A World has a bunch of [Object]s.
+/// A World has a bunch of [Object]s, and maybe some [Light]s.
+/// 
+/// They only get added through [World::push] and [World::push_light], which throw out
+/// the trees over them, so nothing added afterwards gets left out.
 pub struct World {
-	pub objects: Vec<Object>,
-	pub sky_color: (Color, Color),
+	objects: Vec<Object>,
+	lights: Vec<Box<dyn Light + Send + Sync>>,
+	/// What rays that don't hit anything see.
+	pub environment: Box<dyn Environment + Send + Sync>,
+	/// Fog filling up the space between objects, if there is any.
+	pub fog: Option<Fog>,
+	/// A tree of the `objects`, set up by [World::build_bvh].
+	/// If it's `None`, every object gets checked against every ray.
+	pub bvh: Option<Bvh>,
+	/// A tree of the `lights` and glowing `objects`, set up by [World::build_light_tree].
+	/// If it's `None`, only the `lights` get looked at, each as likely as any other.
+	pub light_tree: Option<LightTree>,
 }
 impl World {
+	pub fn objects(&self) -> &[Object] {
+		&self.objects
+	}
+	
+	pub fn lights(&self) -> &[Box<dyn Light + Send + Sync>] {
+		&self.lights
+	}
+	
+	/// Add an [Object]. Any [Bvh] or [LightTree] has to be built again afterwards.
+	pub fn push(&mut self, object: Object) {
+		self.objects.push(object);
+		self.bvh = None;
+		self.light_tree = None;
+	}
+	
+	/// Add a [Light]. Any [LightTree] has to be built again afterwards.
+	pub fn push_light(&mut self, light: Box<dyn Light + Send + Sync>) {
+		self.lights.push(light);
+		self.light_tree = None;
+	}
+	
 	/// Shoot a [Ray] out and have it react to [Object]s in the [World].
 	/// 
-	/// Returns which Object it hit, along with some [HitInfo].
-	pub fn hit(&self, ray: Ray, t_range: Range<Float>) -> Option<(&Object, HitInfo)> {
-		let mut tmp_hit: Option<(&Object, HitInfo)> = None;
+	/// Returns which Object it hit (and where it is in `objects`), along with some [HitInfo].
+	pub fn hit(&self, ray: Ray, t_range: Range<Float>) -> Option<(usize, &Object, HitInfo)> {
+		if let Some(bvh) = &self.bvh {
+			return bvh.ray_hits(t_range, ray, |i, t_range| {
+				let object = &self.objects[i];
+				object.hit(t_range, ray).map(|hit| ((i, object, hit), hit.t))
+			});
+		}
+		
+		let mut tmp_hit: Option<(usize, &Object, HitInfo)> = None;
 		let mut closest = t_range.end;
 		
-		for object in &self.objects {
-			if let Some(hit) = object.solid.ray_hits(t_range.start..closest, ray) {
+		for (i, object) in self.objects.iter().enumerate() {
+			if let Some(hit) = object.hit(t_range.start..closest, ray) {
 				closest = hit.t;
-				tmp_hit = Some((object, hit));
+				tmp_hit = Some((i, object, hit));
 			}
 		}
 		
 		tmp_hit
 	}
+	
+	/// How much light makes it along the [Ray] within `t_range`, from 0 (none of it) to 1 (all of it).
+	/// Unlike [World::hit], this goes through everything, since volumes only block some of it.
+	pub fn transmittance(&self, ray: Ray, t_range: Range<Float>) -> Float {
+		let mut transmittance = self.fog.map_or(1.0, |fog| fog.transmittance(ray, t_range.end));
+		
+		if let Some(bvh) = &self.bvh {
+			// Only ever report a hit once everything's blocked, so that
+			// it keeps going through every object along the way until then.
+			let start = t_range.start;
+			bvh.ray_hits(t_range, ray, |i, t_range| {
+				transmittance *= self.objects[i].transmittance(t_range, ray);
+				(transmittance == 0.0).then_some(((), start))
+			});
+			return transmittance;
+		}
+		
+		for object in &self.objects {
+			transmittance *= object.transmittance(t_range.clone(), ray);
+			if transmittance == 0.0 { break; }
+		}
+		transmittance
+	}
+	
+	/// A box around everything in the world, leaving out anything that's infinitely big.
+	pub fn bounds(&self) -> BoundingBox {
+		if let Some(bounds) = self.bvh.as_ref().and_then(Bvh::bounds) { return bounds; }
+		self.objects.iter()
+			.filter_map(|object| object.solid.bounding_box())
+			.fold(BoundingBox::EMPTY, BoundingBox::union)
+	}
+	
+	/// Sort the objects into a [Bvh], so rays can skip most of them.
+	/// 
+	/// Adding more objects afterwards throws it out, so call this again after that.
+	pub fn build_bvh(&mut self) {
+		let boxes: Vec<_> = self.objects.iter().map(|o| o.solid.bounding_box()).collect();
+		self.bvh = Some(Bvh::build(&boxes));
+	}
+	
+	/// Sort the lights and glowing objects into a [LightTree], so the ones
+	/// that matter most for each spot get looked at the most.
+	/// 
+	/// Adding more lights or objects afterwards throws it out, so call this again after that.
+	pub fn build_light_tree(&mut self) {
+		let mut emitters = Vec::new();
+		let mut infinite = Vec::new();
+		
+		for (i, light) in self.lights.iter().enumerate() {
+			match light.bounds() {
+				Some(bounds) => emitters.push((Emitter::Light(i), bounds)),
+				None => infinite.push(i),
+			}
+		}
+		
+		for (i, object) in self.objects.iter().enumerate() {
+			let glow = object.material.max_emitted().luminance();
+			// There's no point in picking something there's no way to look for light from.
+			if glow <= 0.0 || !object.solid.is_sampleable() { continue; }
+			let Some(bounds) = object.solid.bounding_box() else { continue };
+			
+			// The box's area is close enough to the surface's for guessing how much light there is.
+			// It could be facing any which way, but only out of the front of the surface.
+			emitters.push((Emitter::Object(i), LightBounds {
+				bounds,
+				power: std::f64::consts::PI as Float * bounds.surface_area() * glow,
+				axis: Vec3::Y,
+				cos_theta_o: -1.0,
+				cos_theta_e: 0.0,
+			}));
+		}
+		
+		self.light_tree = Some(LightTree::build(emitters, infinite));
+	}
+	
+	/// Pick a light (or glowing object) to look for light from at `point`,
+	/// along with the chance of having picked it.
+	pub fn pick_light(&self, point: Point3) -> Option<(Emitter, Float)> {
+		if let Some(tree) = &self.light_tree {
+			return tree.sample(point);
+		}
+		
+		if self.lights.is_empty() { return None; }
+		let count = self.lights.len();
+		let i = ((util::random_float() * count as Float) as usize).min(count - 1);
+		Some((Emitter::Light(i), (count as Float).recip()))
+	}
+	
+	/// The pdf of looking for light from `point` and going in `direction`,
+	/// towards the glowing object at `index`, per unit solid angle.
+	pub fn light_pdf(&self, point: Point3, index: usize, direction: Vec3) -> Float {
+		let Some(tree) = &self.light_tree else { return 0.0 };
+		let pmf = tree.pmf(point, Emitter::Object(index));
+		if pmf == 0.0 { return 0.0; }
+		pmf * self.objects[index].solid.direction_pdf(point, direction)
+	}
 }
 impl Default for World {
 	fn default() -> Self {
 		World {
-			sky_color: (Color::new(0.5, 0.7, 1.0), Color::ONE),
+			environment: Box::new(Gradient { bottom: Color::new(0.5, 0.7, 1.0), top: Color::ONE }),
 			objects: Vec::new(),
+			lights: Vec::new(),
+			fog: None,
+			bvh: None,
+			light_tree: None,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::vector::{Vec3, Point3};
+	use crate::solid::sphere::Sphere;
+	use crate::solid::transformed::Transformed;
+	use crate::material::{Lambertian, DiffuseLight};
+	use crate::vector::Transform;
+	use crate::texture::Checker;
+	
+	use std::sync::Arc;
+	
+	fn ball(x: Float) -> Object {
+		Object::new(Box::new(Sphere::new(Point3::new(x, 0.0, 0.0), 0.5)), Box::new(Lambertian { albedo: Color::ONE }))
+	}
+	
+	#[test]
+	fn objects_added_after_the_bvh_get_hit() {
+		let mut world = World::default();
+		world.push(ball(5.0));
+		world.build_bvh();
+		world.push(ball(2.0));
+		
+		let ray = Ray::new(Point3::ZERO, Vec3::X, 0.0);
+		let (index, _, hit) = world.hit(ray, 0.001..Float::INFINITY).unwrap();
+		assert_eq!(index, 1);
+		assert!((hit.t - 1.5).abs() < 1e-9);
+		
+		world.build_bvh();
+		assert_eq!(world.hit(ray, 0.001..Float::INFINITY).unwrap().0, 1);
+	}
+	
+	#[test]
+	fn stochastic_masks_let_through_one_minus_alpha_on_average() {
+		let object = ball(0.0).with_mask(AlphaMask::stochastic(0.3));
+		// From the middle, there's only the one surface to go through.
+		let ray = Ray::new(Point3::ZERO, Vec3::X, 0.0);
+		assert!((object.transmittance(0.001..Float::INFINITY, ray) - 0.7).abs() < 1e-9);
+		
+		let n = 100_000;
+		let missed = (0..n).filter(|_| object.hit(0.001..Float::INFINITY, ray).is_none()).count();
+		assert!((missed as Float / n as Float - 0.7).abs() < 0.01, "{missed} of {n} got through");
+	}
+	
+	#[test]
+	fn cutoff_masks_skip_hits_below_the_cutoff() {
+		// The near side of the ball lands on an odd square, and the far side on an even one.
+		let alpha = Checker { even: 0.9, odd: 0.2, size: 1.0 };
+		let object = ball(0.0).with_mask(AlphaMask::cutoff(alpha, 0.5));
+		let ray = Ray::new(Point3::new(-2.0, 0.0, 0.0), Vec3::X, 0.0);
+		for _ in 0..100 {
+			let hit = object.hit(0.001..Float::INFINITY, ray).unwrap();
+			assert!((hit.t - 2.5).abs() < 1e-9);
+		}
+		assert_eq!(object.transmittance(0.001..Float::INFINITY, ray), 0.0);
+		assert_eq!(object.transmittance(0.001..2.0, ray), 1.0);
+	}
+	
+	#[test]
+	fn light_tree_leaves_out_glowing_things_it_cant_sample() {
+		let glow = || Box::new(DiffuseLight { emit: Color::ONE });
+		let sphere = Arc::new(Sphere::new(Point3::ZERO, 0.5));
+		
+		let mut world = World::default();
+		world.push(Object::new(Box::new(Transformed::new(sphere.clone(), Transform::translate(Vec3::X))), glow()));
+		world.push(Object::new(Box::new(sphere), glow()));
+		world.build_light_tree();
+		
+		for _ in 0..100 {
+			let (emitter, pmf) = world.pick_light(Point3::new(0.0, 2.0, 0.0)).unwrap();
+			assert_eq!((emitter, pmf), (Emitter::Object(1), 1.0));
 		}
 	}
 }